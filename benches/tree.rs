@@ -0,0 +1,255 @@
+//! Benchmarks of the core tree operations, alongside the std collections they are most often
+//! used in place of. Run with `cargo bench`, adding `--features rayon` to compare building a tree
+//! in parallel with building it on one thread.
+
+use binary_search_tree::{NodeKey, Tree};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use std::collections::BTreeMap;
+use std::hint::black_box;
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+// A small xorshift generator, so that every run inserts in the same order
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
+
+// Builds a tree of the given size by inserting each value next to a random existing node,
+// returning the tree and the keys of its nodes in insertion order
+fn random_tree(size: usize) -> (Tree<usize>, Vec<NodeKey>) {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let mut tree = Tree::with_capacity(size);
+    let mut nodes = vec![tree.create_root(0)];
+    for value in 1..size {
+        let existing = nodes[rng.below(nodes.len())];
+        let node = if value % 2 == 0 {
+            tree.insert_after(existing, value)
+        } else {
+            tree.insert_before(existing, value)
+        };
+        nodes.push(node);
+    }
+    (tree, nodes)
+}
+
+fn sequential_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sequential_insert");
+    for &size in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::new("tree", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut tree = Tree::new();
+                let mut last = tree.create_root(0);
+                for value in 1..size {
+                    last = tree.insert_after(last, value);
+                }
+                tree
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("btree_map", size), &size, |b, &size| {
+            b.iter(|| {
+                (0..size)
+                    .map(|value| (value, value))
+                    .collect::<BTreeMap<_, _>>()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("vec", size), &size, |b, &size| {
+            b.iter(|| (0..size).collect::<Vec<_>>())
+        });
+    }
+    group.finish();
+}
+
+fn random_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_insert");
+    for &size in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::new("tree", size), &size, |b, &size| {
+            b.iter(|| random_tree(size))
+        });
+        group.bench_with_input(BenchmarkId::new("btree_map", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+                let mut map = BTreeMap::new();
+                for value in 0..size {
+                    map.insert(rng.below(usize::MAX), value);
+                }
+                map
+            })
+        });
+        // Inserting into the middle of a Vec is quadratic, so it is only measured for small sizes
+        if size <= 10_000 {
+            group.bench_with_input(BenchmarkId::new("vec", size), &size, |b, &size| {
+                b.iter(|| {
+                    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+                    let mut values = Vec::with_capacity(size);
+                    for value in 0..size {
+                        values.insert(rng.below(values.len() + 1), value);
+                    }
+                    values
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn delete(c: &mut Criterion) {
+    let mut group = c.benchmark_group("delete");
+    for &size in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::new("tree", size), &size, |b, &size| {
+            b.iter_batched(
+                || random_tree(size),
+                |(mut tree, nodes)| {
+                    for node in nodes {
+                        black_box(tree.delete_node(node));
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("btree_map", size), &size, |b, &size| {
+            b.iter_batched(
+                || {
+                    (0..size)
+                        .map(|value| (value, value))
+                        .collect::<BTreeMap<_, _>>()
+                },
+                |mut map| {
+                    for value in 0..size {
+                        black_box(map.remove(&value));
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate");
+    for &size in SIZES.iter() {
+        let (tree, _) = random_tree(size);
+        group.bench_with_input(BenchmarkId::new("tree", size), &tree, |b, tree| {
+            b.iter(|| {
+                let mut sum = 0;
+                let mut node = tree.get_leftmost_node();
+                while let Some(current) = node {
+                    sum += *tree.get_contents(current);
+                    node = tree.get_next(current);
+                }
+                sum
+            })
+        });
+        let map: BTreeMap<_, _> = (0..size).map(|value| (value, value)).collect();
+        group.bench_with_input(BenchmarkId::new("btree_map", size), &map, |b, map| {
+            b.iter(|| map.values().sum::<usize>())
+        });
+        let values: Vec<_> = (0..size).collect();
+        group.bench_with_input(BenchmarkId::new("vec", size), &values, |b, values| {
+            b.iter(|| values.iter().sum::<usize>())
+        });
+    }
+    group.finish();
+}
+
+// Alternates inserting next to and deleting random nodes, keeping the size of the tree steady
+fn mixed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mixed");
+    for &size in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::new("tree", size), &size, |b, &size| {
+            b.iter_batched(
+                || random_tree(size),
+                |(mut tree, mut nodes)| {
+                    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+                    for value in 0..size {
+                        let index = rng.below(nodes.len());
+                        let node = tree.insert_after(nodes[index], value);
+                        tree.delete_node(nodes.swap_remove(index));
+                        nodes.push(node);
+                    }
+                    tree
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("btree_map", size), &size, |b, &size| {
+            b.iter_batched(
+                || {
+                    (0..size)
+                        .map(|value| (value, value))
+                        .collect::<BTreeMap<_, _>>()
+                },
+                |mut map| {
+                    for value in 0..size {
+                        map.insert(size + value, value);
+                        map.remove(&value);
+                    }
+                    map
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+// Building a tree from a sorted slice, with the values cloned and linked on one thread or with
+// chunks of them built on every thread and then appended together
+#[cfg(feature = "rayon")]
+fn from_sorted(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_sorted");
+    for &size in SIZES.iter().chain(&[1_000_000]) {
+        let values: Vec<usize> = (0..size).collect();
+        group.bench_with_input(BenchmarkId::new("iter", size), &values, |b, values| {
+            b.iter(|| Tree::<usize>::from_sorted_iter(values.iter().cloned()))
+        });
+        group.bench_with_input(BenchmarkId::new("par", size), &values, |b, values| {
+            b.iter(|| Tree::<usize>::from_sorted_par(values))
+        });
+
+        // Values that are expensive to clone are where building in parallel should gain most
+        let strings: Vec<String> = values
+            .iter()
+            .map(|value| format!("{:032}", value))
+            .collect();
+        group.bench_with_input(
+            BenchmarkId::new("iter_string", size),
+            &strings,
+            |b, strings| b.iter(|| Tree::<String>::from_sorted_iter(strings.iter().cloned())),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("par_string", size),
+            &strings,
+            |b, strings| b.iter(|| Tree::<String>::from_sorted_par(strings)),
+        );
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "rayon"))]
+criterion_group!(
+    benches,
+    sequential_insert,
+    random_insert,
+    delete,
+    iterate,
+    mixed
+);
+#[cfg(feature = "rayon")]
+criterion_group!(
+    benches,
+    sequential_insert,
+    random_insert,
+    delete,
+    iterate,
+    mixed,
+    from_sorted
+);
+criterion_main!(benches);
@@ -0,0 +1,14 @@
+// Generates the C header for the ffi feature into the build's output directory, where a test
+// checks that the copy committed in include/ is up to date
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let out_dir = std::env::var("OUT_DIR").unwrap();
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        cbindgen::generate(&crate_dir)
+            .expect("unable to generate the C header")
+            .write_to_file(format!("{}/binary_search_tree.h", out_dir));
+    }
+}
@@ -0,0 +1,61 @@
+#![no_main]
+//! Applies a random sequence of inserts and deletes to a tree and to a Vec holding the same
+//! values, checking after every step that the tree is valid and holds the values in the same order
+
+use binary_search_tree::{NodeKey, Tree};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    InsertBefore(u16, u8),
+    InsertAfter(u16, u8),
+    Delete(u16),
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut tree: Tree<u8> = Tree::new();
+    // The model holds the key and contents of every node, in order
+    let mut model: Vec<(NodeKey, u8)> = vec![];
+    for op in ops {
+        match op {
+            Op::InsertBefore(index, value) => {
+                if model.is_empty() {
+                    model.push((tree.create_root(value), value));
+                } else {
+                    let index = index as usize % model.len();
+                    let node = tree.insert_before(model[index].0, value);
+                    model.insert(index, (node, value));
+                }
+            }
+            Op::InsertAfter(index, value) => {
+                if model.is_empty() {
+                    model.push((tree.create_root(value), value));
+                } else {
+                    let index = index as usize % model.len();
+                    let node = tree.insert_after(model[index].0, value);
+                    model.insert(index + 1, (node, value));
+                }
+            }
+            Op::Delete(index) => {
+                if !model.is_empty() {
+                    let (node, value) = model.remove(index as usize % model.len());
+                    assert_eq!(tree.delete_node(node), value);
+                    assert!(!tree.contains_node(node));
+                }
+            }
+        }
+        if let Err(violations) = tree.check_invariants() {
+            panic!("invalid tree: {:?}", violations);
+        }
+        assert_eq!(tree.len(), model.len());
+    }
+
+    let mut node = tree.get_leftmost_node();
+    for &(key, value) in model.iter() {
+        assert_eq!(node, Some(key));
+        assert_eq!(*tree.get_contents(key), value);
+        node = tree.get_next(key);
+    }
+    assert_eq!(node, None);
+});
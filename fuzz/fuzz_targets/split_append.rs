@@ -0,0 +1,49 @@
+#![no_main]
+//! Splits and rejoins trees at random positions between inserts and deletes, checking the trees
+//! against Vecs holding the same values
+
+use binary_search_tree::Tree;
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Insert(u16, u8),
+    Delete(u16),
+    // Splits the tree and appends the front half onto the back half
+    Rotate(u16),
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut tree: Tree<u8> = Tree::new();
+    let mut model: Vec<u8> = vec![];
+    for op in ops {
+        match op {
+            Op::Insert(index, value) => {
+                let index = index as usize % (model.len() + 1);
+                tree.insert_at(index, value);
+                model.insert(index, value);
+            }
+            Op::Delete(index) => {
+                if !model.is_empty() {
+                    let index = index as usize % model.len();
+                    assert_eq!(tree.remove_at(index), Some(model.remove(index)));
+                }
+            }
+            Op::Rotate(index) => {
+                let index = index as usize % (model.len() + 1);
+                let (mut back, _) = tree.split_off_at(index);
+                if let Err(violations) = back.check_invariants() {
+                    panic!("invalid split off tree: {:?}", violations);
+                }
+                back.append(&mut tree);
+                tree = back;
+                model.rotate_left(index);
+            }
+        }
+        if let Err(violations) = tree.check_invariants() {
+            panic!("invalid tree: {:?}", violations);
+        }
+        assert_eq!(tree.to_vec(), model);
+    }
+});
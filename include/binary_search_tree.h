@@ -0,0 +1,135 @@
+#ifndef BINARY_SEARCH_TREE_H
+#define BINARY_SEARCH_TREE_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit */
+
+#include <stdint.h>
+#include <stddef.h>
+
+/**
+ * An opaque handle to a tree holding a pointer in every node, created with `bst_tree_new` and
+ * destroyed with `bst_tree_free`
+ */
+typedef struct BstTree BstTree;
+
+/**
+ * Called by `bst_tree_for_each` with the id and contents of each node along with the user data
+ */
+typedef void (*BstVisitFn)(uint64_t node, void *contents, void *user_data);
+
+/**
+ * Creates an empty tree, which must be destroyed with `bst_tree_free`
+ */
+struct BstTree *bst_tree_new(void);
+
+/**
+ * Destroys a tree. The pointers held in its nodes are not freed.
+ *
+ * # Safety
+ *
+ * `tree` must have been returned by `bst_tree_new` and not already freed, or be NULL.
+ */
+void bst_tree_free(struct BstTree *tree);
+
+/**
+ * Returns the number of nodes in the tree
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+uintptr_t bst_tree_len(const struct BstTree *tree);
+
+/**
+ * Adds the first node to an empty tree, returning its id, or 0 if the tree isn't empty
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+uint64_t bst_tree_create_root(struct BstTree *tree, void *contents);
+
+/**
+ * Inserts a node before an existing node in the sequence, returning the id of the new node, or
+ * 0 if the existing node isn't in the tree
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+uint64_t bst_tree_insert_before(struct BstTree *tree, uint64_t node, void *contents);
+
+/**
+ * Inserts a node after an existing node in the sequence, returning the id of the new node, or 0
+ * if the existing node isn't in the tree
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+uint64_t bst_tree_insert_after(struct BstTree *tree, uint64_t node, void *contents);
+
+/**
+ * Deletes a node from the tree, returning the pointer it held, or NULL if it isn't in the tree
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+void *bst_tree_delete(struct BstTree *tree, uint64_t node);
+
+/**
+ * Returns the pointer held by a node, or NULL if it isn't in the tree
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+void *bst_tree_get(const struct BstTree *tree, uint64_t node);
+
+/**
+ * Returns the id of the first node in the sequence, or 0 if the tree is empty
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+uint64_t bst_tree_first(const struct BstTree *tree);
+
+/**
+ * Returns the id of the last node in the sequence, or 0 if the tree is empty
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+uint64_t bst_tree_last(const struct BstTree *tree);
+
+/**
+ * Returns the id of the node after a node in the sequence, or 0 if there is none
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+uint64_t bst_tree_next(const struct BstTree *tree, uint64_t node);
+
+/**
+ * Returns the id of the node before a node in the sequence, or 0 if there is none
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`.
+ */
+uint64_t bst_tree_prev(const struct BstTree *tree, uint64_t node);
+
+/**
+ * Calls a function with the id and contents of every node, in order
+ *
+ * # Safety
+ *
+ * `tree` must be a live tree returned by `bst_tree_new`, which the callback must not modify.
+ */
+void bst_tree_for_each(const struct BstTree *tree, BstVisitFn visit, void *user_data);
+
+#endif  /* BINARY_SEARCH_TREE_H */
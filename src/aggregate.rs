@@ -0,0 +1,180 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use std::fmt;
+
+/// An augmentation that caches the aggregate of every subtree using an associative combine
+/// function, such as a sum, minimum or maximum. This allows the aggregate of any contiguous
+/// range of nodes to be found in O(log n) time with `Tree::aggregate_range`.
+#[derive(Clone)]
+pub struct Aggregate<L, C> {
+    lift: L,
+    combine: C,
+}
+
+impl<L, C> Aggregate<L, C> {
+    /// Creates a new aggregation
+    ///
+    /// # Arguments
+    ///
+    /// * `lift` - Converts the contents of a node into an aggregate value
+    /// * `combine` - Combines the aggregates of two adjacent ranges, this must be associative
+    ///
+    pub fn new(lift: L, combine: C) -> Self {
+        Aggregate { lift, combine }
+    }
+}
+
+impl<T, V: Clone, L: Fn(&T) -> V, C: Fn(&V, &V) -> V> Augment<T> for Aggregate<L, C> {
+    type Value = V;
+
+    fn update(&self, contents: &T, left: Option<&V>, right: Option<&V>) -> V {
+        let mut value = (self.lift)(contents);
+        if let Some(left) = left {
+            value = (self.combine)(left, &value);
+        }
+        if let Some(right) = right {
+            value = (self.combine)(&value, right);
+        }
+        value
+    }
+}
+
+impl<T, V, L, C, S, B> Tree<T, Aggregate<L, C>, S, B>
+where
+    T: Clone + fmt::Debug,
+    V: Clone,
+    L: Fn(&T) -> V,
+    C: Fn(&V, &V) -> V,
+    S: NodeStore<Node>,
+    B: Balance,
+{
+    /// Returns the aggregate of every node in the tree, or None if the tree is empty
+    pub fn aggregate_all(&self) -> Option<V> {
+        self.root.map(|root| self.get_augmented(root).clone())
+    }
+
+    /// Returns the aggregate of the nodes from `from` to `to` inclusive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from` comes after `to` in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The first node in the range
+    /// * `to` - The last node in the range
+    ///
+    pub fn aggregate_range(&self, from: NodeKey, to: NodeKey) -> V {
+        let first = self.rank_of(from);
+        let last = self.rank_of(to);
+        assert!(first <= last, "the range must not end before it starts");
+        self.aggregate_indices(self.root, 0, first, last).unwrap()
+    }
+
+    // Aggregates the nodes with indices between first and last inclusive within the subtree,
+    // where offset is the index of the first node in the subtree
+    fn aggregate_indices(
+        &self,
+        node: Option<NodeKey>,
+        offset: usize,
+        first: usize,
+        last: usize,
+    ) -> Option<V> {
+        let node = node?;
+        let size = self.get_size(Some(node));
+        if last < offset || first >= offset + size {
+            // The subtree is entirely outside the range
+            return None;
+        }
+        if first <= offset && offset + size - 1 <= last {
+            // The subtree is entirely inside the range
+            return Some(self.get_augmented(node).clone());
+        }
+
+        let left = self.get_left(node);
+        let index = offset + self.get_size(left);
+        let mut value = self.aggregate_indices(left, offset, first, last);
+        if first <= index && index <= last {
+            let contents = (self.augment.lift)(self.get_contents(node));
+            value = Some(match value {
+                Some(value) => (self.augment.combine)(&value, &contents),
+                None => contents,
+            });
+        }
+        match self.aggregate_indices(self.get_right(node), index + 1, first, last) {
+            Some(right) => Some(match value {
+                Some(value) => (self.augment.combine)(&value, &right),
+                None => right,
+            }),
+            None => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_range_test() {
+        let mut tree: Tree<_, _> =
+            Tree::with_augment(Aggregate::new(|x: &u64| *x, |a: &u64, b: &u64| a + b));
+        assert_eq!(tree.aggregate_all(), None);
+
+        let mut nodes = vec![tree.create_root(0)];
+        for i in 1..50 {
+            nodes.push(tree.insert_after(nodes[i - 1], i as u64));
+        }
+        assert_eq!(tree.aggregate_all(), Some((0..50).sum()));
+        for first in (0..50).step_by(3) {
+            for last in (first..50).step_by(5) {
+                let expected: u64 = (first as u64..=last as u64).sum();
+                assert_eq!(tree.aggregate_range(nodes[first], nodes[last]), expected);
+            }
+        }
+
+        tree.set_contents(nodes[10], 1000);
+        assert_eq!(tree.aggregate_range(nodes[9], nodes[11]), 9 + 1000 + 11);
+        tree.delete_node(nodes[10]);
+        assert_eq!(tree.aggregate_range(nodes[9], nodes[11]), 9 + 11);
+    }
+
+    #[test]
+    fn aggregate_non_commutative_test() {
+        // String concatenation is associative but not commutative so checks the order is kept
+        let mut tree: Tree<_, _> = Tree::with_augment(Aggregate::new(
+            |c: &char| c.to_string(),
+            |a: &String, b: &String| format!("{}{}", a, b),
+        ));
+        let mut node = tree.create_root('a');
+        let mut nodes = vec![node];
+        for c in "bcdefghij".chars() {
+            node = tree.insert_after(node, c);
+            nodes.push(node);
+        }
+        assert_eq!(tree.aggregate_all().unwrap(), "abcdefghij");
+        assert_eq!(tree.aggregate_range(nodes[2], nodes[6]), "cdefg");
+        assert_eq!(tree.aggregate_range(nodes[4], nodes[4]), "e");
+    }
+
+    #[test]
+    fn aggregate_other_trees_test() {
+        let sum = || Aggregate::new(|x: &u64| *x, |a: &u64, b: &u64| a + b);
+        let mut avl: crate::AvlTree<u64, _> = crate::AvlTree::with_augment(sum());
+        let mut vec_tree: Tree<u64, _, crate::VecStore<Node>> = Tree::with_augment(sum());
+        avl.extend(0..100);
+        vec_tree.extend(0..100);
+        assert_eq!(avl.aggregate_all(), Some((0..100).sum()));
+        let (from, to) = (vec_tree.nth(10).unwrap(), vec_tree.nth(20).unwrap());
+        assert_eq!(vec_tree.aggregate_range(from, to), (10..=20).sum::<u64>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn aggregate_reversed_range_test() {
+        let mut tree: Tree<_, _> =
+            Tree::with_augment(Aggregate::new(|x: &u64| *x, |a: &u64, b: &u64| a + b));
+        let first = tree.create_root(1);
+        let second = tree.insert_after(first, 2);
+        tree.aggregate_range(second, first);
+    }
+}
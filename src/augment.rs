@@ -0,0 +1,165 @@
+use crate::{Balance, Node, NodeKey, NodeStore, Tree};
+use std::fmt;
+
+/// Per-subtree data that the tree keeps up to date as it is modified.
+///
+/// Every node stores a value computed from its own contents and the values of its children, which
+/// lets the value summarise the whole subtree (sizes, maxima, sums...). The tree calls `update`
+/// whenever a node's subtree changes: for the nodes involved in a rotation, for the two nodes
+/// swapped while deleting a node and their ancestors, and for every ancestor of a node that is
+/// inserted, deleted or has its contents set.
+///
+/// The value of a node should only depend on the in-order sequence of its subtree, not the shape
+/// of it, as the values of the ancestors of a rotation are not recomputed.
+pub trait Augment<T> {
+    /// The data stored for every node
+    type Value;
+
+    /// Computes the value for a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `contents` - The contents of the node
+    /// * `left` - The value of the node's left child, if it has one
+    /// * `right` - The value of the node's right child, if it has one
+    ///
+    fn update(
+        &self,
+        contents: &T,
+        left: Option<&Self::Value>,
+        right: Option<&Self::Value>,
+    ) -> Self::Value;
+}
+
+/// The default augmentation, which stores nothing
+impl<T> Augment<T> for () {
+    type Value = ();
+
+    fn update(&self, _contents: &T, _left: Option<&()>, _right: Option<&()>) {}
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Returns a reference to the augmentation used by the tree
+    pub fn augment(&self) -> &A {
+        &self.augment
+    }
+
+    /// Returns the augmented value of the subtree rooted at the specified node
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The root of the subtree to return the value of
+    ///
+    pub fn get_augmented(&self, node: NodeKey) -> &A::Value {
+        &self.augmented[node]
+    }
+
+    /// Recomputes the augmented values of the specified node and all of its ancestors.
+    /// This must be called after changing a node's contents through `get_mut_contents` if the
+    /// augmented value depends on them.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node whose contents have changed
+    ///
+    pub fn update_augmented(&mut self, node: NodeKey) {
+        let mut node = Some(node);
+        while let Some(current) = node {
+            self.update_augmented_node(current);
+            node = self.get_parent(current);
+        }
+    }
+
+    // Recomputes the augmented value of a single node from its contents and its children
+    pub(crate) fn update_augmented_node(&mut self, node: NodeKey) {
+        let left = self.get_left(node);
+        let right = self.get_right(node);
+        let value = self.augment.update(
+            &self.node_data[node],
+            left.map(|left| &self.augmented[left]),
+            right.map(|right| &self.augmented[right]),
+        );
+        self.augmented.insert(node, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tracks the maximum value and height of each subtree
+    struct MaxAugment;
+
+    impl Augment<usize> for MaxAugment {
+        type Value = usize;
+
+        fn update(&self, contents: &usize, left: Option<&usize>, right: Option<&usize>) -> usize {
+            *contents.max(left.unwrap_or(&0)).max(right.unwrap_or(&0))
+        }
+    }
+
+    fn check_max(tree: &Tree<usize, MaxAugment>, node: Option<NodeKey>) -> usize {
+        match node {
+            None => 0,
+            Some(node) => {
+                let expected = (*tree.get_contents(node))
+                    .max(check_max(tree, tree.get_left(node)))
+                    .max(check_max(tree, tree.get_right(node)));
+                assert_eq!(*tree.get_augmented(node), expected);
+                expected
+            }
+        }
+    }
+
+    #[test]
+    fn augment_test() {
+        let mut tree = Tree::with_augment(MaxAugment);
+        let mut nodes = vec![tree.create_root(0)];
+        for i in 1..100 {
+            let value = (i * 37) % 101;
+            let node = if i % 3 == 0 {
+                tree.insert_before(nodes[i / 2], value)
+            } else {
+                tree.insert_after(nodes[i / 2], value)
+            };
+            nodes.push(node);
+            check_max(&tree, tree.root);
+        }
+        assert_eq!(*tree.get_augmented(tree.root.unwrap()), 100);
+
+        tree.set_contents(nodes[10], 500);
+        assert_eq!(*tree.get_augmented(tree.root.unwrap()), 500);
+        *tree.get_mut_contents(nodes[10]) = 0;
+        tree.update_augmented(nodes[10]);
+        check_max(&tree, tree.root);
+
+        for &node in nodes.iter().step_by(2) {
+            tree.delete_node(node);
+            check_max(&tree, tree.root);
+        }
+        tree.shrink_to_fit();
+        check_max(&tree, tree.root);
+    }
+
+    #[test]
+    fn swap_nodes_test() {
+        let mut tree = Tree::with_augment(MaxAugment);
+        let mut node = tree.create_root(0);
+        for i in 1..50 {
+            node = tree.insert_after(node, (i * 37) % 101);
+        }
+
+        // Swap nodes with their successors, as deleting does, and with their children
+        let nodes: Vec<NodeKey> = tree.iter_with_keys().map(|(node, _)| node).collect();
+        for &node in nodes.iter() {
+            if let (Some(_), Some(next)) = (tree.get_right(node), tree.get_next(node)) {
+                tree.swap_nodes(node, next);
+                check_max(&tree, tree.root);
+            }
+            if let Some(left) = tree.get_left(node) {
+                tree.swap_nodes(node, left);
+                check_max(&tree, tree.root);
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use crate::balance::{Rebalance, Subtree};
+use crate::{Augment, Balance, InvariantViolation, Node, NodeKey, NodeStore, Tree};
+use slotmap::SlotMap;
+use std::fmt;
+
+/// AVL balancing. Every node stores the height of its subtree and the heights of the subtrees of
+/// a node differ by at most one, so the tree is never more than about 1.44 times the height of a
+/// perfectly balanced tree, against twice the height for red-black balancing. Searches and walks
+/// down the tree are shorter in exchange for more rotations while inserting and deleting.
+#[derive(PartialEq, Eq, Copy, Clone, Default, Debug)]
+pub struct Avl;
+
+/// A tree kept balanced by AVL rotations, see `Avl`
+pub type AvlTree<T, A = ()> = Tree<T, A, SlotMap<NodeKey, Node>, Avl>;
+
+impl Rebalance for Avl {
+    fn inserted<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        tree.set_rank(node, 1);
+        tree.avl_rebalance_from(tree.get_parent(node));
+    }
+
+    fn remove<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        let parent = tree.unlink(node);
+        tree.avl_rebalance_from(parent);
+    }
+
+    fn rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: Option<NodeKey>,
+    ) -> usize {
+        tree.get_rank(node)
+    }
+
+    fn join<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        left: Subtree,
+        pivot: NodeKey,
+        right: Subtree,
+    ) -> (NodeKey, usize) {
+        tree.avl_join(left, pivot, right)
+    }
+
+    fn linked<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+        _deepest: bool,
+    ) {
+        tree.avl_update_height(node);
+    }
+
+    fn check<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+        violations: &mut Vec<InvariantViolation>,
+    ) -> usize {
+        let (left, right) = (left.unwrap_or(0), right.unwrap_or(0));
+        if left.max(right) - left.min(right) > 1 {
+            violations.push(InvariantViolation::Unbalanced { node, left, right });
+        }
+        let actual = left.max(right) + 1;
+        let stored = tree.get_rank(Some(node));
+        if stored != actual {
+            violations.push(InvariantViolation::HeightMismatch {
+                node,
+                stored,
+                actual,
+            });
+        }
+        actual
+    }
+
+    fn restore<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+    ) -> Result<usize, &'static str> {
+        let (left, right) = (left.unwrap_or(0), right.unwrap_or(0));
+        if left.max(right) - left.min(right) > 1 {
+            return Err("the heights of the subtrees differ by more than one");
+        }
+        tree.set_rank(node, left.max(right) + 1);
+        Ok(left.max(right) + 1)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    // Returns the height stored for an AVL subtree, 0 for an empty one
+    fn avl_height(&self, node: Option<NodeKey>) -> usize {
+        self.get_rank(node)
+    }
+
+    // Recomputes the height stored for a node from the heights of its children
+    fn avl_update_height(&mut self, node: NodeKey) {
+        let left = self.avl_height(self.get_left(node));
+        let right = self.avl_height(self.get_right(node));
+        self.set_rank(node, left.max(right) + 1);
+    }
+
+    // Updates the height of a node whose children are balanced, rotating it if its children differ
+    // in height by two. Returns the node that takes its place as the root of its subtree.
+    fn avl_balance(&mut self, node: NodeKey) -> NodeKey {
+        let left = self.get_left(node);
+        let right = self.get_right(node);
+        let (left_height, right_height) = (self.avl_height(left), self.avl_height(right));
+        if left_height > right_height + 1 {
+            let left = left.unwrap();
+            if self.avl_height(self.get_left(left)) < self.avl_height(self.get_right(left)) {
+                // Left-right case, the inner grandchild is rotated up first
+                self.left_rotate(left);
+                self.avl_update_height(left);
+            }
+            self.right_rotate(node);
+        } else if right_height > left_height + 1 {
+            let right = right.unwrap();
+            if self.avl_height(self.get_right(right)) < self.avl_height(self.get_left(right)) {
+                // Right-left case
+                self.right_rotate(right);
+                self.avl_update_height(right);
+            }
+            self.left_rotate(node);
+        } else {
+            self.avl_update_height(node);
+            return node;
+        }
+        self.avl_update_height(node);
+        let root = self.get_parent(node).unwrap();
+        self.avl_update_height(root);
+        root
+    }
+
+    // Rebalances the nodes from the specified node up to the root, stopping once the height of a
+    // subtree is unchanged as nothing above it can have changed either
+    fn avl_rebalance_from(&mut self, mut node: Option<NodeKey>) {
+        while let Some(current) = node {
+            self.count_fixup_iteration();
+            let height = self.avl_height(Some(current));
+            let root = self.avl_balance(current);
+            if self.avl_height(Some(root)) == height {
+                break;
+            }
+            node = self.get_parent(root);
+        }
+    }
+
+    // Joins two detached subtrees and a detached pivot node that sits between them in the sequence
+    // into a single subtree. This takes time proportional to the difference in the heights of the
+    // subtrees.
+    fn avl_join(&mut self, left: Subtree, pivot: NodeKey, right: Subtree) -> (NodeKey, usize) {
+        let left_height = left.map_or(0, |(_, height)| height);
+        let right_height = right.map_or(0, |(_, height)| height);
+        if left_height > right_height + 1 {
+            let (root, _) = left.unwrap();
+            self.avl_join_into(root, pivot, right, right_height, false)
+        } else if right_height > left_height + 1 {
+            let (root, _) = right.unwrap();
+            self.avl_join_into(root, pivot, left, left_height, true)
+        } else {
+            // The subtrees are close enough in height for the pivot to become the root of both
+            let left = left.map(|(root, _)| root);
+            let right = right.map(|(root, _)| root);
+            self.set_left(pivot, left);
+            self.set_right(pivot, right);
+            for child in left.into_iter().chain(right) {
+                self.set_parent(child, Some(pivot));
+            }
+            self.set_size(pivot, self.get_size(left) + self.get_size(right) + 1);
+            self.avl_update_height(pivot);
+            self.update_augmented_node(pivot);
+            (pivot, self.avl_height(Some(pivot)))
+        }
+    }
+
+    // Joins the shorter subtree into the taller one by walking down the inner spine of the taller
+    // subtree to the first node at most one taller than the shorter subtree, and replacing it with
+    // the pivot node that has that node and the shorter subtree as children.
+    // When `into_left` is false the shorter subtree comes after the taller one in the sequence.
+    fn avl_join_into(
+        &mut self,
+        tall_root: NodeKey,
+        pivot: NodeKey,
+        short: Subtree,
+        short_height: usize,
+        into_left: bool,
+    ) -> (NodeKey, usize) {
+        let mut parent = tall_root;
+        let mut node = self.inner_child(tall_root, into_left);
+        while let Some(current) = node {
+            if self.avl_height(Some(current)) <= short_height + 1 {
+                break;
+            }
+            parent = current;
+            node = self.inner_child(current, into_left);
+        }
+
+        self.link_pivot(parent, pivot, node, short, into_left);
+        self.avl_update_height(pivot);
+
+        // The pivot is balanced but its ancestors may not be, the rotations need the taller
+        // subtree to be treated as the whole tree while they happen
+        let root = self.root.replace(tall_root);
+        self.avl_rebalance_from(Some(parent));
+        let joined_root = std::mem::replace(&mut self.root, root).unwrap();
+        (joined_root, self.avl_height(Some(joined_root)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::tests::{insert_delete_test, split_append_test};
+
+    #[test]
+    fn avl_insert_delete_test() {
+        insert_delete_test::<Avl>(500, |tree, _| {
+            // An AVL tree of height h holds at least fib(h + 2) - 1 nodes
+            assert_eq!(tree.height(), 9);
+        });
+    }
+
+    #[test]
+    fn avl_split_append_test() {
+        split_append_test::<Avl>(40, |_| {});
+    }
+}
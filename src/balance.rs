@@ -0,0 +1,623 @@
+use crate::{Augment, Color, InvariantViolation, Node, NodeKey, NodeStore, NodeType, Tree};
+use std::cmp::Ordering;
+use std::fmt;
+
+// Subtrees taking part in a split or join are detached from the tree, so have no parent, and are
+// tracked by their root along with their rank: the black height of a red-black subtree or the
+// height of an AVL subtree. An empty subtree has a rank of zero.
+pub(crate) type Subtree = Option<(NodeKey, usize)>;
+
+// Rebalances a red pivot joined into a red-black subtree, returning true if the black height of
+// the subtree grew
+pub(crate) type JoinFixup<Tree> = fn(&mut Tree, NodeKey) -> bool;
+
+/// A scheme for keeping a tree balanced, chosen with the last type parameter of `Tree`. Every
+/// scheme keeps the height of the tree logarithmic in its length, amortized in the case of splay
+/// trees and expected in the case of treaps, so the operations on the tree have the same
+/// complexity whichever is used, but they trade the cost of updates off against the depth of the
+/// nodes differently.
+///
+/// * `RedBlack` - The default, rebalancing an insert or delete makes at most three rotations
+/// * `Avl` - Keeps the tree more strictly balanced, so searches are shorter, at the cost of more
+///   rotations while updating
+/// * `Llrb` - A left-leaning red-black tree, with far fewer cases to handle while rebalancing
+/// * `WeightBalanced` - Balances the sizes of the subtrees rather than their heights, suiting
+///   workloads heavy in splits and joins
+/// * `Splay` - Keeps no balance but moves the nodes it touches up to the root, suiting workloads
+///   that keep coming back to the same nodes
+/// * `Treap` - Balanced by random priorities, with the simplest splits and joins
+///
+/// The trait is sealed, the tree relies on the details of the schemes it provides.
+pub trait Balance: Rebalance {}
+
+impl<B: Rebalance> Balance for B {}
+
+// The operations a balancing scheme provides to the tree. Not exported, so the schemes are limited
+// to the ones in this crate.
+//
+// The balance data of a node, such as its color or height, is kept in the rank bits of the node.
+// The rank of a subtree is whatever measure of its height the scheme joins subtrees by.
+pub trait Rebalance: Copy + Default + fmt::Debug + Send + Sync + 'static {
+    // Returns true if the node is red, only red-black schemes have red nodes
+    fn is_red(_node: &Node) -> bool {
+        false
+    }
+
+    // Rebalances the tree after a node has been linked in as a leaf, or made the root
+    fn inserted<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    );
+
+    // Unlinks a node that has at most one child from the tree and rebalances the rest. The subtree
+    // sizes of its ancestors have already been reduced.
+    fn remove<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    );
+
+    // Returns the rank of a subtree
+    fn rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: Option<NodeKey>,
+    ) -> usize;
+
+    // Returns the rank of a child subtree given the rank of its parent's subtree
+    fn child_rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        _parent: NodeKey,
+        _parent_rank: usize,
+        child: Option<NodeKey>,
+    ) -> usize {
+        Self::rank(tree, child)
+    }
+
+    // Returns the rank of a subtree given the rank of one of its children
+    fn parent_rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        parent: NodeKey,
+        _child_rank: usize,
+    ) -> usize {
+        Self::rank(tree, Some(parent))
+    }
+
+    // Joins two detached subtrees and a detached pivot node that sits between them in the sequence
+    // into a single subtree, returning its root and rank
+    fn join<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        left: Subtree,
+        pivot: NodeKey,
+        right: Subtree,
+    ) -> (NodeKey, usize);
+
+    // Prepares a joined or split off subtree to become the root of a tree
+    fn make_root<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &mut Tree<T, A, S, Self>,
+        _root: NodeKey,
+    ) {
+    }
+
+    // Links the specified nodes into a balanced subtree holding them in order, returning its root.
+    // The nodes must all be in the arena but their links can be anything.
+    fn link<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        keys: &[NodeKey],
+    ) -> Option<NodeKey> {
+        // The depth of the deepest level of a perfectly balanced tree, which may be partly filled
+        let mut deepest = 0;
+        while 2 << deepest <= keys.len() {
+            deepest += 1;
+        }
+        tree.link_range(keys, None, 0, deepest)
+    }
+
+    // Sets the balance data of a node once its children have been linked into a perfectly
+    // balanced subtree by the default link. `deepest` is true for the nodes on the deepest level.
+    fn linked<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &mut Tree<T, A, S, Self>,
+        _node: NodeKey,
+        _deepest: bool,
+    ) {
+    }
+
+    // Checks the balance of a node given the ranks found for its child subtrees, None where a
+    // child is missing, recording any violations. Returns the rank of the node's subtree.
+    fn check<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+        violations: &mut Vec<InvariantViolation>,
+    ) -> usize;
+
+    // Checks the balance data of the root of the tree, recording any violations
+    fn check_root<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &Tree<T, A, S, Self>,
+        _root: NodeKey,
+        _violations: &mut Vec<InvariantViolation>,
+    ) {
+    }
+
+    // Recomputes the balance data of a node that has been restored along with its children, given
+    // the ranks of its child subtrees. Returns the rank of the node's subtree, or a description of
+    // the problem if the node is out of balance.
+    fn restore<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+    ) -> Result<usize, &'static str>;
+
+    // Checks the balance data of a restored root, returning a description of any problem
+    fn restore_root<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &Tree<T, A, S, Self>,
+        _root: NodeKey,
+    ) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+/// Red-black balancing, the default. Every node is red or black, no red node has a red child and
+/// every path from the root down to a leaf passes through the same number of black nodes. The
+/// longest path is at most twice the length of the shortest.
+#[derive(PartialEq, Eq, Copy, Clone, Default, Debug)]
+pub struct RedBlack;
+
+impl Rebalance for RedBlack {
+    fn is_red(node: &Node) -> bool {
+        node.color() == Color::Red
+    }
+
+    fn inserted<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        tree.insert_rebalance(node);
+    }
+
+    fn remove<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        tree.red_black_remove(node);
+    }
+
+    fn rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: Option<NodeKey>,
+    ) -> usize {
+        tree.subtree_black_height(node)
+    }
+
+    fn child_rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        parent: NodeKey,
+        parent_rank: usize,
+        _child: Option<NodeKey>,
+    ) -> usize {
+        parent_rank - tree.is_black(parent)
+    }
+
+    fn parent_rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        parent: NodeKey,
+        child_rank: usize,
+    ) -> usize {
+        child_rank + tree.is_black(parent)
+    }
+
+    fn join<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        left: Subtree,
+        pivot: NodeKey,
+        right: Subtree,
+    ) -> (NodeKey, usize) {
+        tree.red_black_join(left, pivot, right, Tree::insert_rebalance)
+    }
+
+    fn make_root<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        root: NodeKey,
+    ) {
+        tree.set_color(root, Color::Black);
+    }
+
+    // The depth of the leaves of a perfectly balanced tree differ by at most one, so colouring the
+    // nodes on the deepest level red and every other node black gives every path the same black
+    // height
+    fn linked<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+        deepest: bool,
+    ) {
+        let color = if deepest { Color::Red } else { Color::Black };
+        tree.set_color(node, color);
+    }
+
+    fn check<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+        violations: &mut Vec<InvariantViolation>,
+    ) -> usize {
+        tree.red_black_check(node, left, right, violations)
+    }
+
+    fn check_root<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        root: NodeKey,
+        violations: &mut Vec<InvariantViolation>,
+    ) {
+        if tree.is_black(root) == 0 {
+            violations.push(InvariantViolation::RedRoot(root));
+        }
+    }
+
+    fn restore<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+    ) -> Result<usize, &'static str> {
+        tree.red_black_restore(node, left, right)
+    }
+
+    fn restore_root<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        root: NodeKey,
+    ) -> Result<(), &'static str> {
+        if tree.is_black(root) == 0 {
+            return Err("the root is red");
+        }
+        Ok(())
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    // Unlinks a node that has at most one child, moving the child up into its place. Returns the
+    // parent of the node.
+    pub(crate) fn unlink(&mut self, node: NodeKey) -> Option<NodeKey> {
+        let child = self.get_left(node).or_else(|| self.get_right(node));
+        let parent = self.get_parent(node);
+        if let Some(child) = child {
+            self.set_parent(child, parent);
+        }
+        match self.get_node_type(node) {
+            NodeType::LeftChild => self.set_left(parent.unwrap(), child),
+            NodeType::RightChild => self.set_right(parent.unwrap(), child),
+            NodeType::Orphan => self.root = child,
+        }
+        self.set_parent(node, None);
+        self.set_left(node, None);
+        self.set_right(node, None);
+        if let Some(parent) = parent {
+            self.update_augmented(parent);
+        }
+        parent
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>> Tree<T, A, S, RedBlack> {
+    // Unlinks a node with at most one child and fixes up the colors of the remaining nodes
+    fn red_black_remove(&mut self, node: NodeKey) {
+        let replacement = self.get_replacement_node(node);
+        let both_black = self.get_color(Some(node)) == Color::Black
+            && self.get_color(replacement) == Color::Black;
+        match replacement {
+            None => {
+                // The node is a leaf
+                if Some(node) == self.root {
+                    // node is the root so set the root to None
+                    self.root = None;
+                } else {
+                    if both_black {
+                        // Both the node and the replacement are black
+                        // As v is a leaf we need to fix the double black at v
+                        self.fix_double_black(node);
+                    } else if let Some(sibling) = self.get_sibling(node) {
+                        // The node must be red
+                        self.set_color(sibling, Color::Red);
+                    }
+                    let parent = self.get_parent(node);
+                    match self.get_node_type(node) {
+                        NodeType::LeftChild => self.set_left(parent.unwrap(), None),
+                        NodeType::RightChild => self.set_right(parent.unwrap(), None),
+                        NodeType::Orphan => panic!("None root node can't be an orphan"),
+                    }
+                    self.update_augmented(parent.unwrap());
+                }
+            }
+            Some(replacement) => {
+                if Some(node) == self.root {
+                    // Removing the root node
+                    self.swap_nodes(node, replacement);
+                    self.set_left(replacement, None);
+                    self.set_right(replacement, None);
+                    self.update_augmented_node(replacement);
+                } else {
+                    let parent = self.get_parent(node);
+                    match self.get_node_type(node) {
+                        NodeType::LeftChild => self.set_left(parent.unwrap(), Some(replacement)),
+                        NodeType::RightChild => self.set_right(parent.unwrap(), Some(replacement)),
+                        NodeType::Orphan => panic!("None root node can't be an orphan"),
+                    }
+                    self.set_parent(replacement, parent);
+                    self.update_augmented(parent.unwrap());
+                    if both_black {
+                        self.fix_double_black(node);
+                    } else {
+                        self.set_color(replacement, Color::Black);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    // Returns 1 if the node is black and 0 if it is red
+    pub(crate) fn is_black(&self, node: NodeKey) -> usize {
+        (self.get_color(Some(node)) == Color::Black) as usize
+    }
+
+    // Checks the colors of a red-black node given the black heights of its child subtrees,
+    // returning the black height of its subtree
+    pub(crate) fn red_black_check(
+        &self,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+        violations: &mut Vec<InvariantViolation>,
+    ) -> usize {
+        // Missing children are black leaves
+        let (left_height, right_height) = (left.unwrap_or(1), right.unwrap_or(1));
+        if left_height != right_height {
+            violations.push(InvariantViolation::BlackHeightMismatch {
+                node,
+                left: left_height,
+                right: right_height,
+            });
+        }
+        let is_black = self.is_black(node);
+        if is_black == 0 {
+            let children = [self.get_left(node), self.get_right(node)];
+            for child in children.iter().flatten() {
+                if self.contains_node(*child) && self.is_black(*child) == 0 {
+                    violations.push(InvariantViolation::RedRedEdge {
+                        parent: node,
+                        child: *child,
+                    });
+                }
+            }
+        }
+        left_height.max(right_height) + is_black
+    }
+
+    // Checks the colors of a restored red-black node, see red_black_check
+    pub(crate) fn red_black_restore(
+        &self,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+    ) -> Result<usize, &'static str> {
+        let mut violations = vec![];
+        let black_height = self.red_black_check(node, left, right, &mut violations);
+        match violations.first() {
+            None => Ok(black_height),
+            Some(InvariantViolation::RedRedEdge { .. }) => Err("a red node has a red child"),
+            Some(_) => Err("the black heights of the subtrees differ"),
+        }
+    }
+
+    // Joins two detached subtrees and a detached pivot node that sits between them in the sequence
+    // into a single subtree, using the fixup to rebalance a red pivot joined into the taller
+    // subtree. This takes time proportional to the difference in the black heights of the
+    // subtrees.
+    pub(crate) fn red_black_join(
+        &mut self,
+        left: Subtree,
+        pivot: NodeKey,
+        right: Subtree,
+        fixup: JoinFixup<Self>,
+    ) -> (NodeKey, usize) {
+        // Red roots are made black so both subtrees are valid red-black trees in their own right
+        let left = left.map(|(root, height)| (root, height + self.blacken(root)));
+        let right = right.map(|(root, height)| (root, height + self.blacken(root)));
+        let left_height = left.map_or(0, |(_, height)| height);
+        let right_height = right.map_or(0, |(_, height)| height);
+
+        match left_height.cmp(&right_height) {
+            Ordering::Equal => {
+                // The pivot can become the root of both subtrees
+                let left = left.map(|(root, _)| root);
+                let right = right.map(|(root, _)| root);
+                self.set_left(pivot, left);
+                self.set_right(pivot, right);
+                for child in left.into_iter().chain(right) {
+                    self.set_parent(child, Some(pivot));
+                }
+                self.set_color(pivot, Color::Red);
+                self.set_size(pivot, self.get_size(left) + self.get_size(right) + 1);
+                self.update_augmented_node(pivot);
+                (pivot, left_height)
+            }
+            Ordering::Greater => {
+                let (root, _) = left.unwrap();
+                self.red_black_join_into(
+                    root,
+                    left_height,
+                    pivot,
+                    right,
+                    right_height,
+                    false,
+                    fixup,
+                )
+            }
+            Ordering::Less => {
+                let (root, _) = right.unwrap();
+                self.red_black_join_into(root, right_height, pivot, left, left_height, true, fixup)
+            }
+        }
+    }
+
+    // Joins the shorter subtree into the taller one by walking down the inner spine of the taller
+    // subtree to a black node with the same black height as the shorter subtree, and replacing it
+    // with a red pivot node that has the black node and the shorter subtree as children.
+    // When `into_left` is false the shorter subtree comes after the taller one in the sequence.
+    #[allow(clippy::too_many_arguments)]
+    fn red_black_join_into(
+        &mut self,
+        tall_root: NodeKey,
+        tall_height: usize,
+        pivot: NodeKey,
+        short: Subtree,
+        short_height: usize,
+        into_left: bool,
+        fixup: JoinFixup<Self>,
+    ) -> (NodeKey, usize) {
+        let mut parent = tall_root;
+        let mut height = tall_height - self.is_black(tall_root);
+        let mut node = self.inner_child(tall_root, into_left);
+        while let Some(current) = node {
+            if height == short_height && self.is_black(current) == 1 {
+                break;
+            }
+            height -= self.is_black(current);
+            parent = current;
+            node = self.inner_child(current, into_left);
+        }
+
+        self.link_pivot(parent, pivot, node, short, into_left);
+        self.set_color(pivot, Color::Red);
+
+        // The pivot may have a red parent so rebalance as if it had just been inserted, the
+        // rotations need the taller subtree to be treated as the whole tree while they happen
+        let root = self.root.replace(tall_root);
+        let grew = fixup(self, pivot);
+        let joined_root = std::mem::replace(&mut self.root, root).unwrap();
+        (joined_root, tall_height + grew as usize)
+    }
+
+    // Makes a node black, returning 1 if it was red and so the black height of its subtree grew
+    fn blacken(&mut self, node: NodeKey) -> usize {
+        let grew = 1 - self.is_black(node);
+        self.set_color(node, Color::Black);
+        grew
+    }
+
+    // Rotates a node up over its parent
+    pub(crate) fn rotate_up(&mut self, node: NodeKey) {
+        let parent = self.get_parent(node).unwrap();
+        match self.get_node_type(node) {
+            NodeType::LeftChild => self.right_rotate(parent),
+            NodeType::RightChild => self.left_rotate(parent),
+            NodeType::Orphan => unreachable!("the root has no parent to rotate over"),
+        }
+    }
+
+    // Returns the child of a node on the side facing the subtree being joined into it
+    pub(crate) fn inner_child(&self, node: NodeKey, into_left: bool) -> Option<NodeKey> {
+        if into_left {
+            self.get_left(node)
+        } else {
+            self.get_right(node)
+        }
+    }
+
+    // Replaces the inner child `node` of `parent` with the pivot of a join, which takes `node` and
+    // the shorter subtree as its children. The sizes and augmented values of the pivot and its new
+    // ancestors are updated.
+    pub(crate) fn link_pivot(
+        &mut self,
+        parent: NodeKey,
+        pivot: NodeKey,
+        node: Option<NodeKey>,
+        short: Subtree,
+        into_left: bool,
+    ) {
+        let short = short.map(|(root, _)| root);
+        if let Some(short) = short {
+            self.set_parent(short, Some(pivot));
+        }
+        if let Some(node) = node {
+            self.set_parent(node, Some(pivot));
+        }
+        self.set_parent(pivot, Some(parent));
+        if into_left {
+            self.set_left(parent, Some(pivot));
+            self.set_left(pivot, short);
+            self.set_right(pivot, node);
+        } else {
+            self.set_right(parent, Some(pivot));
+            self.set_left(pivot, node);
+            self.set_right(pivot, short);
+        }
+        let added = self.get_size(short) + 1;
+        self.set_size(pivot, self.get_size(node) + added);
+        self.update_ancestor_sizes(pivot, |size| size + added);
+        self.update_augmented(pivot);
+    }
+}
+
+// Tests shared by the balancing schemes, each of which calls them with its own type and adds the
+// assertions particular to it
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use slotmap::SlotMap;
+
+    pub(crate) type BalancedTree<B> = Tree<usize, (), SlotMap<NodeKey, Node>, B>;
+
+    // Inserts the values up to `size` in order, the worst case for an unbalanced tree, checking
+    // the tree after every insert. The full tree is handed to `check` along with the keys of its
+    // nodes, then two out of every three nodes are deleted.
+    pub(crate) fn insert_delete_test<B: Balance>(
+        size: usize,
+        check: impl FnOnce(&mut BalancedTree<B>, &[NodeKey]),
+    ) {
+        let mut tree: BalancedTree<B> = Tree::default();
+        let mut nodes = vec![tree.create_root(0)];
+        for i in 1..size {
+            let node = tree.insert_after(nodes[i - 1], i);
+            nodes.push(node);
+            tree.assert_valid();
+        }
+        check(&mut tree, &nodes);
+        for (i, node) in nodes.into_iter().enumerate() {
+            if i % 3 != 0 {
+                assert_eq!(tree.delete_node(node), i);
+                tree.assert_valid();
+            }
+        }
+        assert_eq!(tree.to_vec(), (0..size).step_by(3).collect::<Vec<_>>());
+    }
+
+    // Splits trees of every size below `max_size` at every position and appends the two halves
+    // back together. The second half is handed to `change` before it is appended, so that the
+    // joins meet trees of shapes particular to the balancing scheme.
+    pub(crate) fn split_append_test<B: Balance>(
+        max_size: usize,
+        mut change: impl FnMut(&mut BalancedTree<B>),
+    ) {
+        for size in 1..max_size {
+            for at in 0..size {
+                let mut tree: BalancedTree<B> = (0..size).collect();
+                tree.assert_valid();
+                let (mut other, _) = tree.split_off_at(at);
+                tree.assert_valid();
+                other.assert_valid();
+                assert_eq!(tree.to_vec(), (0..at).collect::<Vec<_>>());
+                assert_eq!(other.to_vec(), (at..size).collect::<Vec<_>>());
+
+                change(&mut other);
+                let mut expected = tree.to_vec();
+                expected.extend(other.to_vec());
+                tree.append(&mut other);
+                tree.assert_valid();
+                assert!(other.is_empty());
+                assert_eq!(tree.to_vec(), expected);
+            }
+        }
+    }
+}
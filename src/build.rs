@@ -0,0 +1,414 @@
+#[cfg(feature = "serde")]
+use crate::Color;
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use slotmap::SecondaryMap;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FromIterator;
+
+impl<T: Clone + fmt::Debug, A: Augment<T> + Default, S: NodeStore<Node> + Default, B: Balance>
+    Tree<T, A, S, B>
+{
+    /// Creates a perfectly balanced tree holding the items in the order they are iterated,
+    /// taking time proportional to the number of items. The iterator must be sorted if the tree
+    /// is going to be searched or used with `insert_sorted`.
+    ///
+    /// # Arguments
+    ///
+    /// * `iter` - The items to fill the tree with, in order
+    ///
+    pub fn from_sorted_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut tree: Self = Tree::with_capacity_and_augment(iter.size_hint().0, A::default());
+        let keys: Vec<NodeKey> = iter
+            .map(|value| {
+                let node = tree.nodes.insert(Node::new());
+                tree.node_data.insert(node, value);
+                node
+            })
+            .collect();
+        tree.link_balanced(&keys);
+        tree
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T> + Default, S: NodeStore<Node> + Default, B: Balance>
+    FromIterator<T> for Tree<T, A, S, B>
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Tree::from_sorted_iter(iter)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Extend<T>
+    for Tree<T, A, S, B>
+{
+    /// Inserts the items after the last node in the tree, in the order they are iterated
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            match self.rightmost {
+                Some(last) => self.insert_after(last, value),
+                None => self.create_root(value),
+            };
+        }
+    }
+}
+
+// A node of a tree described by its position in the tree's sequence, used to rebuild a tree that
+// has been exported along with its exact structure
+#[cfg(feature = "serde")]
+pub(crate) struct NodeParts<T> {
+    pub contents: T,
+    pub color: Color,
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Merges the nodes of another tree into this one, leaving the other tree empty. Both trees
+    /// must already be sorted by the comparison function, the merged tree will be too. Nodes that
+    /// compare equal keep their relative order, with the nodes of this tree coming first.
+    ///
+    /// Takes time proportional to the total number of nodes, the merged tree is rebuilt to be
+    /// perfectly balanced. The moved nodes are given new NodeKeys, returns a map from the old
+    /// NodeKeys of the moved nodes to their new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The sorted tree whose nodes are merged into this one
+    /// * `cmp` - The function the trees are sorted by
+    ///
+    pub fn merge_by<F: FnMut(&T, &T) -> Ordering>(
+        &mut self,
+        other: &mut Tree<T, A, S, B>,
+        mut cmp: F,
+    ) -> SecondaryMap<NodeKey, NodeKey> {
+        self.begin_counting();
+        let other_root = match other.root {
+            Some(root) => root,
+            None => return SecondaryMap::new(),
+        };
+        let other_leftmost = other.leftmost.unwrap();
+        let (_, key_map) = other.transplant(other_root, self);
+        other.root = None;
+        other.len = 0;
+        other.leftmost = None;
+        other.rightmost = None;
+
+        let mut keys = Vec::with_capacity(self.len + key_map.len());
+        let mut node = self.leftmost;
+        let mut other_node = Some(key_map[other_leftmost]);
+        while let (Some(current), Some(other_current)) = (node, other_node) {
+            if cmp(self.get_contents(other_current), self.get_contents(current)) == Ordering::Less {
+                keys.push(other_current);
+                other_node = self.get_next(other_current);
+            } else {
+                keys.push(current);
+                node = self.get_next(current);
+            }
+        }
+        for mut remaining in [node, other_node].iter().copied() {
+            while let Some(current) = remaining {
+                keys.push(current);
+                remaining = self.get_next(current);
+            }
+        }
+
+        self.link_balanced(&keys);
+        for (_, &new_key) in key_map.iter() {
+            self.log_insert(new_key);
+        }
+        trace_event!(count = key_map.len(), "merge");
+        self.validate_after_mutation();
+        key_map
+    }
+
+    /// Rebuilds the tree into a shape of the minimum height for its length, keeping the order of
+    /// the nodes and their NodeKeys. Takes time proportional to the number of nodes, worthwhile
+    /// after a pattern of inserts that left the tree deeper than it needs to be, such as a splay
+    /// tree built up in order, or before a long phase of searches.
+    ///
+    /// Left-leaning red-black trees can only have red left children, so they are rebuilt as the
+    /// shallowest 2-3 tree instead, which can be a level deeper.
+    pub fn rebalance_to_perfect(&mut self) {
+        self.begin_counting();
+        let keys: Vec<NodeKey> = self.iter_with_keys().map(|(node, _)| node).collect();
+        self.link_balanced(&keys);
+        trace_event!(count = keys.len(), "rebalance to perfect");
+        self.validate_after_mutation();
+    }
+
+    // Builds a tree with exactly the structure described by the parts, which must be listed in
+    // the order of the tree's sequence. Returns the tree along with the NodeKeys of the nodes in
+    // order, or a description of the problem if the parts don't describe a tree balanced by the
+    // tree's balancing scheme.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_parts(
+        parts: Vec<NodeParts<T>>,
+        root: Option<usize>,
+        augment: A,
+    ) -> Result<(Self, Vec<NodeKey>), &'static str>
+    where
+        S: Default,
+    {
+        let count = parts.len();
+        let mut tree: Self = Tree::with_capacity_and_augment(count, augment);
+        let mut links = Vec::with_capacity(count);
+        let keys: Vec<NodeKey> = parts
+            .into_iter()
+            .map(|part| {
+                let node = tree.nodes.insert(Node::new());
+                tree.node_data.insert(node, part.contents);
+                tree.set_color(node, part.color);
+                links.push((part.left, part.right));
+                node
+            })
+            .collect();
+
+        let mut has_parent = vec![false; count];
+        for (index, &(left, right)) in links.iter().enumerate() {
+            for child in left.into_iter().chain(right) {
+                if child >= count || has_parent[child] || Some(child) == root {
+                    return Err("the nodes do not form a tree");
+                }
+                has_parent[child] = true;
+                tree.set_parent(keys[child], Some(keys[index]));
+            }
+            tree.set_left(keys[index], left.map(|left| keys[left]));
+            tree.set_right(keys[index], right.map(|right| keys[right]));
+        }
+        match root {
+            Some(root) if root >= count => return Err("the nodes do not form a tree"),
+            None if count > 0 => return Err("the nodes do not form a tree"),
+            _ => {}
+        }
+        tree.root = root.map(|root| keys[root]);
+
+        // Walk the tree in order, every node must be reached and in the order they were listed
+        let mut visited = 0;
+        let mut stack = vec![];
+        let mut node = tree.root;
+        while node.is_some() || !stack.is_empty() {
+            while let Some(current) = node {
+                stack.push(current);
+                node = tree.get_left(current);
+            }
+            let current = stack.pop().unwrap();
+            if visited >= count || keys[visited] != current {
+                return Err("the nodes are not listed in order");
+            }
+            visited += 1;
+            node = tree.get_right(current);
+        }
+        if visited != count {
+            return Err("the nodes do not form a tree");
+        }
+
+        // Children come before their parents in reverse pre-order, so sizes, augmented values and
+        // the balance data can be computed bottom up
+        let mut ranks = SecondaryMap::with_capacity(count);
+        let mut pre_order: Vec<NodeKey> = Vec::with_capacity(count);
+        pre_order.extend(tree.root);
+        let mut index = 0;
+        while index < pre_order.len() {
+            let current = pre_order[index];
+            pre_order.extend(tree.get_left(current));
+            pre_order.extend(tree.get_right(current));
+            index += 1;
+        }
+        for &current in pre_order.iter().rev() {
+            let left = tree.get_left(current);
+            let right = tree.get_right(current);
+            let rank = |child: Option<NodeKey>| child.map(|child| ranks[child]);
+            let (left_rank, right_rank) = (rank(left), rank(right));
+            ranks.insert(
+                current,
+                B::restore(&mut tree, current, left_rank, right_rank)?,
+            );
+            tree.set_size(current, tree.get_size(left) + tree.get_size(right) + 1);
+            tree.update_augmented_node(current);
+        }
+        if let Some(root) = tree.root {
+            B::restore_root(&tree, root)?;
+        }
+
+        for pair in keys.windows(2) {
+            tree.set_next(pair[0], Some(pair[1]));
+            tree.set_prev(pair[1], Some(pair[0]));
+        }
+        tree.len = count;
+        tree.leftmost = keys.first().copied();
+        tree.rightmost = keys.last().copied();
+        Ok((tree, keys))
+    }
+
+    // Rebuilds the tree structure as a perfectly balanced tree holding the specified nodes in
+    // order. The nodes must all be in the arena but their links can be anything.
+    pub(crate) fn link_balanced(&mut self, keys: &[NodeKey]) {
+        self.bump_generation();
+        self.root = B::link(self, keys);
+        if let Some(root) = self.root {
+            B::make_root(self, root);
+        }
+
+        let mut prev = None;
+        for &key in keys {
+            self.set_prev(key, prev);
+            if let Some(prev) = prev {
+                self.set_next(prev, Some(key));
+            }
+            prev = Some(key);
+        }
+        if let Some(&last) = keys.last() {
+            self.set_next(last, None);
+        }
+        self.len = keys.len();
+        self.leftmost = keys.first().copied();
+        self.rightmost = keys.last().copied();
+    }
+
+    // Links a range of nodes into a perfectly balanced subtree, returning its root
+    pub(crate) fn link_range(
+        &mut self,
+        keys: &[NodeKey],
+        parent: Option<NodeKey>,
+        depth: usize,
+        deepest: usize,
+    ) -> Option<NodeKey> {
+        if keys.is_empty() {
+            return None;
+        }
+        let middle = keys.len() / 2;
+        let node = keys[middle];
+        let left = self.link_range(&keys[..middle], Some(node), depth + 1, deepest);
+        let right = self.link_range(&keys[middle + 1..], Some(node), depth + 1, deepest);
+        self.set_parent(node, parent);
+        self.set_left(node, left);
+        self.set_right(node, right);
+        self.set_size(node, keys.len());
+        B::linked(self, node, depth == deepest);
+        self.update_augmented_node(node);
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct SumAugment;
+
+    impl Augment<u32> for SumAugment {
+        type Value = u32;
+
+        fn update(&self, contents: &u32, left: Option<&u32>, right: Option<&u32>) -> u32 {
+            contents + left.unwrap_or(&0) + right.unwrap_or(&0)
+        }
+    }
+
+    fn sorted_tree(values: &[i32]) -> Tree<i32> {
+        let mut tree = Tree::new();
+        for &value in values {
+            tree.insert_by(value, |existing| existing.cmp(&value));
+        }
+        tree
+    }
+
+    #[test]
+    fn from_iter_test() {
+        for size in 0..200 {
+            let tree: Tree<usize> = (0..size).collect();
+            tree.assert_valid();
+            assert_eq!(tree.len(), size);
+            for i in 0..size {
+                assert_eq!(tree.get_contents(tree.nth(i).unwrap()), &i);
+            }
+        }
+
+        let sums = Tree::<u32, SumAugment>::from_sorted_iter(1..=10);
+        assert_eq!(sums.get_augmented(sums.root.unwrap()), &55);
+    }
+
+    #[test]
+    fn extend_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        tree.extend(0..10);
+        tree.extend(vec![10, 11, 12]);
+        tree.extend(std::iter::empty());
+        tree.assert_valid();
+        assert_eq!(tree.len(), 13);
+        for i in 0..13 {
+            assert_eq!(tree.get_contents(tree.nth(i).unwrap()), &i);
+        }
+    }
+
+    #[test]
+    fn merge_by_test() {
+        for (left, right) in [
+            (vec![], vec![]),
+            (vec![1, 3, 5], vec![]),
+            (vec![], vec![2, 4]),
+            (vec![1, 3, 5, 7, 9, 11], vec![0, 2, 4, 6, 8, 10, 12, 14, 16]),
+            ((0..100).step_by(3).collect(), (0..100).step_by(5).collect()),
+        ]
+        .iter()
+        {
+            let mut tree = sorted_tree(left);
+            let mut other = sorted_tree(right);
+            let key_map = tree.merge_by(&mut other, |a, b| a.cmp(b));
+            tree.assert_valid();
+            assert!(other.is_empty());
+            assert_eq!(key_map.len(), right.len());
+
+            let mut expected: Vec<i32> = left.iter().chain(right.iter()).copied().collect();
+            expected.sort_unstable();
+            let mut merged = vec![];
+            let mut node = tree.get_leftmost_node();
+            while let Some(current) = node {
+                merged.push(*tree.get_contents(current));
+                node = tree.get_next(current);
+            }
+            assert_eq!(merged, expected);
+        }
+    }
+
+    #[test]
+    fn merge_by_stable_test() {
+        let mut tree: Tree<(u8, char)> = Tree::new();
+        let mut other: Tree<(u8, char)> = Tree::new();
+        let a = tree.create_root((1, 'a'));
+        tree.insert_after(a, (2, 'a'));
+        let b = other.create_root((1, 'b'));
+        other.insert_after(b, (2, 'b'));
+        tree.merge_by(&mut other, |x, y| x.0.cmp(&y.0));
+        let order: Vec<char> = (0..4)
+            .map(|i| tree.get_contents(tree.nth(i).unwrap()).1)
+            .collect();
+        assert_eq!(order, vec!['a', 'b', 'a', 'b']);
+    }
+
+    #[test]
+    fn rebalance_to_perfect_test() {
+        let mut tree: crate::SplayTree<usize> = Tree::default();
+        let mut node = tree.create_root(0);
+        for i in 1..1000 {
+            node = tree.insert_after(node, i);
+        }
+        assert_eq!(tree.height(), 1000);
+        let nodes: Vec<NodeKey> = tree.iter_with_keys().map(|(node, _)| node).collect();
+        tree.rebalance_to_perfect();
+        tree.assert_valid();
+        assert_eq!(tree.height(), 10);
+        for (i, &node) in nodes.iter().enumerate() {
+            assert_eq!(tree[node], i);
+        }
+
+        let mut tree: crate::LlrbTree<usize> = (0..1000).collect();
+        tree.rebalance_to_perfect();
+        tree.assert_valid();
+        assert!(tree.height() <= 11);
+    }
+}
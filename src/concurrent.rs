@@ -0,0 +1,247 @@
+use crate::{Augment, Balance, FrozenTree, Node, NodeKey, NodeStore, RedBlack, Tree, TreeError};
+use slotmap::SlotMap;
+use std::fmt;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A tree that can be shared between threads, behind a reader-writer lock. Any number of threads
+/// can read the tree at once while writes take it in turn.
+///
+/// Another thread may delete a node between one call and the next, so the operations that take a
+/// NodeKey return `TreeError::StaleKey` rather than panicking when the node has gone. A group of
+/// operations that must not be interleaved with other threads can be made under a single lock
+/// with `read` or `write`.
+///
+/// If a thread panics while writing to the tree, later calls panic rather than use a tree that
+/// may have been left half modified.
+pub struct ConcurrentTree<
+    T: Clone + fmt::Debug,
+    A: Augment<T> = (),
+    S = SlotMap<NodeKey, Node>,
+    B = RedBlack,
+> {
+    tree: RwLock<Tree<T, A, S, B>>,
+}
+
+impl<T: Clone + fmt::Debug> ConcurrentTree<T> {
+    /// Creates a new empty tree
+    pub fn new() -> Self {
+        ConcurrentTree::from(Tree::new())
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T> + Default, S: NodeStore<Node> + Default, B: Balance>
+    Default for ConcurrentTree<T, A, S, B>
+{
+    fn default() -> Self {
+        ConcurrentTree::from(Tree::default())
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> From<Tree<T, A, S, B>>
+    for ConcurrentTree<T, A, S, B>
+{
+    fn from(tree: Tree<T, A, S, B>) -> Self {
+        ConcurrentTree {
+            tree: RwLock::new(tree),
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> fmt::Debug
+    for ConcurrentTree<T, A, S, B>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.lock_read().fmt(f)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    ConcurrentTree<T, A, S, B>
+{
+    /// Runs a closure with shared access to the tree, blocking until no thread is writing to it.
+    /// Returns the result of the closure.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The closure to run, given the tree to read
+    ///
+    pub fn read<R, F: FnOnce(&Tree<T, A, S, B>) -> R>(&self, f: F) -> R {
+        f(&self.lock_read())
+    }
+
+    /// Runs a closure with exclusive access to the tree, blocking until no other thread is using
+    /// it. Returns the result of the closure.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The closure to run, given the tree to mutate
+    ///
+    pub fn write<R, F: FnOnce(&mut Tree<T, A, S, B>) -> R>(&self, f: F) -> R {
+        f(&mut self.lock_write())
+    }
+
+    /// Returns the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.lock_read().len()
+    }
+
+    /// Returns true if the tree has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.lock_read().is_empty()
+    }
+
+    /// Returns a copy of the contents of a node, or None if the node is not in the tree
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey of the node to read
+    ///
+    pub fn get_contents(&self, node: NodeKey) -> Option<T> {
+        self.lock_read().try_get_contents(node).cloned()
+    }
+
+    /// Replaces the contents of a node, returning an error if the node is not in the tree
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey of the node to update
+    /// * `contents` - The new contents of the node
+    ///
+    pub fn set_contents(&self, node: NodeKey, contents: T) -> Result<(), TreeError> {
+        self.lock_write()
+            .try_set_contents(node, contents)
+            .map_err(|_| TreeError::StaleKey(node))
+    }
+
+    /// Creates a new root node for the tree and returns its NodeKey, or an error if the tree
+    /// already has a root
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to populate the new node with
+    ///
+    pub fn create_root(&self, value: T) -> Result<NodeKey, TreeError> {
+        self.lock_write().try_create_root(value)
+    }
+
+    /// Create and insert a new node immediately after the specified node and rebalance the tree.
+    /// Returns an error if the existing node is not in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `existing_node` - The NodeKey of the existing node to insert the new node after
+    /// * `value` - The value to populate the newly created node with
+    ///
+    pub fn insert_after(&self, existing_node: NodeKey, value: T) -> Result<NodeKey, TreeError> {
+        self.lock_write().try_insert_after(existing_node, value)
+    }
+
+    /// Create and insert a new node immediately before the specified node and rebalance the tree.
+    /// Returns an error if the existing node is not in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `existing_node` - The NodeKey of the existing node to insert the new node before
+    /// * `value` - The value to populate the newly created node with
+    ///
+    pub fn insert_before(&self, existing_node: NodeKey, value: T) -> Result<NodeKey, TreeError> {
+        self.lock_write().try_insert_before(existing_node, value)
+    }
+
+    /// Delete the specified node from the tree and rebalance the remaining nodes. Returns the
+    /// contents of the deleted node, or an error if the node is not in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey of the node to delete from the tree
+    ///
+    pub fn delete_node(&self, node: NodeKey) -> Result<T, TreeError> {
+        self.lock_write().try_delete_node(node)
+    }
+
+    /// Returns the tree, consuming the wrapper
+    pub fn into_inner(self) -> Tree<T, A, S, B> {
+        self.tree
+            .into_inner()
+            .expect("the tree was poisoned by a panic")
+    }
+
+    fn lock_read(&self) -> RwLockReadGuard<'_, Tree<T, A, S, B>> {
+        self.tree.read().expect("the tree was poisoned by a panic")
+    }
+
+    fn lock_write(&self) -> RwLockWriteGuard<'_, Tree<T, A, S, B>> {
+        self.tree.write().expect("the tree was poisoned by a panic")
+    }
+}
+
+impl<T: Clone + fmt::Debug + Ord, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    ConcurrentTree<T, A, S, B>
+{
+    /// Inserts a new node in sorted position and rebalance the tree, returning the NodeKey of the
+    /// newly created node
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to populate the newly created node with
+    ///
+    pub fn insert_sorted(&self, value: T) -> NodeKey {
+        self.lock_write().insert_sorted(value)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T> + Clone, S: NodeStore<Node> + Clone, B: Balance>
+    ConcurrentTree<T, A, S, B>
+where
+    A::Value: Clone,
+{
+    /// Returns an immutable copy of the tree as it is now, which can be read without taking the
+    /// lock again
+    pub fn freeze(&self) -> FrozenTree<T, A, S, B> {
+        self.lock_read().freeze()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn concurrent_tree_test() {
+        let tree = Arc::new(ConcurrentTree::new());
+        let root = tree.create_root(0u32).unwrap();
+        assert_eq!(tree.create_root(1), Err(TreeError::RootAlreadyExists));
+
+        let writers: Vec<_> = (0..4)
+            .map(|thread| {
+                let tree = Arc::clone(&tree);
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        let node = tree.insert_after(root, thread * 1000 + i).unwrap();
+                        if i % 2 == 0 {
+                            tree.delete_node(node).unwrap();
+                            assert_eq!(tree.delete_node(node), Err(TreeError::StaleKey(node)));
+                        }
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(tree.len(), 201);
+        let frozen = tree.freeze();
+        tree.set_contents(root, 7).unwrap();
+        assert_eq!(tree.get_contents(root), Some(7));
+        assert_eq!(*frozen.get_contents(root), 0);
+        tree.read(|tree| tree.assert_valid());
+
+        let tree = Arc::try_unwrap(tree).unwrap().into_inner();
+        let mut odd = tree.to_vec();
+        odd.sort_unstable();
+        assert_eq!(&odd[..3], &[1, 3, 5]);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{Augment, Balance, Node, NodeStore, Tree};
+use std::fmt;
+use std::ops::AddAssign;
+
+/// Counts of the work done rebalancing a tree, returned by `Tree::rebalance_counters` and
+/// `Tree::last_rebalance_counters`
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub struct RebalanceCounters {
+    /// The number of left and right rotations
+    pub rotations: usize,
+    /// The number of times a node changed color
+    pub recolorings: usize,
+    /// The number of passes made through the insert and delete fixup loops
+    pub fixup_iterations: usize,
+}
+
+impl AddAssign for RebalanceCounters {
+    fn add_assign(&mut self, other: Self) {
+        self.rotations += other.rotations;
+        self.recolorings += other.recolorings;
+        self.fixup_iterations += other.fixup_iterations;
+    }
+}
+
+// The counters for the latest operation and the total since they were last reset
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct Counters {
+    last: RebalanceCounters,
+    total: RebalanceCounters,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Returns the rebalancing work done since the tree was created or the counters were last
+    /// reset. Operations that rebuild the tree in bulk, such as `retain` and `merge_by`, are
+    /// counted through the inserts, deletes, rotations and recolorings they make.
+    pub fn rebalance_counters(&self) -> RebalanceCounters {
+        self.counters.total
+    }
+
+    /// Returns the rebalancing work done by the latest insert or delete
+    pub fn last_rebalance_counters(&self) -> RebalanceCounters {
+        self.counters.last
+    }
+
+    /// Sets all of the rebalancing counters back to zero
+    pub fn reset_rebalance_counters(&mut self) {
+        self.counters = Counters::default();
+    }
+
+    // Starts counting the work of a new insert or delete
+    pub(crate) fn begin_counting(&mut self) {
+        self.counters.last = RebalanceCounters::default();
+    }
+
+    pub(crate) fn count_rotation(&mut self) {
+        self.count(RebalanceCounters {
+            rotations: 1,
+            ..Default::default()
+        });
+    }
+
+    pub(crate) fn count_recoloring(&mut self) {
+        self.count(RebalanceCounters {
+            recolorings: 1,
+            ..Default::default()
+        });
+    }
+
+    pub(crate) fn count_fixup_iteration(&mut self) {
+        self.count(RebalanceCounters {
+            fixup_iterations: 1,
+            ..Default::default()
+        });
+    }
+
+    fn count(&mut self, counters: RebalanceCounters) {
+        self.counters.last += counters;
+        self.counters.total += counters;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebalance_counters_test() {
+        let mut tree: Tree<u32> = Tree::new();
+        let root = tree.create_root(0);
+        assert_eq!(tree.rebalance_counters().rotations, 0);
+        assert_eq!(tree.rebalance_counters().fixup_iterations, 0);
+
+        // The third node in a row forces a rotation about the root
+        let second = tree.insert_after(root, 1);
+        assert_eq!(tree.last_rebalance_counters().rotations, 0);
+        tree.insert_after(second, 2);
+        let last = tree.last_rebalance_counters();
+        assert_eq!(last.rotations, 1);
+        assert_eq!(last.fixup_iterations, 1);
+        assert!(last.recolorings >= 2);
+
+        let mut node = tree.get_rightmost_node().unwrap();
+        for i in 3..100 {
+            node = tree.insert_after(node, i);
+        }
+        while let Some(first) = tree.get_leftmost_node() {
+            tree.delete_node(first);
+        }
+        let total = tree.rebalance_counters();
+        assert!(total.rotations > 100);
+        assert!(total.fixup_iterations >= total.rotations / 2);
+
+        tree.reset_rebalance_counters();
+        assert_eq!(tree.rebalance_counters(), RebalanceCounters::default());
+
+        // Splitting a node is counted as a single operation
+        let mut tree: Tree<u32> = (0..100).collect();
+        for i in 0..10 {
+            tree.reset_rebalance_counters();
+            let node = tree.nth(i * 9).unwrap();
+            tree.split_node(node, 0, 0, 0);
+            assert_eq!(tree.last_rebalance_counters(), tree.rebalance_counters());
+        }
+        assert_ne!(tree.last_rebalance_counters(), RebalanceCounters::default());
+
+        // So is inserting a run of nodes
+        let before = tree.rebalance_counters();
+        let node = tree.nth(50).unwrap();
+        tree.insert_all_after(node, 0..20);
+        let (total, last) = (tree.rebalance_counters(), tree.last_rebalance_counters());
+        assert_eq!(last.rotations, total.rotations - before.rotations);
+        assert_eq!(last.recolorings, total.recolorings - before.recolorings);
+
+        // And inserting the nodes of another tree
+        let before = tree.rebalance_counters();
+        let node = tree.nth(10).unwrap();
+        tree.insert_tree_after(node, (0..20).collect());
+        let (total, last) = (tree.rebalance_counters(), tree.last_rebalance_counters());
+        assert_eq!(last.rotations, total.rotations - before.rotations);
+        assert_eq!(last.recolorings, total.recolorings - before.recolorings);
+
+        // And detaching a subtree
+        let before = tree.rebalance_counters();
+        let node = tree.get_left(tree.root.unwrap()).unwrap();
+        tree.detach_subtree(node);
+        let (total, last) = (tree.rebalance_counters(), tree.last_rebalance_counters());
+        assert_eq!(last.rotations, total.rotations - before.rotations);
+        assert_eq!(last.recolorings, total.recolorings - before.recolorings);
+
+        // And deleting a run of nodes
+        let before = tree.rebalance_counters();
+        let (from, to) = (tree.nth(5).unwrap(), tree.nth(40).unwrap());
+        tree.delete_range(from, to);
+        let (total, last) = (tree.rebalance_counters(), tree.last_rebalance_counters());
+        assert_eq!(last.rotations, total.rotations - before.rotations);
+        assert_eq!(last.recolorings, total.recolorings - before.recolorings);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, RedBlack, Tree, TreeError};
+use slotmap::SlotMap;
+use std::fmt;
+
+/// A read only cursor pointing at a node in a tree.
+///
+/// The cursor can either point at a node or at a "ghost" position that sits between the last and
+/// first nodes of the tree, which is where it ends up after moving off either end of the sequence.
+/// Moving next from the ghost position goes to the first node, moving prev goes to the last.
+pub struct Cursor<
+    'a,
+    T: Clone + fmt::Debug,
+    A: Augment<T> = (),
+    S: NodeStore<Node> = SlotMap<NodeKey, Node>,
+    B: Balance = RedBlack,
+> {
+    tree: &'a Tree<T, A, S, B>,
+    node: Option<NodeKey>,
+}
+
+/// The position of a cursor saved by `Cursor::position`, which doesn't borrow the tree. It is
+/// stamped with the generation of the tree so that `Tree::cursor_at` can tell if the tree's
+/// structure has changed since.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct CursorPosition {
+    node: Option<NodeKey>,
+    generation: u64,
+}
+
+impl CursorPosition {
+    /// Returns the NodeKey of the node the cursor pointed at, or None for the ghost position
+    pub fn key(&self) -> Option<NodeKey> {
+        self.node
+    }
+
+    /// Returns the generation of the tree when the position was saved
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Clone
+    for Cursor<'a, T, A, S, B>
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Copy
+    for Cursor<'a, T, A, S, B>
+{
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    Cursor<'a, T, A, S, B>
+{
+    /// Returns the NodeKey of the node the cursor points at, or None at the ghost position
+    pub fn key(&self) -> Option<NodeKey> {
+        self.node
+    }
+
+    /// Returns a reference to the contents of the node the cursor points at, or None at the
+    /// ghost position
+    pub fn contents(&self) -> Option<&'a T> {
+        self.node.map(|node| self.tree.get_contents(node))
+    }
+
+    /// Saves the position of the cursor so that it can be recreated with `Tree::cursor_at` once
+    /// the borrow of the tree has ended
+    pub fn position(&self) -> CursorPosition {
+        CursorPosition {
+            node: self.node,
+            generation: self.tree.generation(),
+        }
+    }
+
+    /// Moves the cursor to the next node in the tree
+    pub fn move_next(&mut self) {
+        self.node = match self.node {
+            Some(node) => self.tree.get_next(node),
+            None => self.tree.get_leftmost_node(),
+        }
+    }
+
+    /// Moves the cursor to the previous node in the tree
+    pub fn move_prev(&mut self) {
+        self.node = match self.node {
+            Some(node) => self.tree.get_prev(node),
+            None => self.tree.get_rightmost_node(),
+        }
+    }
+
+    /// Returns a cursor pointing at the parent of the current node
+    pub fn parent(&self) -> Self {
+        self.with_node(self.node.and_then(|node| self.tree.get_parent(node)))
+    }
+
+    /// Returns a cursor pointing at the left child of the current node
+    pub fn left(&self) -> Self {
+        self.with_node(self.node.and_then(|node| self.tree.get_left(node)))
+    }
+
+    /// Returns a cursor pointing at the right child of the current node
+    pub fn right(&self) -> Self {
+        self.with_node(self.node.and_then(|node| self.tree.get_right(node)))
+    }
+
+    fn with_node(&self, node: Option<NodeKey>) -> Self {
+        Cursor {
+            tree: self.tree,
+            node,
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Returns a cursor pointing at the specified node
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey of the node for the cursor to point at
+    ///
+    pub fn cursor(&self, node: NodeKey) -> Cursor<'_, T, A, S, B> {
+        debug_assert!(self.contains_node(node));
+        Cursor {
+            tree: self,
+            node: Some(node),
+        }
+    }
+
+    /// Returns a cursor at a position saved by `Cursor::position`, or `TreeError::Invalidated`
+    /// if the structure of the tree has changed since the position was saved
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The saved position
+    ///
+    pub fn cursor_at(&self, position: CursorPosition) -> Result<Cursor<'_, T, A, S, B>, TreeError> {
+        if position.generation != self.generation() {
+            return Err(TreeError::Invalidated);
+        }
+        Ok(Cursor {
+            tree: self,
+            node: position.node,
+        })
+    }
+
+    /// Returns a cursor pointing at the first node in the tree, or the ghost position if the tree
+    /// is empty
+    pub fn cursor_front(&self) -> Cursor<'_, T, A, S, B> {
+        Cursor {
+            tree: self,
+            node: self.get_leftmost_node(),
+        }
+    }
+
+    /// Returns a cursor pointing at the last node in the tree, or the ghost position if the tree
+    /// is empty
+    pub fn cursor_back(&self) -> Cursor<'_, T, A, S, B> {
+        Cursor {
+            tree: self,
+            node: self.get_rightmost_node(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_navigation_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        let two = tree.create_root(2);
+        let one = tree.insert_before(two, 1);
+        let three = tree.insert_after(two, 3);
+
+        let mut cursor = tree.cursor_front();
+        assert_eq!(cursor.key(), Some(one));
+        assert_eq!(cursor.contents(), Some(&1));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.contents(), Some(&3));
+        cursor.move_next();
+        assert_eq!(cursor.key(), None);
+        assert_eq!(cursor.contents(), None);
+        cursor.move_next();
+        assert_eq!(cursor.key(), Some(one));
+        cursor.move_prev();
+        assert_eq!(cursor.key(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.key(), Some(three));
+
+        let root = tree.cursor(two);
+        assert_eq!(root.left().key(), Some(one));
+        assert_eq!(root.right().key(), Some(three));
+        assert_eq!(root.left().parent().key(), Some(two));
+        assert_eq!(root.parent().key(), None);
+        assert_eq!(root.left().left().key(), None);
+        assert_eq!(tree.cursor_back().contents(), Some(&3));
+    }
+
+    #[test]
+    fn cursor_position_test() {
+        let mut tree: Tree<u32> = (0..10).collect();
+        let mut cursor = tree.cursor_front();
+        cursor.move_next();
+        let position = cursor.position();
+        assert_eq!(tree.cursor_at(position).unwrap().contents(), Some(&1));
+
+        // Changing contents leaves the structure alone
+        let first = tree.get_leftmost_node().unwrap();
+        tree.set_contents(first, 100);
+        assert_eq!(tree.cursor_at(position).unwrap().key(), position.key());
+
+        let generation = tree.generation();
+        tree.delete_node(first);
+        assert_ne!(tree.generation(), generation);
+        assert_eq!(tree.cursor_at(position).err(), Some(TreeError::Invalidated));
+
+        let position = tree.cursor_back().position();
+        tree.retain(|_| false);
+        assert_eq!(tree.cursor_at(position).err(), Some(TreeError::Invalidated));
+    }
+}
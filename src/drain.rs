@@ -0,0 +1,142 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, RedBlack, Tree};
+use slotmap::{SecondaryMap, SlotMap};
+use std::fmt;
+use std::mem;
+use std::vec;
+
+/// A draining iterator over the contents of a tree, in order.
+///
+/// The tree is emptied as soon as the iterator is created, its contents are taken out of it as the
+/// iterator is advanced and any left over are dropped along with the iterator. The memory
+/// allocated for the nodes is kept so that the tree can be refilled without reallocating, unless
+/// the iterator is leaked, which leaves the tree empty without it.
+pub struct Drain<
+    'a,
+    T: Clone + fmt::Debug,
+    A: Augment<T> = (),
+    S: NodeStore<Node> = SlotMap<NodeKey, Node>,
+    B: Balance = RedBlack,
+> {
+    tree: &'a mut Tree<T, A, S, B>,
+    // The nodes still to be yielded, in order
+    keys: vec::IntoIter<NodeKey>,
+    // The contents of the nodes, taken out of the tree
+    node_data: SecondaryMap<NodeKey, T>,
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    Drain<'a, T, A, S, B>
+{
+    // Takes the contents of a node, calling the delete callback for it
+    fn take(&mut self, node: NodeKey) -> Option<T> {
+        let contents = self.node_data.remove(node)?;
+        self.tree.notify_delete_taken(node, &contents);
+        Some(contents)
+    }
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Iterator
+    for Drain<'a, T, A, S, B>
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let node = self.keys.next()?;
+        self.take(node)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> DoubleEndedIterator
+    for Drain<'a, T, A, S, B>
+{
+    fn next_back(&mut self) -> Option<T> {
+        let node = self.keys.next_back()?;
+        self.take(node)
+    }
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> ExactSizeIterator
+    for Drain<'a, T, A, S, B>
+{
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Drop
+    for Drain<'a, T, A, S, B>
+{
+    fn drop(&mut self) {
+        while let Some(node) = self.keys.next() {
+            self.take(node);
+        }
+        // Hand the emptied map back so the tree keeps its capacity
+        self.node_data.clear();
+        self.tree.node_data = mem::take(&mut self.node_data);
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Removes every node from the tree, returning an iterator over their contents in order.
+    /// All existing NodeKeys are invalidated.
+    pub fn drain(&mut self) -> Drain<'_, T, A, S, B> {
+        self.log_delete_all();
+        let keys: Vec<NodeKey> = self.iter_with_keys().map(|(node, _)| node).collect();
+        // The tree is emptied up front, so that leaking the iterator leaves it empty and valid
+        let node_data = mem::take(&mut self.node_data);
+        self.bump_generation();
+        self.nodes.clear();
+        self.augmented.clear();
+        self.clear_side_data();
+        self.root = None;
+        self.len = 0;
+        self.leftmost = None;
+        self.rightmost = None;
+        Drain {
+            tree: self,
+            keys: keys.into_iter(),
+            node_data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_test() {
+        let mut tree: Tree<usize> = (0..10).collect();
+        let capacity = tree.capacity();
+        let mut drain = tree.drain();
+        assert_eq!(drain.len(), 10);
+        assert_eq!(drain.next(), Some(0));
+        assert_eq!(drain.next_back(), Some(9));
+        assert_eq!(
+            drain.by_ref().collect::<Vec<_>>(),
+            (1..9).collect::<Vec<_>>()
+        );
+        assert_eq!(drain.next(), None);
+        drop(drain);
+        assert!(tree.is_empty());
+        assert_eq!(tree.root, None);
+        assert_eq!(tree.capacity(), capacity);
+
+        tree.extend(0..5);
+        assert_eq!(tree.drain().take(2).collect::<Vec<_>>(), vec![0, 1]);
+        assert!(tree.is_empty());
+        assert_eq!(tree.get_leftmost_node(), None);
+
+        // A leaked drain leaves the tree empty rather than broken
+        tree.extend(0..5);
+        let mut drain = tree.drain();
+        assert_eq!(drain.next(), Some(0));
+        std::mem::forget(drain);
+        assert!(tree.is_empty());
+        assert!(tree.to_vec().is_empty());
+        tree.assert_valid();
+        tree.extend(0..3);
+        assert_eq!(tree.to_vec(), vec![0, 1, 2]);
+    }
+}
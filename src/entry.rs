@@ -0,0 +1,252 @@
+use crate::{Augment, Balance, InsertPosition, Node, NodeKey, NodeStore, RedBlack, Tree};
+use slotmap::SlotMap;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A view into a position in the tree found by a comparison guided descent, which is either
+/// occupied by a matching node or vacant.
+pub enum Entry<
+    'a,
+    T: Clone + fmt::Debug,
+    A: Augment<T> = (),
+    S: NodeStore<Node> = SlotMap<NodeKey, Node>,
+    B: Balance = RedBlack,
+> {
+    Occupied(OccupiedEntry<'a, T, A, S, B>),
+    Vacant(VacantEntry<'a, T, A, S, B>),
+}
+
+/// An entry for a node that matched the comparison
+pub struct OccupiedEntry<
+    'a,
+    T: Clone + fmt::Debug,
+    A: Augment<T> = (),
+    S: NodeStore<Node> = SlotMap<NodeKey, Node>,
+    B: Balance = RedBlack,
+> {
+    tree: &'a mut Tree<T, A, S, B>,
+    node: NodeKey,
+}
+
+/// An entry for the position a matching node would be inserted at
+pub struct VacantEntry<
+    'a,
+    T: Clone + fmt::Debug,
+    A: Augment<T> = (),
+    S: NodeStore<Node> = SlotMap<NodeKey, Node>,
+    B: Balance = RedBlack,
+> {
+    tree: &'a mut Tree<T, A, S, B>,
+    position: InsertPosition,
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    Entry<'a, T, A, S, B>
+{
+    /// Returns the NodeKey of the matching node, or None if the entry is vacant
+    pub fn key(&self) -> Option<NodeKey> {
+        match self {
+            Entry::Occupied(entry) => Some(entry.key()),
+            Entry::Vacant(_) => None,
+        }
+    }
+
+    /// Inserts `value` if the entry is vacant.
+    /// Returns the NodeKey of the matching or newly inserted node.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to populate the new node with
+    ///
+    pub fn or_insert(self, value: T) -> NodeKey {
+        self.or_insert_with(|| value)
+    }
+
+    /// Inserts the result of `default` if the entry is vacant.
+    /// Returns the NodeKey of the matching or newly inserted node.
+    ///
+    /// # Arguments
+    ///
+    /// * `default` - Function called to create the value of the new node
+    ///
+    pub fn or_insert_with<F: FnOnce() -> T>(self, default: F) -> NodeKey {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Calls `f` on the contents of the matching node if the entry is occupied.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Function called with the contents of the matching node
+    ///
+    pub fn and_modify<F: FnOnce(&mut T)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+            entry.tree.update_augmented(entry.node);
+        }
+        self
+    }
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    OccupiedEntry<'a, T, A, S, B>
+{
+    /// Returns the NodeKey of the matching node
+    pub fn key(&self) -> NodeKey {
+        self.node
+    }
+
+    /// Returns a reference to the contents of the matching node
+    pub fn get(&self) -> &T {
+        self.tree.get_contents(self.node)
+    }
+
+    /// Returns a mutable reference to the contents of the matching node.
+    /// If the tree is augmented, `Tree::update_augmented` must be called after modifying them.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.tree.get_mut_contents(self.node)
+    }
+
+    /// Converts the entry into a mutable reference to the contents of the matching node.
+    /// If the tree is augmented, `Tree::update_augmented` must be called after modifying them.
+    pub fn into_mut(self) -> &'a mut T {
+        self.tree.get_mut_contents(self.node)
+    }
+
+    /// Replaces the contents of the matching node, returning the old contents
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The new contents for the node
+    ///
+    pub fn insert(&mut self, value: T) -> T {
+        self.tree.replace(self.node, value)
+    }
+
+    /// Deletes the matching node from the tree, returning its contents
+    pub fn remove(self) -> T {
+        self.tree.delete_node(self.node)
+    }
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    VacantEntry<'a, T, A, S, B>
+{
+    /// Inserts a new node at the entry's position and returns its NodeKey
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to populate the new node with
+    ///
+    pub fn insert(self, value: T) -> NodeKey {
+        self.tree.insert_at_position(self.position, value)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Descends the tree using a comparison function and returns the entry where it stops.
+    /// The tree must be ordered consistently with the comparison function, which follows the same
+    /// convention as `slice::binary_search_by`.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Returns the ordering of a node's contents relative to the target
+    ///
+    pub fn entry_by<F: FnMut(&T) -> Ordering>(&mut self, mut f: F) -> Entry<'_, T, A, S, B> {
+        let mut position = InsertPosition::Root;
+        let mut node = self.root;
+        while let Some(current) = node {
+            match f(self.get_contents(current)) {
+                Ordering::Less => {
+                    position = InsertPosition::After(current);
+                    node = self.get_right(current);
+                }
+                Ordering::Greater => {
+                    position = InsertPosition::Before(current);
+                    node = self.get_left(current);
+                }
+                Ordering::Equal => {
+                    return Entry::Occupied(OccupiedEntry {
+                        tree: self,
+                        node: current,
+                    })
+                }
+            }
+        }
+        Entry::Vacant(VacantEntry {
+            tree: self,
+            position,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_test() {
+        let mut tree: Tree<(usize, usize)> = Tree::new();
+        for &key in [5, 3, 8, 1, 4, 7, 9, 2, 6].iter() {
+            let node = tree.entry_by(|&(k, _)| k.cmp(&key)).or_insert((key, 0));
+            assert_eq!(tree.get_contents(node).0, key);
+        }
+        assert_eq!(tree.len(), 9);
+        assert_eq!(
+            tree.get_nodes_order(),
+            "(1, 0) (2, 0) (3, 0) (4, 0) (5, 0) (6, 0) (7, 0) (8, 0) (9, 0) "
+        );
+
+        let node = tree
+            .entry_by(|&(k, _)| k.cmp(&4))
+            .and_modify(|(_, count)| *count += 1)
+            .or_insert((4, 0));
+        assert_eq!(tree.get_contents(node), &(4, 1));
+        assert_eq!(tree.len(), 9);
+
+        match tree.entry_by(|&(k, _)| k.cmp(&10)) {
+            Entry::Occupied(_) => panic!("10 is not in the tree"),
+            Entry::Vacant(entry) => {
+                entry.insert((10, 0));
+            }
+        }
+        assert_eq!(
+            tree.get_contents(tree.get_rightmost_node().unwrap()),
+            &(10, 0)
+        );
+
+        match tree.entry_by(|&(k, _)| k.cmp(&1)) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.insert((1, 5)), (1, 0));
+                assert_eq!(entry.remove(), (1, 5));
+            }
+            Entry::Vacant(_) => panic!("1 is in the tree"),
+        }
+        assert_eq!(tree.len(), 9);
+        assert_eq!(tree.entry_by(|&(k, _)| k.cmp(&1)).key(), None);
+    }
+
+    #[test]
+    fn entry_transaction_test() {
+        let mut tree: Tree<(usize, usize)> = (0..10).map(|key| (key, 0)).collect();
+        let result: Result<(), _> = tree.transaction(|tx| {
+            tx.entry_by(|&(k, _)| k.cmp(&3))
+                .and_modify(|(_, count)| *count += 1);
+            if let Entry::Occupied(mut entry) = tx.entry_by(|&(k, _)| k.cmp(&5)) {
+                entry.insert((5, 7));
+            }
+            if let Entry::Occupied(mut entry) = tx.entry_by(|&(k, _)| k.cmp(&8)) {
+                entry.get_mut().1 = 9;
+            }
+            Err(())
+        });
+        assert!(result.is_err());
+        assert_eq!(
+            tree.to_vec(),
+            (0..10).map(|key| (key, 0)).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use crate::NodeKey;
+use std::error::Error;
+use std::fmt;
+
+/// The errors that can be returned by the fallible tree operations.
+///
+/// Deleting a node with two children is not an error, the node is swapped with its successor
+/// first, and every node in the tree's arena is linked into the tree, so a NodeKey that does not
+/// refer to a node in the tree is reported as `StaleKey`. Stale keys are told apart by the version
+/// of their slot in the arena though, so the old keys of the nodes moved by `shrink_to` and
+/// `compact`, which rebuild the arena, may refer to other nodes afterwards and have to be replaced
+/// using the returned map.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum TreeError {
+    /// The NodeKey does not refer to a node in the tree, it has most likely been deleted
+    StaleKey(NodeKey),
+    /// A root node was created for a tree that already has one
+    RootAlreadyExists,
+    /// The structure of the tree has changed since a saved position was taken from it
+    Invalidated,
+}
+
+impl fmt::Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TreeError::StaleKey(node) => {
+                write!(f, "{:?} does not refer to a node in the tree", node)
+            }
+            TreeError::RootAlreadyExists => write!(f, "the tree already has a root node"),
+            TreeError::Invalidated => {
+                write!(f, "the tree has been modified since the position was saved")
+            }
+        }
+    }
+}
+
+impl Error for TreeError {}
+
+/// The error returned by `transaction` when its closure fails, holding the closure's error.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum TransactionError<E> {
+    /// The changes made by the closure were rolled back
+    RolledBack(E),
+    /// The closure ran an operation that can't be rolled back, such as `compact`, so the changes
+    /// it made were kept
+    NotRolledBack(E),
+}
+
+impl<E> TransactionError<E> {
+    /// Returns the error returned by the closure
+    pub fn into_inner(self) -> E {
+        match self {
+            TransactionError::RolledBack(error) | TransactionError::NotRolledBack(error) => error,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for TransactionError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransactionError::RolledBack(error) => {
+                write!(f, "the transaction was rolled back: {}", error)
+            }
+            TransactionError::NotRolledBack(error) => {
+                write!(
+                    f,
+                    "the transaction failed and could not be rolled back: {}",
+                    error
+                )
+            }
+        }
+    }
+}
+
+impl<E: Error> Error for TransactionError<E> {}
@@ -0,0 +1,250 @@
+use crate::{Augment, Balance, Node, NodeStore, Tree};
+use std::cmp::{self, Ordering};
+use std::fmt;
+use std::iter::{self, FromIterator};
+
+/// A read-only copy of a sequence laid out in a single array in the Eytzinger order, the order of
+/// a breadth first walk of a complete binary tree. The children of the value at position `k` are
+/// at `2k` and `2k + 1`, so a search needs no links and touches the top levels of the tree, which
+/// stay in cache, far more often than the rest.
+///
+/// It is meant for workloads that build a sequence in one phase and only query it in the next:
+/// build a `Tree`, convert it with `Tree::to_eytzinger` for the queries, and convert it back with
+/// `to_tree` if it has to change again.
+#[derive(PartialEq, Eq, Clone)]
+pub struct EytzingerTree<T> {
+    // The value at position k is stored at index k - 1
+    values: Vec<T>,
+}
+
+impl<T> EytzingerTree<T> {
+    /// Returns the number of values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if there are no values
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the first value, or None if there are no values
+    pub fn first(&self) -> Option<&T> {
+        self.first_position().map(|position| self.at(position))
+    }
+
+    /// Returns the last value, or None if there are no values
+    pub fn last(&self) -> Option<&T> {
+        let mut position = 1;
+        if position > self.len() {
+            return None;
+        }
+        while 2 * position < self.len() {
+            position = 2 * position + 1;
+        }
+        Some(self.at(position))
+    }
+
+    /// Returns the value at an index of the sequence, or None if the index is out of range. Takes
+    /// O(log² n) time, as the sizes of the subtrees are worked out rather than stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the value to return
+    ///
+    pub fn nth(&self, mut index: usize) -> Option<&T> {
+        let mut position = 1;
+        while position <= self.len() {
+            let left_size = self.subtree_size(2 * position);
+            match index.cmp(&left_size) {
+                Ordering::Less => position *= 2,
+                Ordering::Equal => return Some(self.at(position)),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    position = 2 * position + 1;
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the first value that is not less than the target, or None if every value is less
+    /// than the target. The values must be ordered consistently with the comparison function.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Returns the ordering of a value relative to the target
+    ///
+    pub fn lower_bound<F: FnMut(&T) -> Ordering>(&self, mut f: F) -> Option<&T> {
+        let mut position = 1;
+        while position <= self.len() {
+            position = 2 * position + (f(self.at(position)) == Ordering::Less) as usize;
+        }
+        // The walk ends below the answer, after one step left and then only steps right
+        position >>= position.trailing_ones() + 1;
+        if position == 0 {
+            None
+        } else {
+            Some(self.at(position))
+        }
+    }
+
+    /// Binary searches the values using a comparison function, following the same convention as
+    /// `slice::binary_search_by`. Returns the index of a matching value, or the index a matching
+    /// value could be inserted at to keep the values ordered.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Returns the ordering of a value relative to the target
+    ///
+    pub fn binary_search_by<F: FnMut(&T) -> Ordering>(&self, mut f: F) -> Result<usize, usize> {
+        let mut position = 1;
+        let mut rank = 0;
+        while position <= self.len() {
+            match f(self.at(position)) {
+                Ordering::Less => {
+                    rank += self.subtree_size(2 * position) + 1;
+                    position = 2 * position + 1;
+                }
+                Ordering::Greater => position *= 2,
+                Ordering::Equal => return Ok(rank + self.subtree_size(2 * position)),
+            }
+        }
+        Err(rank)
+    }
+
+    /// Returns an iterator over the values in order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        iter::successors(self.first_position(), move |&position| {
+            self.next_position(position)
+        })
+        .map(move |position| self.at(position))
+    }
+
+    fn at(&self, position: usize) -> &T {
+        &self.values[position - 1]
+    }
+
+    // The position of the first value in order
+    fn first_position(&self) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut position = 1;
+        while 2 * position <= self.len() {
+            position *= 2;
+        }
+        Some(position)
+    }
+
+    // The position of the value after the one at the given position
+    fn next_position(&self, mut position: usize) -> Option<usize> {
+        if 2 * position < self.len() {
+            position = 2 * position + 1;
+            while 2 * position <= self.len() {
+                position *= 2;
+            }
+        } else {
+            // Climb past the ancestors this subtree is on the right of
+            position >>= position.trailing_ones() + 1;
+        }
+        Some(position).filter(|&position| position != 0)
+    }
+
+    // The number of values in the subtree rooted at a position, one level at a time
+    fn subtree_size(&self, position: usize) -> usize {
+        let (mut first, mut last) = (position, position);
+        let mut size = 0;
+        while first <= self.len() {
+            size += cmp::min(last, self.len()) - first + 1;
+            first *= 2;
+            last = 2 * last + 1;
+        }
+        size
+    }
+}
+
+impl<T: Ord> EytzingerTree<T> {
+    /// Binary searches the values for a target, following the same convention as
+    /// `slice::binary_search`
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The value to search for
+    ///
+    pub fn binary_search(&self, target: &T) -> Result<usize, usize> {
+        self.binary_search_by(|value| value.cmp(target))
+    }
+}
+
+impl<T: Clone + fmt::Debug> EytzingerTree<T> {
+    /// Returns a tree holding a copy of the values in order
+    pub fn to_tree<A: Augment<T> + Default, S: NodeStore<Node> + Default, B: Balance>(
+        &self,
+    ) -> Tree<T, A, S, B> {
+        Tree::from_sorted_iter(self.iter().cloned())
+    }
+}
+
+/// Lays out the values in the order they are iterated
+impl<T> FromIterator<T> for EytzingerTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut in_order: Vec<_> = iter.into_iter().map(Some).collect();
+        let empty = EytzingerTree {
+            values: vec![(); in_order.len()],
+        };
+        let positions = iter::successors(empty.first_position(), |&position| {
+            empty.next_position(position)
+        });
+        let mut values: Vec<Option<T>> = iter::repeat_with(|| None).take(in_order.len()).collect();
+        for (value, position) in in_order.iter_mut().zip(positions) {
+            values[position - 1] = value.take();
+        }
+        EytzingerTree {
+            values: values.into_iter().map(Option::unwrap).collect(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for EytzingerTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Returns a copy of the contents of the tree in a compact array layout for fast searching
+    pub fn to_eytzinger(&self) -> EytzingerTree<T> {
+        self.to_vec().into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eytzinger_test() {
+        for len in 0..40u32 {
+            let tree: Tree<u32> = (0..len).map(|i| i * 2).collect();
+            let eytzinger = tree.to_eytzinger();
+            assert_eq!(eytzinger.len(), len as usize);
+            assert_eq!(eytzinger.iter().copied().collect::<Vec<_>>(), tree.to_vec());
+            assert_eq!(eytzinger.first(), tree.to_vec().first());
+            assert_eq!(eytzinger.last(), tree.to_vec().last());
+            for i in 0..len {
+                assert_eq!(eytzinger.nth(i as usize), Some(&(i * 2)));
+                assert_eq!(eytzinger.binary_search(&(i * 2)), Ok(i as usize));
+                assert_eq!(eytzinger.binary_search(&(i * 2 + 1)), Err(i as usize + 1));
+                assert_eq!(eytzinger.lower_bound(|x| x.cmp(&(i * 2))), Some(&(i * 2)));
+                let next = Some(i * 2 + 2).filter(|&x| x < len * 2);
+                assert_eq!(
+                    eytzinger.lower_bound(|x| x.cmp(&(i * 2 + 1))),
+                    next.as_ref()
+                );
+            }
+            assert_eq!(eytzinger.nth(len as usize), None);
+            assert_eq!(eytzinger.to_tree::<(), _, _>(), tree);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! A C interface to a tree of opaque pointers, declared in `include/binary_search_tree.h`. A
+//! static or dynamic library to link against is built with
+//! `cargo rustc --release --lib --features ffi --crate-type staticlib` or `--crate-type cdylib`.
+//!
+//! Building with the ffi feature also generates the header into the build's output directory,
+//! and a test checks that the committed copy matches it. After changing this module, regenerate
+//! the committed header with `cbindgen --output include/binary_search_tree.h`.
+//!
+//! Nodes are identified by 64 bit ids, with 0 standing for no node. The functions that take a
+//! node return 0 or NULL when given an id that isn't in the tree rather than panicking, as a
+//! panic can't unwind into C.
+
+use crate::{NodeKey, Tree};
+use slotmap::KeyData;
+use std::os::raw::c_void;
+use std::ptr;
+
+/// An opaque handle to a tree holding a pointer in every node, created with `bst_tree_new` and
+/// destroyed with `bst_tree_free`
+pub struct BstTree(Tree<*mut c_void>);
+
+/// Called by `bst_tree_for_each` with the id and contents of each node along with the user data
+pub type BstVisitFn = extern "C" fn(node: u64, contents: *mut c_void, user_data: *mut c_void);
+
+// Occupied slots always have an odd version, so no key converts to 0
+fn to_id(node: Option<NodeKey>) -> u64 {
+    node.map_or(0, |node| KeyData::from(node).as_ffi())
+}
+
+// Returns the node with the given id if it is in the tree
+fn to_node(tree: &BstTree, id: u64) -> Option<NodeKey> {
+    if id == 0 {
+        return None;
+    }
+    let node = NodeKey::from(KeyData::from_ffi(id));
+    Some(node).filter(|&node| tree.0.contains_node(node))
+}
+
+/// Creates an empty tree, which must be destroyed with `bst_tree_free`
+#[no_mangle]
+pub extern "C" fn bst_tree_new() -> *mut BstTree {
+    Box::into_raw(Box::new(BstTree(Tree::new())))
+}
+
+/// Destroys a tree. The pointers held in its nodes are not freed.
+///
+/// # Safety
+///
+/// `tree` must have been returned by `bst_tree_new` and not already freed, or be NULL.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_free(tree: *mut BstTree) {
+    if !tree.is_null() {
+        drop(Box::from_raw(tree));
+    }
+}
+
+/// Returns the number of nodes in the tree
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_len(tree: *const BstTree) -> usize {
+    (*tree).0.len()
+}
+
+/// Adds the first node to an empty tree, returning its id, or 0 if the tree isn't empty
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_create_root(tree: *mut BstTree, contents: *mut c_void) -> u64 {
+    to_id((*tree).0.try_create_root(contents).ok())
+}
+
+/// Inserts a node before an existing node in the sequence, returning the id of the new node, or
+/// 0 if the existing node isn't in the tree
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_insert_before(
+    tree: *mut BstTree,
+    node: u64,
+    contents: *mut c_void,
+) -> u64 {
+    let tree = &mut *tree;
+    to_id(to_node(tree, node).map(|node| tree.0.insert_before(node, contents)))
+}
+
+/// Inserts a node after an existing node in the sequence, returning the id of the new node, or 0
+/// if the existing node isn't in the tree
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_insert_after(
+    tree: *mut BstTree,
+    node: u64,
+    contents: *mut c_void,
+) -> u64 {
+    let tree = &mut *tree;
+    to_id(to_node(tree, node).map(|node| tree.0.insert_after(node, contents)))
+}
+
+/// Deletes a node from the tree, returning the pointer it held, or NULL if it isn't in the tree
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_delete(tree: *mut BstTree, node: u64) -> *mut c_void {
+    let tree = &mut *tree;
+    to_node(tree, node).map_or(ptr::null_mut(), |node| tree.0.delete_node(node))
+}
+
+/// Returns the pointer held by a node, or NULL if it isn't in the tree
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_get(tree: *const BstTree, node: u64) -> *mut c_void {
+    let tree = &*tree;
+    to_node(tree, node).map_or(ptr::null_mut(), |node| *tree.0.get_contents(node))
+}
+
+/// Returns the id of the first node in the sequence, or 0 if the tree is empty
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_first(tree: *const BstTree) -> u64 {
+    to_id((*tree).0.get_leftmost_node())
+}
+
+/// Returns the id of the last node in the sequence, or 0 if the tree is empty
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_last(tree: *const BstTree) -> u64 {
+    to_id((*tree).0.get_rightmost_node())
+}
+
+/// Returns the id of the node after a node in the sequence, or 0 if there is none
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_next(tree: *const BstTree, node: u64) -> u64 {
+    let tree = &*tree;
+    to_id(to_node(tree, node).and_then(|node| tree.0.get_next(node)))
+}
+
+/// Returns the id of the node before a node in the sequence, or 0 if there is none
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_prev(tree: *const BstTree, node: u64) -> u64 {
+    let tree = &*tree;
+    to_id(to_node(tree, node).and_then(|node| tree.0.get_prev(node)))
+}
+
+/// Calls a function with the id and contents of every node, in order
+///
+/// # Safety
+///
+/// `tree` must be a live tree returned by `bst_tree_new`, which the callback must not modify.
+#[no_mangle]
+pub unsafe extern "C" fn bst_tree_for_each(
+    tree: *const BstTree,
+    visit: BstVisitFn,
+    user_data: *mut c_void,
+) {
+    for (node, &contents) in (*tree).0.iter_with_keys() {
+        visit(to_id(Some(node)), contents, user_data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern "C" fn collect(_node: u64, contents: *mut c_void, user_data: *mut c_void) {
+        let values = unsafe { &mut *(user_data as *mut Vec<usize>) };
+        values.push(contents as usize);
+    }
+
+    #[test]
+    fn ffi_test() {
+        unsafe {
+            let tree = bst_tree_new();
+            let root = bst_tree_create_root(tree, 20 as *mut c_void);
+            assert_ne!(root, 0);
+            assert_eq!(bst_tree_create_root(tree, ptr::null_mut()), 0);
+            let first = bst_tree_insert_before(tree, root, 10 as *mut c_void);
+            let last = bst_tree_insert_after(tree, root, 30 as *mut c_void);
+            assert_eq!(bst_tree_len(tree), 3);
+            assert_eq!(bst_tree_first(tree), first);
+            assert_eq!(bst_tree_last(tree), last);
+            assert_eq!(bst_tree_next(tree, first), root);
+            assert_eq!(bst_tree_prev(tree, first), 0);
+
+            let mut values: Vec<usize> = vec![];
+            bst_tree_for_each(tree, collect, &mut values as *mut Vec<usize> as *mut c_void);
+            assert_eq!(values, vec![10, 20, 30]);
+
+            assert_eq!(bst_tree_delete(tree, root) as usize, 20);
+            // Stale ids are rejected rather than panicking
+            assert!(bst_tree_get(tree, root).is_null());
+            assert_eq!(bst_tree_insert_after(tree, root, ptr::null_mut()), 0);
+            assert_eq!(bst_tree_get(tree, last) as usize, 30);
+            bst_tree_free(tree);
+        }
+    }
+
+    #[test]
+    fn header_test() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/binary_search_tree.h"));
+        let committed = include_str!("../include/binary_search_tree.h");
+        assert!(
+            generated == committed,
+            "include/binary_search_tree.h is out of date, regenerate it with cbindgen"
+        );
+    }
+}
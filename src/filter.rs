@@ -0,0 +1,145 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, RedBlack, Tree};
+use slotmap::SlotMap;
+use std::fmt;
+
+/// An iterator that removes the nodes whose contents satisfy a predicate, yielding their contents
+/// in order. Nodes are only visited as the iterator is advanced, so any nodes after the point
+/// where the iterator is dropped are left in the tree.
+pub struct ExtractIf<
+    'a,
+    T: Clone + fmt::Debug,
+    A: Augment<T>,
+    F: FnMut(&T) -> bool,
+    S: NodeStore<Node> = SlotMap<NodeKey, Node>,
+    B: Balance = RedBlack,
+> {
+    tree: &'a mut Tree<T, A, S, B>,
+    next: Option<NodeKey>,
+    predicate: F,
+}
+
+impl<'a, T, A, F, S: NodeStore<Node>, B: Balance> Iterator for ExtractIf<'a, T, A, F, S, B>
+where
+    T: Clone + fmt::Debug,
+    A: Augment<T>,
+    F: FnMut(&T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while let Some(current) = self.next {
+            self.next = self.tree.get_next(current);
+            if (self.predicate)(self.tree.get_contents(current)) {
+                return Some(self.tree.delete_node(current));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.tree.len()))
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Removes every node whose contents do not satisfy the predicate, keeping the order of the
+    /// remaining nodes. The predicate is called once for each node, in order.
+    ///
+    /// Takes time proportional to the number of nodes as the remaining nodes are rebuilt into a
+    /// perfectly balanced tree in a single pass. The NodeKeys of the remaining nodes stay valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `predicate` - Returns true for the contents of the nodes to keep
+    ///
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut predicate: F) {
+        let mut kept: Vec<NodeKey> = Vec::with_capacity(self.len);
+        let mut removed = vec![];
+        let mut node = self.get_leftmost_node();
+        while let Some(current) = node {
+            node = self.get_next(current);
+            if predicate(self.get_contents(current)) {
+                kept.push(current);
+            } else {
+                removed.push(current);
+            }
+        }
+        // The nodes are only removed once the walk is done, as finding the next node may need the
+        // links of the nodes before it. They are logged last node first, so that a rollback puts
+        // the first node back first
+        for &node in removed.iter().rev() {
+            self.log_delete(node);
+        }
+        for node in removed {
+            self.notify_delete(node);
+            self.nodes.remove(node);
+            self.node_data.remove(node);
+            self.augmented.remove(node);
+            self.remove_side_data(node);
+        }
+        if kept.len() != self.len {
+            self.link_balanced(&kept);
+        }
+    }
+
+    /// Returns an iterator that walks the tree in order, removing the nodes whose contents satisfy
+    /// the predicate and yielding their contents. The NodeKeys of the remaining nodes stay valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `predicate` - Returns true for the contents of the nodes to remove
+    ///
+    pub fn extract_if<F: FnMut(&T) -> bool>(
+        &mut self,
+        predicate: F,
+    ) -> ExtractIf<'_, T, A, F, S, B> {
+        ExtractIf {
+            next: self.leftmost,
+            tree: self,
+            predicate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retain_test() {
+        let mut tree: Tree<usize> = (0..100).collect();
+        let seven = tree.nth(7).unwrap();
+        let eight = tree.nth(8).unwrap();
+        tree.retain(|x| x % 3 != 2);
+        tree.assert_valid();
+        assert_eq!(
+            tree.to_vec(),
+            (0..100).filter(|x| x % 3 != 2).collect::<Vec<_>>()
+        );
+        assert_eq!(tree.get_contents(seven), &7);
+        assert!(!tree.contains_node(eight));
+
+        tree.retain(|_| true);
+        assert_eq!(tree.len(), 67);
+        tree.retain(|_| false);
+        tree.assert_valid();
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn extract_if_test() {
+        let mut tree: Tree<usize> = (0..50).collect();
+        let five = tree.nth(5).unwrap();
+        let evens: Vec<usize> = tree.extract_if(|x| x % 2 == 0).collect();
+        assert_eq!(evens, (0..50).step_by(2).collect::<Vec<_>>());
+        assert_eq!(tree.to_vec(), (1..50).step_by(2).collect::<Vec<_>>());
+        assert_eq!(tree.get_contents(five), &5);
+        tree.assert_valid();
+
+        // Stopping early leaves the unvisited nodes in place
+        assert_eq!(tree.extract_if(|x| x % 5 == 0).next(), Some(5));
+        assert_eq!(tree.len(), 24);
+        assert_eq!(tree.get_contents(tree.nth(7).unwrap()), &17);
+        tree.assert_valid();
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{Augment, Balance, Color, Node, NodeKey, NodeStore, Tree};
+use slotmap::SecondaryMap;
+use std::fmt;
+
+// Formats a node and its subtree, the children are nested inside their parents
+struct DebugNode<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> {
+    tree: &'a Tree<T, A, S, B>,
+    node: NodeKey,
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> fmt::Debug
+    for DebugNode<'a, T, A, S, B>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let child = |node: Option<NodeKey>| {
+            node.map(|node| DebugNode {
+                tree: self.tree,
+                node,
+            })
+        };
+        f.debug_struct("Node")
+            .field("key", &self.node)
+            .field("contents", self.tree.get_contents(self.node))
+            .field("color", &self.tree.get_color(Some(self.node)))
+            .field("left", &child(self.tree.get_left(self.node)))
+            .field("right", &child(self.tree.get_right(self.node)))
+            .finish()
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> fmt::Debug
+    for Tree<T, A, S, B>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let root = self.root.map(|node| DebugNode { tree: self, node });
+        f.debug_struct("Tree")
+            .field("len", &self.len)
+            .field("root", &root)
+            .finish()
+    }
+}
+
+/// Renders the tree sideways, with the root on the left and the right subtree above it, e.g.
+///
+/// ```text
+///     [R] 3
+/// [B] 2
+///     [R] 1
+/// ```
+impl<T: Clone + fmt::Debug + fmt::Display, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    fmt::Display for Tree<T, A, S, B>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_sideways(f, self.root, 0, &|f, contents| write!(f, "{}", contents))
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Renders the tree sideways in the same way as `Display`, using the `Debug` representation
+    /// of the contents of the nodes
+    pub fn pretty_print(&self) -> String {
+        struct Sideways<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance>(
+            &'a Tree<T, A, S, B>,
+        );
+
+        impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> fmt::Display
+            for Sideways<'a, T, A, S, B>
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let tree = self.0;
+                tree.write_sideways(f, tree.root, 0, &|f, contents| write!(f, "{:?}", contents))
+            }
+        }
+
+        Sideways(self).to_string()
+    }
+
+    /// Returns a Mermaid flowchart of the tree structure, with the nodes labelled by the `Debug`
+    /// representation of their contents and styled by their colour. Nodes are named by their
+    /// position in the sequence and the edges to children are labelled L and R.
+    pub fn to_mermaid(&self) -> String {
+        let mut ids = SecondaryMap::with_capacity(self.len);
+        let mut node = self.leftmost;
+        while let Some(current) = node {
+            ids.insert(current, ids.len());
+            node = self.get_next(current);
+        }
+
+        let mut mermaid = String::from("flowchart TD\n");
+        mermaid.push_str("    classDef red fill:#d33,stroke:#900,color:#fff\n");
+        mermaid.push_str("    classDef black fill:#222,stroke:#000,color:#fff\n");
+        let mut node = self.leftmost;
+        while let Some(current) = node {
+            let label = format!("{:?}", self.get_contents(current)).replace('"', "#quot;");
+            let class = match self.get_color(Some(current)) {
+                Color::Red => "red",
+                Color::Black => "black",
+            };
+            mermaid.push_str(&format!(
+                "    n{}[\"{}\"]:::{}\n",
+                ids[current], label, class
+            ));
+            node = self.get_next(current);
+        }
+
+        // List the edges parent first so that Mermaid lays the tree out from the root down
+        let mut stack: Vec<NodeKey> = self.root.into_iter().collect();
+        while let Some(current) = stack.pop() {
+            let children = [
+                ("L", self.get_left(current)),
+                ("R", self.get_right(current)),
+            ];
+            for &(side, child) in children.iter() {
+                if let Some(child) = child {
+                    mermaid.push_str(&format!(
+                        "    n{} -->|{}| n{}\n",
+                        ids[current], side, ids[child]
+                    ));
+                }
+            }
+            stack.extend(self.get_right(current));
+            stack.extend(self.get_left(current));
+        }
+        mermaid
+    }
+
+    // Writes a subtree sideways, one node per line indented by its depth
+    fn write_sideways<F>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        node: Option<NodeKey>,
+        depth: usize,
+        write_contents: &F,
+    ) -> fmt::Result
+    where
+        F: Fn(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+    {
+        let node = match node {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+        self.write_sideways(f, self.get_right(node), depth + 1, write_contents)?;
+        let marker = match self.get_color(Some(node)) {
+            Color::Red => 'R',
+            Color::Black => 'B',
+        };
+        write!(f, "{:indent$}[{}] ", "", marker, indent = depth * 4)?;
+        write_contents(f, self.get_contents(node))?;
+        writeln!(f)?;
+        self.write_sideways(f, self.get_left(node), depth + 1, write_contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_test() {
+        let empty: Tree<usize> = Tree::new();
+        assert_eq!(format!("{:?}", empty), "Tree { len: 0, root: None }");
+
+        let mut tree: Tree<usize> = Tree::new();
+        let two = tree.create_root(2);
+        let one = tree.insert_before(two, 1);
+        let expected = format!(
+            "Tree {{ len: 2, root: Some(Node {{ key: {:?}, contents: 2, color: Black, \
+             left: Some(Node {{ key: {:?}, contents: 1, color: Red, left: None, right: None }}), \
+             right: None }}) }}",
+            two, one
+        );
+        assert_eq!(format!("{:?}", tree), expected);
+        assert!(format!("{:#?}", tree).contains("\n    root: Some(\n        Node {\n"));
+    }
+
+    #[test]
+    fn display_test() {
+        let tree: Tree<usize> = (1..=3).collect();
+        assert_eq!(tree.to_string(), "    [R] 3\n[B] 2\n    [R] 1\n");
+        assert_eq!(Tree::<usize>::new().to_string(), "");
+
+        let tree: Tree<&str> = vec!["a", "b"].into_iter().collect();
+        assert_eq!(tree.to_string(), "[B] b\n    [R] a\n");
+        assert_eq!(tree.pretty_print(), "[B] \"b\"\n    [R] \"a\"\n");
+    }
+
+    #[test]
+    fn to_mermaid_test() {
+        let tree: Tree<&str> = vec!["a", "b", "c"].into_iter().collect();
+        let mermaid = tree.to_mermaid();
+        let lines: Vec<&str> = mermaid.lines().map(str::trim).collect();
+        assert_eq!(
+            lines[3..].to_vec(),
+            vec![
+                "n0[\"#quot;a#quot;\"]:::red",
+                "n1[\"#quot;b#quot;\"]:::black",
+                "n2[\"#quot;c#quot;\"]:::red",
+                "n1 -->|L| n0",
+                "n1 -->|R| n2",
+            ]
+        );
+        assert_eq!(lines[0], "flowchart TD");
+        assert_eq!(Tree::<usize>::new().to_mermaid().lines().count(), 3);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, RedBlack, Tree};
+use slotmap::SlotMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// An immutable copy of a tree made by `Tree::freeze`. Cloning a frozen tree only copies a
+/// pointer, so one copy can be handed to each of many reader threads while the original tree
+/// carries on being mutated. Every read-only method of `Tree` is available through `Deref`.
+///
+/// The frozen tree keeps the NodeKeys of the tree it was made from, so keys for nodes that
+/// existed at the time of the freeze can be used with it.
+pub struct FrozenTree<
+    T: Clone + fmt::Debug,
+    A: Augment<T> = (),
+    S = SlotMap<NodeKey, Node>,
+    B = RedBlack,
+> {
+    tree: Arc<Tree<T, A, S, B>>,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Clone
+    for FrozenTree<T, A, S, B>
+{
+    fn clone(&self) -> Self {
+        FrozenTree {
+            tree: Arc::clone(&self.tree),
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Deref
+    for FrozenTree<T, A, S, B>
+{
+    type Target = Tree<T, A, S, B>;
+
+    fn deref(&self) -> &Tree<T, A, S, B> {
+        &self.tree
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> fmt::Debug
+    for FrozenTree<T, A, S, B>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.tree.fmt(f)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T> + Clone, S: NodeStore<Node> + Clone, B: Balance>
+    FrozenTree<T, A, S, B>
+where
+    A::Value: Clone,
+{
+    /// Returns a mutable tree holding the same nodes, with the same NodeKeys. The nodes are only
+    /// copied if other copies of the frozen tree are still alive.
+    pub fn thaw(self) -> Tree<T, A, S, B> {
+        Arc::try_unwrap(self.tree).unwrap_or_else(|tree| (*tree).clone())
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T> + Clone, S: NodeStore<Node> + Clone, B: Balance>
+    Tree<T, A, S, B>
+where
+    A::Value: Clone,
+{
+    /// Returns an immutable copy of the tree that can be shared between threads. The copy is
+    /// made in time proportional to the number of nodes, after which the tree and the frozen
+    /// copy are independent. Callbacks and the undo history are not carried over.
+    pub fn freeze(&self) -> FrozenTree<T, A, S, B> {
+        FrozenTree {
+            tree: Arc::new(self.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn assert_send_sync<X: Send + Sync>(_: &X) {}
+
+    #[test]
+    fn freeze_test() {
+        let mut tree: Tree<u32> = (0..100).collect();
+        let first = tree.get_leftmost_node().unwrap();
+        let frozen = tree.freeze();
+        assert_send_sync(&frozen);
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let frozen = frozen.clone();
+                thread::spawn(move || frozen.to_vec().into_iter().sum::<u32>())
+            })
+            .collect();
+        tree.delete_node(first);
+        tree.insert_sorted(1000);
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), (0..100).sum::<u32>());
+        }
+
+        assert_eq!(*frozen.get_contents(first), 0);
+        assert_eq!(frozen.len(), 100);
+        assert_eq!(tree.len(), 100);
+        assert!(!tree.contains_node(first));
+
+        let mut thawed = frozen.thaw();
+        thawed.delete_node(first);
+        thawed.assert_valid();
+        assert_eq!(thawed.to_vec(), (1..100).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use petgraph::graph::{Graph, NodeIndex};
+use slotmap::SecondaryMap;
+use std::fmt;
+
+/// The kind of link an edge of a graph created by `Tree::to_petgraph` represents
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum TreeEdge {
+    /// From a parent to its left child
+    Left,
+    /// From a parent to its right child
+    Right,
+    /// From a node to the next node in the sequence
+    Next,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Returns a directed graph holding a copy of the contents of every node, with edges from
+    /// parents to their children and from every node to the next node in the sequence.
+    /// The graph nodes are added in order, so the index of a graph node is the position of the
+    /// tree node in the sequence. Returns the graph along with a map from NodeKeys to the indices
+    /// of their graph nodes.
+    pub fn to_petgraph(&self) -> (Graph<T, TreeEdge>, SecondaryMap<NodeKey, NodeIndex>) {
+        let mut graph = Graph::with_capacity(self.len, self.len * 2);
+        let mut indices = SecondaryMap::with_capacity(self.len);
+        let mut node = self.leftmost;
+        while let Some(current) = node {
+            indices.insert(current, graph.add_node(self.get_contents(current).clone()));
+            node = self.get_next(current);
+        }
+
+        for (node, &index) in indices.iter() {
+            if let Some(left) = self.get_left(node) {
+                graph.add_edge(index, indices[left], TreeEdge::Left);
+            }
+            if let Some(right) = self.get_right(node) {
+                graph.add_edge(index, indices[right], TreeEdge::Right);
+            }
+            if let Some(next) = self.get_next(node) {
+                graph.add_edge(index, indices[next], TreeEdge::Next);
+            }
+        }
+        (graph, indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::visit::EdgeRef;
+
+    #[test]
+    fn to_petgraph_test() {
+        let tree: Tree<u32> = (0..10).collect();
+        let (graph, indices) = tree.to_petgraph();
+        assert_eq!(graph.node_count(), 10);
+        // Every node but the root has a parent, and every node but the last has a next node
+        assert_eq!(graph.edge_count(), 9 + 9);
+
+        let root = indices[tree.root.unwrap()];
+        assert_eq!(graph[root], *tree.get_contents(tree.root.unwrap()));
+        for edge in graph.edges(root) {
+            let child = match edge.weight() {
+                TreeEdge::Left => tree.get_left(tree.root.unwrap()),
+                TreeEdge::Right => tree.get_right(tree.root.unwrap()),
+                TreeEdge::Next => tree.get_next(tree.root.unwrap()),
+            };
+            assert_eq!(edge.target(), indices[child.unwrap()]);
+        }
+        for (position, index) in graph.node_indices().enumerate() {
+            assert_eq!(graph[index], position as u32);
+        }
+    }
+}
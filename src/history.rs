@@ -0,0 +1,259 @@
+use crate::side_data::SideMap;
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use slotmap::SecondaryMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem;
+
+// The complete state of a tree's nodes at a checkpoint
+pub(crate) struct Snapshot<T, V, S> {
+    nodes: S,
+    node_data: SecondaryMap<NodeKey, T>,
+    augmented: SecondaryMap<NodeKey, V>,
+    root: Option<NodeKey>,
+    len: usize,
+    leftmost: Option<NodeKey>,
+    rightmost: Option<NodeKey>,
+    side_data: Vec<Box<dyn SideMap>>,
+}
+
+// The states a tree can be returned to with undo and redo, most recent last, and the most
+// checkpoints that undo can go back through
+pub(crate) struct History<T, V, S> {
+    undo: VecDeque<Snapshot<T, V, S>>,
+    redo: Vec<Snapshot<T, V, S>>,
+    limit: Option<usize>,
+}
+
+impl<T, V, S> Default for History<T, V, S> {
+    fn default() -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit: None,
+        }
+    }
+}
+
+impl<T, V, S> History<T, V, S> {
+    // Discards the oldest checkpoints until there are no more than the limit
+    fn trim(&mut self) {
+        if let Some(limit) = self.limit {
+            while self.undo.len() > limit {
+                self.undo.pop_front();
+            }
+        }
+    }
+}
+
+fn clone_map<V: Clone>(map: &SecondaryMap<NodeKey, V>) -> SecondaryMap<NodeKey, V> {
+    let mut copy = SecondaryMap::with_capacity(map.capacity());
+    for (node, value) in map.iter() {
+        copy.insert(node, value.clone());
+    }
+    copy
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node> + Clone, B: Balance> Tree<T, A, S, B>
+where
+    A::Value: Clone,
+{
+    /// Records the current state of the tree so that `undo` can return to it, discarding any
+    /// states that could be returned to with `redo`.
+    ///
+    /// History is opt-in, nothing is recorded until the first checkpoint. Each checkpoint copies
+    /// every node, taking O(n) time and holding O(n) memory until it is discarded, so they are best
+    /// made at the boundaries of user-visible edits rather than after every mutation. Use
+    /// `set_history_limit` to bound the memory held.
+    pub fn checkpoint(&mut self) {
+        let snapshot = self.snapshot();
+        self.history.undo.push_back(snapshot);
+        self.history.redo.clear();
+        self.history.trim();
+    }
+
+    /// Returns the tree to the state recorded by the most recent checkpoint, keeping the current
+    /// state so that `redo` can return to it. Returns false if there is no checkpoint to return
+    /// to.
+    ///
+    /// NodeKeys are restored along with the nodes, so keys from the restored state are valid
+    /// again while keys for nodes created since are stale. The delete callback is not called for
+    /// nodes that are undone.
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo.pop_back() {
+            Some(snapshot) => {
+                let current = self.restore(snapshot);
+                self.history.redo.push(current);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the tree to the state it was in before the most recent `undo`. Returns false if
+    /// there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo.pop() {
+            Some(snapshot) => {
+                let current = self.restore(snapshot);
+                self.history.undo.push_back(current);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if there is a checkpoint that `undo` can return to
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    /// Returns true if there is an undone state that `redo` can return to
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    // Copies the current state of the tree
+    pub(crate) fn snapshot(&self) -> Snapshot<T, A::Value, S> {
+        Snapshot {
+            nodes: self.nodes.clone(),
+            node_data: clone_map(&self.node_data),
+            augmented: clone_map(&self.augmented),
+            root: self.root,
+            len: self.len,
+            leftmost: self.leftmost,
+            rightmost: self.rightmost,
+            side_data: self.side_data.iter().map(|map| map.clone_box()).collect(),
+        }
+    }
+
+    // Replaces the state of the tree with a snapshot, returning the state it replaced
+    pub(crate) fn restore(
+        &mut self,
+        mut snapshot: Snapshot<T, A::Value, S>,
+    ) -> Snapshot<T, A::Value, S> {
+        self.poison_transaction();
+        self.bump_generation();
+        // Side data maps registered since the snapshot was taken are kept, emptied, so that their
+        // handles stay valid
+        for map in self.side_data.iter().skip(snapshot.side_data.len()) {
+            let mut map = map.clone_box();
+            map.clear();
+            snapshot.side_data.push(map);
+        }
+        Snapshot {
+            nodes: mem::replace(&mut self.nodes, snapshot.nodes),
+            node_data: mem::replace(&mut self.node_data, snapshot.node_data),
+            augmented: mem::replace(&mut self.augmented, snapshot.augmented),
+            root: mem::replace(&mut self.root, snapshot.root),
+            len: mem::replace(&mut self.len, snapshot.len),
+            leftmost: mem::replace(&mut self.leftmost, snapshot.leftmost),
+            rightmost: mem::replace(&mut self.rightmost, snapshot.rightmost),
+            side_data: mem::replace(&mut self.side_data, snapshot.side_data),
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Discards every recorded state, freeing the memory held by the history. The history limit
+    /// is kept.
+    pub fn clear_history(&mut self) {
+        self.history.undo.clear();
+        self.history.redo.clear();
+    }
+
+    /// Sets the most checkpoints that `undo` can go back through, discarding the oldest
+    /// checkpoints once there are more. `None`, the default, keeps every checkpoint.
+    pub fn set_history_limit(&mut self, limit: Option<usize>) {
+        self.history.limit = limit;
+        self.history.trim();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_redo_test() {
+        let mut tree: Tree<u32> = (0..5).collect();
+        assert!(!tree.undo());
+
+        tree.checkpoint();
+        let first = tree.get_leftmost_node().unwrap();
+        tree.delete_node(first);
+        let added = tree.insert_after(tree.get_rightmost_node().unwrap(), 10);
+        tree.checkpoint();
+        tree.clear();
+
+        assert!(tree.undo());
+        assert_eq!(tree.to_vec(), vec![1, 2, 3, 4, 10]);
+        assert_eq!(*tree.get_contents(added), 10);
+        assert!(tree.undo());
+        assert_eq!(tree.to_vec(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(*tree.get_contents(first), 0);
+        assert!(!tree.contains_node(added));
+        tree.assert_valid();
+        assert!(!tree.can_undo());
+
+        assert!(tree.redo());
+        assert!(tree.redo());
+        assert!(tree.is_empty());
+        assert!(!tree.redo());
+
+        // A new checkpoint discards the undone states
+        tree.undo();
+        tree.checkpoint();
+        assert!(!tree.can_redo());
+        tree.clear_history();
+        assert!(!tree.can_undo());
+    }
+
+    #[test]
+    fn undo_side_data_test() {
+        let mut tree: Tree<u32> = (0..3).collect();
+        let labels = tree.register_side_data::<&str>();
+        let first = tree.get_leftmost_node().unwrap();
+        tree.side_data_mut(labels).insert(first, "first");
+        tree.checkpoint();
+        tree.delete_node(first);
+        assert!(tree.side_data(labels).is_empty());
+        tree.undo();
+        assert_eq!(tree.side_data(labels)[first], "first");
+    }
+
+    #[test]
+    fn history_limit_test() {
+        let mut tree: Tree<u32> = Tree::new();
+        tree.set_history_limit(Some(3));
+        for i in 0..10 {
+            tree.checkpoint();
+            tree.insert_at(i as usize, i);
+        }
+        // Only the three most recent checkpoints are kept
+        for _ in 0..3 {
+            assert!(tree.undo());
+        }
+        assert!(!tree.undo());
+        assert_eq!(tree.to_vec(), (0..7).collect::<Vec<_>>());
+
+        // Redoing doesn't grow the history past the limit, and lowering it trims it
+        while tree.redo() {}
+        tree.checkpoint();
+        tree.set_history_limit(Some(1));
+        assert!(tree.undo());
+        assert!(!tree.undo());
+
+        // Clearing the history keeps the limit
+        tree.clear_history();
+        tree.checkpoint();
+        tree.checkpoint();
+        assert!(tree.undo());
+        assert!(!tree.undo());
+        tree.set_history_limit(None);
+        tree.checkpoint();
+        tree.checkpoint();
+        assert!(tree.undo());
+        assert!(tree.undo());
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use std::fmt;
+
+type DeleteHook<T> = Box<dyn FnMut(NodeKey, &T) + Send + Sync>;
+type StructureHook = Box<dyn FnMut(StructureChange) + Send + Sync>;
+
+/// A change to the shape of a tree made while it rebalances, reported to the callback registered
+/// by `set_on_structure_change` once the change is complete
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum StructureChange {
+    /// The right child `pivot` of `root` was rotated up to take its place, `root` becoming the
+    /// left child of `pivot`
+    LeftRotate { root: NodeKey, pivot: NodeKey },
+    /// The left child `pivot` of `root` was rotated up to take its place, `root` becoming the
+    /// right child of `pivot`
+    RightRotate { root: NodeKey, pivot: NodeKey },
+    /// The two nodes swapped their positions and colors in the tree, keeping their contents.
+    /// The sequence order is not updated until the node being deleted is removed.
+    Swap(NodeKey, NodeKey),
+}
+
+// The callbacks registered with a tree. They are not copied when the tree is cloned.
+pub(crate) struct Hooks<T> {
+    on_delete: Option<DeleteHook<T>>,
+    on_structure_change: Option<StructureHook>,
+}
+
+impl<T> Default for Hooks<T> {
+    fn default() -> Self {
+        Hooks {
+            on_delete: None,
+            on_structure_change: None,
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Registers a callback that is called with the NodeKey and contents of every node just before
+    /// it is removed from the tree, replacing any callback registered before. It is called by
+    /// `delete_node` and everything built on it, and by `retain`, `clear` and `drain`.
+    ///
+    /// Nodes that are moved to another tree, for example by `split_off`, are not removed and so
+    /// the callback is not called for them, the moves are reported through the returned key maps
+    /// instead. Callbacks are not copied when the tree is cloned.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The callback to call before a node is removed
+    ///
+    pub fn set_on_delete<F: FnMut(NodeKey, &T) + Send + Sync + 'static>(&mut self, hook: F) {
+        self.hooks.on_delete = Some(Box::new(hook));
+    }
+
+    /// Removes the callback registered by `set_on_delete`
+    pub fn remove_on_delete(&mut self) {
+        self.hooks.on_delete = None;
+    }
+
+    /// Registers a callback that is called after every rotation and swap the tree makes while it
+    /// rebalances, replacing any callback registered before. This lets a structure that mirrors
+    /// the shape of the tree outside of it follow along. Operations that rebuild the tree in bulk,
+    /// such as `retain`, `merge_by` and `shrink_to`, and `split_node`, which relinks three nodes
+    /// at once, relink the nodes without reporting them.
+    /// Callbacks are not copied when the tree is cloned.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The callback to call with each change
+    ///
+    pub fn set_on_structure_change<F: FnMut(StructureChange) + Send + Sync + 'static>(
+        &mut self,
+        hook: F,
+    ) {
+        self.hooks.on_structure_change = Some(Box::new(hook));
+    }
+
+    /// Removes the callback registered by `set_on_structure_change`
+    pub fn remove_on_structure_change(&mut self) {
+        self.hooks.on_structure_change = None;
+    }
+
+    pub(crate) fn notify_structure_change(&mut self, change: StructureChange) {
+        if let Some(hook) = self.hooks.on_structure_change.as_mut() {
+            hook(change);
+        }
+    }
+
+    // Runs an operation without reporting the rotations it makes. Used by operations that relink
+    // several nodes at once, as the tree is only in a consistent shape once they finish.
+    pub(crate) fn without_structure_hook<R, F: FnOnce(&mut Self) -> R>(&mut self, f: F) -> R {
+        let hook = self.hooks.on_structure_change.take();
+        let result = f(self);
+        self.hooks.on_structure_change = hook;
+        result
+    }
+
+    // Calls the delete callback for a node that is about to be removed
+    pub(crate) fn notify_delete(&mut self, node: NodeKey) {
+        if let (Some(hook), Some(contents)) =
+            (self.hooks.on_delete.as_mut(), self.node_data.get(node))
+        {
+            hook(node, contents);
+        }
+    }
+
+    // Calls the delete callback for a node whose contents have already been taken out of the tree
+    pub(crate) fn notify_delete_taken(&mut self, node: NodeKey, contents: &T) {
+        if let Some(hook) = self.hooks.on_delete.as_mut() {
+            hook(node, contents);
+        }
+    }
+
+    // Calls the delete callback for every node, before the tree is cleared
+    pub(crate) fn notify_delete_all(&mut self) {
+        if self.hooks.on_delete.is_none() {
+            return;
+        }
+        let mut node = self.leftmost;
+        while let Some(current) = node {
+            self.notify_delete(current);
+            node = self.get_next(current);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn on_delete_test() {
+        let deleted = Arc::new(Mutex::new(vec![]));
+        let mut tree: Tree<u32> = (0..10).collect();
+        let log = deleted.clone();
+        tree.set_on_delete(move |_, &value| log.lock().unwrap().push(value));
+
+        let third = tree.nth(3).unwrap();
+        tree.delete_node(third);
+        tree.retain(|value| value % 4 != 0);
+        assert_eq!(tree.pop_last(), Some(9));
+        let mut drain = tree.drain();
+        assert_eq!(drain.next(), Some(1));
+        drop(drain);
+        assert_eq!(*deleted.lock().unwrap(), vec![3, 0, 4, 8, 9, 1, 2, 5, 6, 7]);
+
+        tree.remove_on_delete();
+        tree.extend(0..3);
+        tree.clear();
+        assert_eq!(deleted.lock().unwrap().len(), 10);
+    }
+
+    #[test]
+    fn on_structure_change_test() {
+        let mut tree: Tree<u32> = Tree::new();
+        let changes = Arc::new(Mutex::new(vec![]));
+        let log = changes.clone();
+        tree.set_on_structure_change(move |change| log.lock().unwrap().push(change));
+
+        let root = tree.create_root(0);
+        let second = tree.insert_after(root, 1);
+        tree.insert_after(second, 2);
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![StructureChange::LeftRotate {
+                root,
+                pivot: second
+            }]
+        );
+        assert_eq!(tree.root, Some(second));
+
+        changes.lock().unwrap().clear();
+        tree.delete_node(second);
+        assert!(changes
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|change| matches!(change, StructureChange::Swap(..))));
+
+        // Splitting a node relinks it without reporting the rotations made along the way
+        changes.lock().unwrap().clear();
+        let mut node = tree.get_rightmost_node().unwrap();
+        for i in 3..20 {
+            node = tree.insert_after(node, i);
+        }
+        changes.lock().unwrap().clear();
+        tree.split_node(root, 0, 0, 0);
+        assert!(changes.lock().unwrap().is_empty());
+    }
+}
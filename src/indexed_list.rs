@@ -0,0 +1,201 @@
+use crate::Tree;
+use std::fmt;
+
+/// A list that can be indexed, inserted into and removed from at any position in O(log n) time.
+/// The tree keeps the order of the elements and NodeKeys are hidden entirely.
+pub struct IndexedList<T: Clone + fmt::Debug> {
+    tree: Tree<T>,
+}
+
+impl<T: Clone + fmt::Debug> Default for IndexedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + fmt::Debug> IndexedList<T> {
+    /// Create a new empty list
+    pub fn new() -> Self {
+        IndexedList { tree: Tree::new() }
+    }
+
+    /// Returns a reference to the underlying tree
+    pub fn tree(&self) -> &Tree<T> {
+        &self.tree
+    }
+
+    /// Returns the number of elements in the list
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the list contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Removes all elements from the list
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    /// Returns a reference to the element at the position, or None if it is out of bounds
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The zero based position of the element
+    ///
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let node = self.tree.nth(index)?;
+        Some(self.tree.get_contents(node))
+    }
+
+    /// Returns a mutable reference to the element at the position, or None if it is out of bounds
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The zero based position of the element
+    ///
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let node = self.tree.nth(index)?;
+        Some(self.tree.get_mut_contents(node))
+    }
+
+    /// Returns the first element of the list
+    pub fn front(&self) -> Option<&T> {
+        let node = self.tree.get_leftmost_node()?;
+        Some(self.tree.get_contents(node))
+    }
+
+    /// Returns the last element of the list
+    pub fn back(&self) -> Option<&T> {
+        let node = self.tree.get_rightmost_node()?;
+        Some(self.tree.get_contents(node))
+    }
+
+    /// Inserts an element at the start of the list
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The element to insert
+    ///
+    pub fn push_front(&mut self, value: T) {
+        self.tree.insert_at(0, value);
+    }
+
+    /// Inserts an element at the end of the list
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The element to insert
+    ///
+    pub fn push_back(&mut self, value: T) {
+        self.tree.extend(std::iter::once(value));
+    }
+
+    /// Removes and returns the first element of the list
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.tree.pop_first()
+    }
+
+    /// Removes and returns the last element of the list
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tree.pop_last()
+    }
+
+    /// Inserts an element at the position, shifting every element after it along by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length of the list.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position the element will have once inserted
+    /// * `value` - The element to insert
+    ///
+    pub fn insert(&mut self, index: usize, value: T) {
+        self.tree.insert_at(index, value);
+    }
+
+    /// Removes and returns the element at the position, shifting every element after it back by
+    /// one, or returns None if the position is out of bounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position of the element to remove
+    ///
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        self.tree.remove_at(index)
+    }
+
+    /// Returns an iterator over the elements of the list, in order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        std::iter::successors(self.tree.get_leftmost_node(), move |&node| {
+            self.tree.get_next(node)
+        })
+        .map(move |node| self.tree.get_contents(node))
+    }
+}
+
+impl<T: Clone + fmt::Debug> std::iter::FromIterator<T> for IndexedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        IndexedList {
+            tree: Tree::from_sorted_iter(iter),
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug> From<Vec<T>> for IndexedList<T> {
+    fn from(values: Vec<T>) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl<T: Clone + fmt::Debug> From<IndexedList<T>> for Vec<T> {
+    fn from(list: IndexedList<T>) -> Self {
+        list.tree.into_sorted_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexed_list_test() {
+        let mut list = IndexedList::new();
+        let mut model = vec![];
+        for i in 0..100 {
+            let index = (i * 7) % (model.len() + 1);
+            list.insert(index, i);
+            model.insert(index, i);
+        }
+        list.push_back(100);
+        model.push(100);
+        list.push_front(101);
+        model.insert(0, 101);
+        assert!(list.iter().eq(model.iter()));
+
+        for i in 0..40 {
+            let index = (i * 13) % model.len();
+            assert_eq!(list.remove(index), Some(model.remove(index)));
+        }
+        assert_eq!(list.remove(model.len()), None);
+        *list.get_mut(3).unwrap() = 1000;
+        model[3] = 1000;
+        for (i, value) in model.iter().enumerate() {
+            assert_eq!(list.get(i), Some(value));
+        }
+        assert_eq!(list.get(model.len()), None);
+        assert_eq!(list.front(), model.first());
+        assert_eq!(list.pop_back(), model.pop());
+        assert_eq!(list.len(), model.len());
+        list.tree().assert_valid();
+
+        let list: IndexedList<char> = "abc".chars().collect();
+        assert_eq!(list.get(1), Some(&'b'));
+        assert_eq!(list.back(), Some(&'c'));
+        assert_eq!(Vec::from(IndexedList::from(vec![3, 1, 2])), vec![3, 1, 2]);
+    }
+}
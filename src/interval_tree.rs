@@ -0,0 +1,206 @@
+use crate::{Augment, NodeKey, Tree};
+use std::fmt;
+use std::ops::Range;
+
+/// Augmentation that tracks the largest interval end within each subtree
+#[derive(Clone)]
+pub struct MaxEnd;
+
+impl<K: Ord + Clone, V> Augment<(Range<K>, V)> for MaxEnd {
+    type Value = K;
+
+    fn update(&self, contents: &(Range<K>, V), left: Option<&K>, right: Option<&K>) -> K {
+        let mut max_end = &contents.0.end;
+        for end in left.into_iter().chain(right) {
+            if end > max_end {
+                max_end = end;
+            }
+        }
+        max_end.clone()
+    }
+}
+
+/// An interval tree storing half-open intervals sorted by their start, each with an associated
+/// value. Every node is augmented with the largest end in its subtree so that overlap and
+/// stabbing queries only visit the subtrees that can contain matches.
+pub struct IntervalTree<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> {
+    tree: Tree<(Range<K>, V), MaxEnd>,
+}
+
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> Default for IntervalTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> IntervalTree<K, V> {
+    /// Create a new empty interval tree
+    pub fn new() -> Self {
+        IntervalTree {
+            tree: Tree::with_augment(MaxEnd),
+        }
+    }
+
+    /// Returns a reference to the underlying tree, for navigating the intervals in order
+    pub fn tree(&self) -> &Tree<(Range<K>, V), MaxEnd> {
+        &self.tree
+    }
+
+    /// Returns the number of intervals in the tree
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the tree contains no intervals
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Inserts an interval after any existing intervals with the same start.
+    /// Returns the NodeKey of the node storing the interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The half-open interval to insert
+    /// * `value` - The value associated with the interval
+    ///
+    pub fn insert(&mut self, interval: Range<K>, value: V) -> NodeKey {
+        let start = interval.start.clone();
+        self.tree.insert_by((interval, value), |(existing, _)| {
+            existing.start.cmp(&start)
+        })
+    }
+
+    /// Removes an interval from the tree, returning it along with its value
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey of the interval to remove
+    ///
+    pub fn remove(&mut self, node: NodeKey) -> (Range<K>, V) {
+        self.tree.delete_node(node)
+    }
+
+    /// Returns the interval stored in the specified node along with its value
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey of the interval
+    ///
+    pub fn get(&self, node: NodeKey) -> (&Range<K>, &V) {
+        let (interval, value) = self.tree.get_contents(node);
+        (interval, value)
+    }
+
+    /// Returns the NodeKeys of all the intervals that overlap the query interval, ordered by
+    /// their start
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The half-open interval to test for overlaps with
+    ///
+    pub fn overlaps(&self, query: Range<K>) -> Vec<NodeKey> {
+        let mut found = vec![];
+        self.search(
+            self.tree.root,
+            &query.start,
+            &mut |start| start < &query.end,
+            &mut found,
+        );
+        found
+    }
+
+    /// Returns the NodeKeys of all the intervals that contain the point, ordered by their start
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - The point to stab the intervals with
+    ///
+    pub fn stab(&self, point: K) -> Vec<NodeKey> {
+        let mut found = vec![];
+        self.search(
+            self.tree.root,
+            &point,
+            &mut |start| start <= &point,
+            &mut found,
+        );
+        found
+    }
+
+    // Collects the intervals in the subtree that end after `after` and whose start satisfies
+    // `start_valid`. As the intervals are sorted by start, once a start is invalid every later
+    // start is too.
+    fn search<F: FnMut(&K) -> bool>(
+        &self,
+        node: Option<NodeKey>,
+        after: &K,
+        start_valid: &mut F,
+        found: &mut Vec<NodeKey>,
+    ) {
+        let node = match node {
+            Some(node) => node,
+            None => return,
+        };
+        if self.tree.get_augmented(node) <= after {
+            // Nothing in this subtree ends late enough
+            return;
+        }
+        self.search(self.tree.get_left(node), after, start_valid, found);
+        let (interval, _) = self.tree.get_contents(node);
+        if !start_valid(&interval.start) {
+            return;
+        }
+        if &interval.end > after {
+            found.push(node);
+        }
+        self.search(self.tree.get_right(node), after, start_valid, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(intervals: &[(Range<i32>, NodeKey)], query: Range<i32>) -> Vec<NodeKey> {
+        let mut matches: Vec<&(Range<i32>, NodeKey)> = intervals
+            .iter()
+            .filter(|(interval, _)| interval.start < query.end && query.start < interval.end)
+            .collect();
+        matches.sort_by_key(|(interval, _)| interval.start);
+        matches.into_iter().map(|(_, node)| *node).collect()
+    }
+
+    #[test]
+    fn interval_tree_test() {
+        let mut tree: IntervalTree<i32, usize> = IntervalTree::new();
+        assert!(tree.overlaps(0..10).is_empty());
+
+        let mut intervals = vec![];
+        for i in 0..60 {
+            let start = (i * 37) % 100;
+            let interval = start..start + (i * 13) % 20 + 1;
+            let node = tree.insert(interval.clone(), i as usize);
+            intervals.push((interval, node));
+        }
+        assert_eq!(tree.len(), 60);
+
+        for query_start in (0..120).step_by(7) {
+            let query = query_start..query_start + 5;
+            assert_eq!(tree.overlaps(query.clone()), brute_force(&intervals, query));
+            assert_eq!(
+                tree.stab(query_start),
+                brute_force(&intervals, query_start..query_start + 1)
+            );
+        }
+
+        for (interval, node) in intervals.drain(..30) {
+            assert_eq!(tree.remove(node).0, interval);
+        }
+        for query_start in (0..120).step_by(3) {
+            let query = query_start..query_start + 10;
+            assert_eq!(tree.overlaps(query.clone()), brute_force(&intervals, query));
+        }
+        let (interval, node) = &intervals[0];
+        assert_eq!(tree.get(*node).0, interval);
+    }
+}
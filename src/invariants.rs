@@ -0,0 +1,369 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use slotmap::SecondaryMap;
+use std::error::Error;
+use std::fmt;
+
+/// A broken invariant found by `Tree::check_invariants`
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum InvariantViolation {
+    /// The root node is red
+    RedRoot(NodeKey),
+    /// The root node has a parent
+    RootHasParent(NodeKey),
+    /// A red node has a red child
+    RedRedEdge { parent: NodeKey, child: NodeKey },
+    /// A red node in a left-leaning red-black tree is a right child
+    RightLeaningRed(NodeKey),
+    /// The paths through the left and right subtrees of a node pass through different numbers of
+    /// black nodes
+    BlackHeightMismatch {
+        node: NodeKey,
+        left: usize,
+        right: usize,
+    },
+    /// The subtrees of a node are further out of balance than the balancing scheme allows: their
+    /// heights in an AVL tree, or their sizes in a weight-balanced tree
+    Unbalanced {
+        node: NodeKey,
+        left: usize,
+        right: usize,
+    },
+    /// The height stored for a node in an AVL tree does not match the height of its subtree
+    HeightMismatch {
+        node: NodeKey,
+        stored: usize,
+        actual: usize,
+    },
+    /// A node in a treap has a child with a higher priority than its own
+    PriorityOrder { parent: NodeKey, child: NodeKey },
+    /// A node links to a child that is not in the arena
+    DanglingChild { parent: NodeKey, child: NodeKey },
+    /// A node is the child of a node other than its parent
+    ParentMismatch {
+        node: NodeKey,
+        parent: Option<NodeKey>,
+        linked_from: NodeKey,
+    },
+    /// The size stored for a subtree does not match the number of nodes in it
+    SizeMismatch {
+        node: NodeKey,
+        stored: usize,
+        actual: usize,
+    },
+    /// The contents of a node are missing
+    MissingContents(NodeKey),
+    /// The prev or next link of a node does not point at its neighbour in an in-order walk
+    ThreadingMismatch(NodeKey),
+    /// The cached first or last node of the tree is not the first or last node of an in-order walk
+    EndsMismatch,
+    /// The number of nodes reachable from the root differs from the length of the tree
+    LengthMismatch { len: usize, reachable: usize },
+    /// The arena holds nodes that can't be reached from the root
+    UnreachableNodes(usize),
+    /// A node can be reached from the root along more than one path, the walk was abandoned
+    Cycle,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvariantViolation::RedRoot(node) => write!(f, "the root {:?} is red", node),
+            InvariantViolation::RootHasParent(node) => {
+                write!(f, "the root {:?} has a parent", node)
+            }
+            InvariantViolation::RedRedEdge { parent, child } => {
+                write!(f, "the red node {:?} has the red child {:?}", parent, child)
+            }
+            InvariantViolation::RightLeaningRed(node) => {
+                write!(f, "the red node {:?} is a right child", node)
+            }
+            InvariantViolation::BlackHeightMismatch { node, left, right } => write!(
+                f,
+                "the subtrees of {:?} have black heights {} and {}",
+                node, left, right
+            ),
+            InvariantViolation::Unbalanced { node, left, right } => write!(
+                f,
+                "the subtrees of {:?} are out of balance, at {} and {}",
+                node, left, right
+            ),
+            InvariantViolation::HeightMismatch {
+                node,
+                stored,
+                actual,
+            } => write!(
+                f,
+                "the subtree of {:?} has a height of {} but a stored height of {}",
+                node, actual, stored
+            ),
+            InvariantViolation::PriorityOrder { parent, child } => write!(
+                f,
+                "the child {:?} has a higher priority than its parent {:?}",
+                child, parent
+            ),
+            InvariantViolation::DanglingChild { parent, child } => write!(
+                f,
+                "{:?} links to the child {:?} which is not in the tree",
+                parent, child
+            ),
+            InvariantViolation::ParentMismatch {
+                node,
+                parent,
+                linked_from,
+            } => write!(
+                f,
+                "{:?} is a child of {:?} but has the parent {:?}",
+                node, linked_from, parent
+            ),
+            InvariantViolation::SizeMismatch {
+                node,
+                stored,
+                actual,
+            } => write!(
+                f,
+                "the subtree of {:?} has {} nodes but a stored size of {}",
+                node, actual, stored
+            ),
+            InvariantViolation::MissingContents(node) => {
+                write!(f, "the contents of {:?} are missing", node)
+            }
+            InvariantViolation::ThreadingMismatch(node) => write!(
+                f,
+                "the prev and next links of {:?} don't match the order of the tree",
+                node
+            ),
+            InvariantViolation::EndsMismatch => {
+                write!(f, "the cached first and last nodes are wrong")
+            }
+            InvariantViolation::LengthMismatch { len, reachable } => write!(
+                f,
+                "the tree has a length of {} but {} nodes are reachable",
+                len, reachable
+            ),
+            InvariantViolation::UnreachableNodes(count) => {
+                write!(f, "{} nodes can't be reached from the root", count)
+            }
+            InvariantViolation::Cycle => write!(f, "the tree contains a cycle"),
+        }
+    }
+}
+
+impl Error for InvariantViolation {}
+
+// The stages of visiting a node in the walk made by check_invariants
+enum Visit {
+    Enter,
+    InOrder,
+    Exit,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Checks every structural invariant of the tree: the nodes are balanced as the tree's
+    /// balancing scheme requires, the parent links and subtree sizes agree with the child links,
+    /// and the cached order of the nodes matches an in-order walk. For a red-black tree the root
+    /// is black, no red node has a red child and every path has the same black height.
+    /// Returns every violation found rather than stopping at the first.
+    ///
+    /// Takes time proportional to the number of nodes. Trees are only left broken by bugs, so
+    /// this is meant for tests and debugging.
+    pub fn check_invariants(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = vec![];
+        if let Some(root) = self.root {
+            B::check_root(self, root, &mut violations);
+            if self.try_get_parent(root).flatten().is_some() {
+                violations.push(InvariantViolation::RootHasParent(root));
+            }
+        }
+
+        let mut in_order = Vec::with_capacity(self.len);
+        let mut ranks = SecondaryMap::with_capacity(self.len);
+        let mut sizes = SecondaryMap::with_capacity(self.len);
+        let mut stack = vec![];
+        if let Some(root) = self.root {
+            stack.push((root, Visit::Enter));
+        }
+        let mut visited = 0;
+        while let Some((node, visit)) = stack.pop() {
+            match visit {
+                Visit::Enter => {
+                    visited += 1;
+                    if visited > self.nodes.len() {
+                        violations.push(InvariantViolation::Cycle);
+                        return Err(violations);
+                    }
+                    stack.push((node, Visit::InOrder));
+                    let left = self.checked_child(node, self.get_left(node), &mut violations);
+                    if let Some(left) = left {
+                        stack.push((left, Visit::Enter));
+                    }
+                }
+                Visit::InOrder => {
+                    in_order.push(node);
+                    stack.push((node, Visit::Exit));
+                    let right = self.checked_child(node, self.get_right(node), &mut violations);
+                    if let Some(right) = right {
+                        stack.push((right, Visit::Enter));
+                    }
+                }
+                Visit::Exit => {
+                    let children = [self.get_left(node), self.get_right(node)];
+                    let [left, right] =
+                        children.map(|child| child.filter(|&child| self.contains_node(child)));
+                    let rank = |child: Option<NodeKey>| child.map(|child| ranks[child]);
+                    let rank = B::check(self, node, rank(left), rank(right), &mut violations);
+                    ranks.insert(node, rank);
+
+                    let size = |child: Option<NodeKey>| child.map_or(0, |child| sizes[child]);
+                    let actual = size(left) + size(right) + 1;
+                    let stored = self.get_size(Some(node));
+                    if stored != actual {
+                        violations.push(InvariantViolation::SizeMismatch {
+                            node,
+                            stored,
+                            actual,
+                        });
+                    }
+                    sizes.insert(node, actual);
+                    if !self.node_data.contains_key(node) {
+                        violations.push(InvariantViolation::MissingContents(node));
+                    }
+                }
+            }
+        }
+
+        if in_order.len() != self.len {
+            violations.push(InvariantViolation::LengthMismatch {
+                len: self.len,
+                reachable: in_order.len(),
+            });
+        }
+        if self.nodes.len() > in_order.len() {
+            violations.push(InvariantViolation::UnreachableNodes(
+                self.nodes.len() - in_order.len(),
+            ));
+        }
+        if self.leftmost != in_order.first().copied() || self.rightmost != in_order.last().copied()
+        {
+            violations.push(InvariantViolation::EndsMismatch);
+        }
+        for (i, &node) in in_order.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|i| in_order[i]);
+            let next = in_order.get(i + 1).copied();
+            if self.get_prev(node) != prev || self.get_next(node) != next {
+                violations.push(InvariantViolation::ThreadingMismatch(node));
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    // With the validate feature enabled in a debug build, panics if an insert or delete has left
+    // the tree broken so the fault is caught where it happened
+    #[inline]
+    pub(crate) fn validate_after_mutation(&self) {
+        #[cfg(all(feature = "validate", debug_assertions))]
+        if let Err(violations) = self.check_invariants() {
+            panic!("tree invariants broken: {:?}", violations);
+        }
+    }
+
+    // Returns the child if it can be walked to, recording a violation if its links are wrong
+    fn checked_child(
+        &self,
+        parent: NodeKey,
+        child: Option<NodeKey>,
+        violations: &mut Vec<InvariantViolation>,
+    ) -> Option<NodeKey> {
+        let child = child?;
+        match self.try_get_parent(child) {
+            None => {
+                violations.push(InvariantViolation::DanglingChild { parent, child });
+                None
+            }
+            Some(child_parent) => {
+                if child_parent != Some(parent) {
+                    violations.push(InvariantViolation::ParentMismatch {
+                        node: child,
+                        parent: child_parent,
+                        linked_from: parent,
+                    });
+                }
+                Some(child)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    #[test]
+    fn check_invariants_test() {
+        let mut tree: Tree<u32> = (0..15).collect();
+        assert_eq!(tree.check_invariants(), Ok(()));
+        assert_eq!(Tree::<u32>::new().check_invariants(), Ok(()));
+
+        let root = tree.root.unwrap();
+        tree.set_color(root, Color::Red);
+        assert_eq!(
+            tree.check_invariants(),
+            Err(vec![InvariantViolation::RedRoot(root)])
+        );
+        tree.set_color(root, Color::Black);
+
+        // Recolouring a leaf black lengthens the paths through it and all of its ancestors
+        let first = tree.get_leftmost_node().unwrap();
+        let parent = tree.get_parent(first).unwrap();
+        tree.set_color(first, Color::Black);
+        let violations = tree.check_invariants().unwrap_err();
+        assert_eq!(
+            violations[0],
+            InvariantViolation::BlackHeightMismatch {
+                node: parent,
+                left: 2,
+                right: 1
+            }
+        );
+        assert!(violations
+            .iter()
+            .all(|violation| matches!(violation, InvariantViolation::BlackHeightMismatch { .. })));
+        tree.set_color(first, Color::Red);
+
+        tree.set_size(parent, 7);
+        tree.set_parent(first, None);
+        let violations = tree.check_invariants().unwrap_err();
+        assert!(violations.contains(&InvariantViolation::SizeMismatch {
+            node: parent,
+            stored: 7,
+            actual: 3
+        }));
+        assert!(violations.contains(&InvariantViolation::ParentMismatch {
+            node: first,
+            parent: None,
+            linked_from: parent
+        }));
+        assert_eq!(
+            violations[0].to_string(),
+            format!(
+                "{:?} is a child of {:?} but has the parent None",
+                first, parent
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "validate", debug_assertions))]
+    #[should_panic(expected = "tree invariants broken")]
+    fn validate_after_mutation_test() {
+        let mut tree: Tree<u32> = (0..7).collect();
+        let first = tree.get_leftmost_node().unwrap();
+        tree.set_size(first, 2);
+        tree.insert_after(first, 10);
+    }
+}
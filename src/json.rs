@@ -0,0 +1,101 @@
+use crate::build::NodeParts;
+use crate::{Augment, Balance, Color, Node, NodeKey, NodeStore, Tree};
+use serde::de::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// A node of a tree in its nested JSON form, with its children nested within it
+#[derive(Serialize, Deserialize)]
+struct JsonNode<T> {
+    value: T,
+    color: Color,
+    left: Option<Box<JsonNode<T>>>,
+    right: Option<Box<JsonNode<T>>>,
+}
+
+impl<T: Clone + fmt::Debug + Serialize, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    Tree<T, A, S, B>
+{
+    /// Returns the tree as nested JSON, where every node is an object holding its `value`, its
+    /// `color` and its `left` and `right` children, which are null if missing. An empty tree is
+    /// written as null.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.root.map(|root| self.json_node(root)))
+    }
+
+    fn json_node(&self, node: NodeKey) -> JsonNode<&T> {
+        JsonNode {
+            value: self.get_contents(node),
+            color: self.get_color(Some(node)),
+            left: self
+                .get_left(node)
+                .map(|left| Box::new(self.json_node(left))),
+            right: self
+                .get_right(node)
+                .map(|right| Box::new(self.json_node(right))),
+        }
+    }
+}
+
+impl<T, A, S: NodeStore<Node> + Default, B: Balance> Tree<T, A, S, B>
+where
+    T: Clone + fmt::Debug + for<'de> Deserialize<'de>,
+    A: Augment<T> + Default,
+{
+    /// Creates a tree from nested JSON in the form written by `to_json`. The tree has exactly the
+    /// structure described by the JSON, an error is returned if it is not a valid red-black tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - The nested JSON to read
+    ///
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let root: Option<JsonNode<T>> = serde_json::from_str(json)?;
+        let mut parts = vec![];
+        let root = root.map(|root| Self::flatten_json(root, &mut parts));
+        let (tree, _) =
+            Tree::from_parts(parts, root, A::default()).map_err(serde_json::Error::custom)?;
+        Ok(tree)
+    }
+
+    // Adds the nodes of a subtree to the parts in order, returning the index of the subtree root
+    fn flatten_json(node: JsonNode<T>, parts: &mut Vec<NodeParts<T>>) -> usize {
+        let left = node.left.map(|left| Self::flatten_json(*left, parts));
+        let index = parts.len();
+        parts.push(NodeParts {
+            contents: node.value,
+            color: node.color,
+            left,
+            right: None,
+        });
+        parts[index].right = node.right.map(|right| Self::flatten_json(*right, parts));
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_test() {
+        let tree: Tree<u32> = (1..=3).collect();
+        let json = tree.to_json().unwrap();
+        assert_eq!(
+            json,
+            "{\"value\":2,\"color\":\"Black\",\
+             \"left\":{\"value\":1,\"color\":\"Red\",\"left\":null,\"right\":null},\
+             \"right\":{\"value\":3,\"color\":\"Red\",\"left\":null,\"right\":null}}"
+        );
+        let copy = Tree::<u32>::from_json(&json).unwrap();
+        copy.assert_valid();
+        assert_eq!(copy.get_level_order(), tree.get_level_order());
+
+        let large: Tree<u32> = (0..500).collect();
+        let copy = Tree::<u32>::from_json(&large.to_json().unwrap()).unwrap();
+        assert!(copy == large);
+
+        assert!(Tree::<u32>::from_json("null").unwrap().is_empty());
+        assert!(Tree::<u32>::from_json(&json.replace("Black", "Red")).is_err());
+    }
+}
@@ -1,12 +1,129 @@
-use slotmap::{new_key_type, SlotMap, SecondaryMap};
+use hooks::Hooks;
+use side_data::SideMap;
+use slotmap::{new_key_type, SecondaryMap, SlotMap};
+use std::cmp::Ordering;
 use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+
+// Emits a trace level event describing a change to the tree when the tracing feature is enabled.
+// Defined before the modules so that they can use it too.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(target: "binary_search_tree", $($arg)*);
+    };
+}
+
+mod aggregate;
+mod augment;
+mod avl;
+mod balance;
+mod build;
+mod concurrent;
+#[cfg(feature = "counters")]
+mod counters;
+mod cursor;
+mod drain;
+mod entry;
+mod error;
+mod eytzinger;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filter;
+mod format;
+mod frozen;
+#[cfg(feature = "petgraph")]
+mod graph;
+mod history;
+mod hooks;
+mod indexed_list;
+mod interval_tree;
+mod invariants;
+#[cfg(feature = "json")]
+mod json;
+mod llrb;
+mod memory;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod persistent;
+mod priority_queue;
+#[cfg(feature = "python")]
+pub mod python;
+mod rb_map;
+mod rb_multiset;
+mod rb_set;
+mod rope;
+mod search;
+#[cfg(feature = "serde")]
+mod serialize;
+mod side_data;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+mod splay;
+mod split_join;
+mod stats;
+mod storage;
+#[cfg(feature = "proptest")]
+mod strategy;
+mod transaction;
+mod treap;
+mod triples;
+mod view;
+mod visitor;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod weight_balanced;
+
+pub use aggregate::Aggregate;
+pub use augment::Augment;
+pub use avl::{Avl, AvlTree};
+pub use balance::{Balance, RedBlack};
+pub use concurrent::ConcurrentTree;
+#[cfg(feature = "counters")]
+pub use counters::RebalanceCounters;
+pub use cursor::{Cursor, CursorPosition};
+pub use drain::Drain;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use error::{TransactionError, TreeError};
+pub use eytzinger::EytzingerTree;
+pub use filter::ExtractIf;
+pub use frozen::FrozenTree;
+#[cfg(feature = "petgraph")]
+pub use graph::TreeEdge;
+pub use hooks::StructureChange;
+pub use indexed_list::IndexedList;
+pub use interval_tree::{IntervalTree, MaxEnd};
+pub use invariants::InvariantViolation;
+pub use llrb::{Llrb, LlrbTree};
+pub use memory::MemoryUsage;
+pub use persistent::PersistentTree;
+pub use priority_queue::TreePriorityQueue;
+pub use rb_map::RBMap;
+pub use rb_multiset::RBMultiSet;
+pub use rb_set::RBSet;
+pub use rope::{CharCount, Rope};
+pub use side_data::SideData;
+pub use splay::{Splay, SplayTree};
+pub use stats::TreeStats;
+pub use storage::{NodeStore, VecStore};
+#[cfg(feature = "proptest")]
+pub use strategy::{tree_ops, trees, TreeOp};
+pub use treap::{Treap, TreapTree};
+pub use triples::Triples;
+pub use view::NodeView;
+pub use visitor::TreeVisitor;
+pub use weight_balanced::{WeightBalanced, WeightBalancedTree};
 
 new_key_type! { pub struct NodeKey; }
 
-#[derive(PartialEq, Copy, Clone, Debug)]
-enum Color {
-    RED,
-    BLACK,
+/// The color of a node in a red-black tree. The nodes of trees kept balanced by other schemes are
+/// all black.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Color {
+    Red,
+    Black,
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
@@ -16,18 +133,39 @@ enum NodeType {
     Orphan,
 }
 
+// A position in the sequence where a new node can be attached as a leaf
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum InsertPosition {
+    Root,
+    Before(NodeKey),
+    After(NodeKey),
+}
+
 #[derive(Clone, Copy)]
 pub struct Node {
     parent: Option<NodeKey>,
     left: Option<NodeKey>,
     right: Option<NodeKey>,
 
+    // The neighbours in the sequence, without them they are found by walking the tree
+    #[cfg(feature = "threading")]
     prev: Option<NodeKey>,
+    #[cfg(feature = "threading")]
     next: Option<NodeKey>,
 
-    color: Color,
+    // The number of nodes in the subtree rooted at this node, with the rank of the node packed
+    // into the top bits. The rank is the balance data kept by the tree's balancing scheme, such
+    // as the color of a red-black node. A separate field would be padded out to a whole word, so
+    // packing it shrinks a node from 56 to 48 bytes on 64 bit targets.
+    size_and_rank: usize,
 }
 
+// The number of bits of size_and_rank taken by the rank, enough for the height of any AVL tree
+// that fits in memory. Subtrees can never hold enough nodes to need the rest.
+const RANK_BITS: u32 = 6;
+const RANK_SHIFT: u32 = usize::BITS - RANK_BITS;
+const SIZE_MASK: usize = (1 << RANK_SHIFT) - 1;
+
 impl Node {
     fn new() -> Self {
         Node {
@@ -37,29 +175,375 @@ impl Node {
             right: None,
 
             // Optimisation
+            #[cfg(feature = "threading")]
             prev: None,
+            #[cfg(feature = "threading")]
             next: None,
-            color: Color::RED,
+            // A node of rank zero, red in a red-black tree, with a size of one
+            size_and_rank: 1,
+        }
+    }
+
+    fn rank(&self) -> usize {
+        self.size_and_rank >> RANK_SHIFT
+    }
+
+    fn set_rank(&mut self, rank: usize) {
+        debug_assert!(rank < 1 << RANK_BITS);
+        self.size_and_rank = (self.size_and_rank & SIZE_MASK) | (rank << RANK_SHIFT);
+    }
+
+    // Red-black nodes are red with a rank of zero and black with a rank of one
+    fn color(&self) -> Color {
+        if self.rank() == 0 {
+            Color::Red
+        } else {
+            Color::Black
+        }
+    }
+
+    fn set_color(&mut self, color: Color) {
+        match color {
+            Color::Red => self.set_rank(0),
+            Color::Black => self.set_rank(1),
         }
     }
+
+    fn size(&self) -> usize {
+        self.size_and_rank & SIZE_MASK
+    }
+
+    fn set_size(&mut self, size: usize) {
+        debug_assert!(size <= SIZE_MASK);
+        self.size_and_rank = (self.size_and_rank & !SIZE_MASK) | size;
+    }
 }
 
 /// The tree structure.
-/// Stores the nodes in a genrational arena and the NodeKey of the root of the tree.
-pub struct Tree<T: Clone + fmt::Debug> {
-    nodes: SlotMap<NodeKey, Node>,
+/// Stores the nodes in a generational arena and the NodeKey of the root of the tree.
+/// The optional augmentation `A` keeps a summary value up to date for every subtree.
+///
+/// The tree is laid out as a structure of arrays. The arena `S` only holds the links between the
+/// nodes, which are small and the same size whatever the contents, while the contents and the
+/// augmented values are kept in their own arrays indexed by the same NodeKeys. Walking the tree
+/// only touches the dense link records, the contents are only loaded when they are read.
+pub struct Tree<T: Clone + fmt::Debug, A: Augment<T> = (), S = SlotMap<NodeKey, Node>, B = RedBlack>
+{
+    // The links of every node
+    nodes: S,
+    // The contents of every node
     node_data: SecondaryMap<NodeKey, T>,
+    augment: A,
+    // The augmented value of the subtree rooted at every node
+    augmented: SecondaryMap<NodeKey, A::Value>,
     pub root: Option<NodeKey>,
+    len: usize,
+    // Changed by every structural mutation, see generation
+    generation: u64,
+    // The first and last nodes in the tree, cached so they can be found without a walk
+    leftmost: Option<NodeKey>,
+    rightmost: Option<NodeKey>,
+    // Maps registered with register_side_data, kept in step with the nodes
+    side_data: Vec<Box<dyn SideMap>>,
+    // Identifies the tree to the side data handles it returns, a clone keeps the id of the tree
+    // it was cloned from along with its maps
+    side_data_id: u64,
+    hooks: Hooks<T>,
+    // The states recorded by checkpoint, for undo and redo
+    history: history::History<T, A::Value, S>,
+    // The changes made by the transaction in progress, for rolling them back
+    undo_log: Option<transaction::UndoLog<T>>,
+    #[cfg(feature = "counters")]
+    counters: counters::Counters,
+    balance: PhantomData<B>,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T> + Default, S: NodeStore<Node> + Default, B: Balance>
+    Default for Tree<T, A, S, B>
+{
+    fn default() -> Self {
+        Self::with_augment(A::default())
+    }
+}
+
+/// Cloning a tree keeps the NodeKeys of its nodes, so a key for a node in the original tree refers
+/// to the copy of that node in the clone.
+impl<T: Clone + fmt::Debug, A: Augment<T> + Clone, S: NodeStore<Node> + Clone, B: Balance> Clone
+    for Tree<T, A, S, B>
+where
+    A::Value: Clone,
+{
+    fn clone(&self) -> Self {
+        let mut node_data = SecondaryMap::with_capacity(self.nodes.capacity());
+        for (node, contents) in self.node_data.iter() {
+            node_data.insert(node, contents.clone());
+        }
+        let mut augmented = SecondaryMap::with_capacity(self.nodes.capacity());
+        for (node, value) in self.augmented.iter() {
+            augmented.insert(node, value.clone());
+        }
+        Tree {
+            nodes: self.nodes.clone(),
+            node_data,
+            augment: self.augment.clone(),
+            augmented,
+            root: self.root,
+            len: self.len,
+            generation: self.generation,
+            leftmost: self.leftmost,
+            rightmost: self.rightmost,
+            side_data: self.side_data.iter().map(|map| map.clone_box()).collect(),
+            side_data_id: self.side_data_id,
+            hooks: Hooks::default(),
+            history: Default::default(),
+            undo_log: None,
+            #[cfg(feature = "counters")]
+            counters: self.counters,
+            balance: PhantomData,
+        }
+    }
+}
+
+/// Trees are equal if they hold equal contents in the same order, regardless of their shapes
+impl<T: Clone + fmt::Debug + PartialEq, A: Augment<T>, S: NodeStore<Node>, B: Balance> PartialEq
+    for Tree<T, A, S, B>
+{
+    fn eq(&self, other: &Self) -> bool {
+        if self.len != other.len {
+            return false;
+        }
+        let mut node = self.leftmost;
+        let mut other_node = other.leftmost;
+        while let (Some(current), Some(other_current)) = (node, other_node) {
+            if self.get_contents(current) != other.get_contents(other_current) {
+                return false;
+            }
+            node = self.get_next(current);
+            other_node = other.get_next(other_current);
+        }
+        true
+    }
+}
+
+impl<T: Clone + fmt::Debug + Eq, A: Augment<T>, S: NodeStore<Node>, B: Balance> Eq
+    for Tree<T, A, S, B>
+{
+}
+
+/// Reads the contents of a node, panicking if the NodeKey is stale
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Index<NodeKey>
+    for Tree<T, A, S, B>
+{
+    type Output = T;
+
+    fn index(&self, node: NodeKey) -> &T {
+        self.get_contents(node)
+    }
+}
+
+/// Writes the contents of a node, panicking if the NodeKey is stale. If the tree is augmented,
+/// `update_augmented` must be called after modifying the contents.
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> IndexMut<NodeKey>
+    for Tree<T, A, S, B>
+{
+    fn index_mut(&mut self, node: NodeKey) -> &mut T {
+        self.get_mut_contents(node)
+    }
 }
 
 impl<T: Clone + fmt::Debug> Tree<T> {
     /// Create a new empty tree
     pub fn new() -> Self {
+        Tree::with_augment(())
+    }
+
+    /// Create a new empty tree with space preallocated for at least `capacity` nodes
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of nodes to allocate space for
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        Tree::with_capacity_and_augment(capacity, ())
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Create a new empty tree that maintains the given augmentation
+    ///
+    /// # Arguments
+    ///
+    /// * `augment` - The augmentation used to compute the value of every subtree
+    ///
+    pub fn with_augment(augment: A) -> Self
+    where
+        S: Default,
+    {
+        Tree::with_store(S::default(), augment)
+    }
+
+    /// Create a new empty tree that maintains the given augmentation, with space preallocated for
+    /// at least `capacity` nodes
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of nodes to allocate space for
+    /// * `augment` - The augmentation used to compute the value of every subtree
+    ///
+    pub fn with_capacity_and_augment(capacity: usize, augment: A) -> Self
+    where
+        S: Default,
+    {
+        let mut tree = Tree::with_store(S::default(), augment);
+        tree.reserve(capacity);
+        tree
+    }
+
+    /// Create a new empty tree that keeps its nodes in the given store, for example a `VecStore`
+    /// created in a custom allocator. Trees created from this one, by splitting it or shrinking
+    /// it, use stores created by `NodeStore::new_like` so they allocate in the same way.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The empty store to keep the nodes in
+    /// * `augment` - The augmentation used to compute the value of every subtree
+    ///
+    pub fn with_store(store: S, augment: A) -> Self {
+        debug_assert!(store.is_empty());
+        let capacity = store.capacity();
         Tree {
-            nodes: SlotMap::with_key(),
-            node_data: SecondaryMap::new(),
+            nodes: store,
+            node_data: SecondaryMap::with_capacity(capacity),
+            augment,
+            augmented: SecondaryMap::with_capacity(capacity),
             root: None,
+            len: 0,
+            generation: 0,
+            leftmost: None,
+            rightmost: None,
+            side_data: Vec::new(),
+            side_data_id: side_data::new_side_data_id(),
+            hooks: Hooks::default(),
+            history: Default::default(),
+            undo_log: None,
+            #[cfg(feature = "counters")]
+            counters: Default::default(),
+            balance: PhantomData,
+        }
+    }
+
+    /// Reserves space for at least `additional` more nodes to be inserted without reallocating
+    ///
+    /// # Arguments
+    ///
+    /// * `additional` - The number of extra nodes to allocate space for
+    ///
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+        self.node_data.set_capacity(self.nodes.capacity());
+        self.augmented.set_capacity(self.nodes.capacity());
+    }
+
+    /// Returns the number of nodes the tree can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Rebuilds the arena with the nodes laid out densely in order, keeping its capacity so the
+    /// tree can grow again without reallocating. After heavy churn the live nodes are scattered
+    /// through the arena, compacting restores the locality of walking the tree in order.
+    ///
+    /// Every node is given a new NodeKey, returns a map from each old NodeKey to its replacement
+    /// so that externally stored keys can be updated.
+    pub fn compact(&mut self) -> SecondaryMap<NodeKey, NodeKey> {
+        self.shrink_to(self.capacity())
+    }
+
+    /// Shrinks the capacity of the tree as much as possible.
+    /// See `shrink_to` for details, including the invalidation of existing NodeKeys.
+    pub fn shrink_to_fit(&mut self) -> SecondaryMap<NodeKey, NodeKey> {
+        self.shrink_to(0)
+    }
+
+    /// Shrinks the capacity of the tree to hold the current nodes or `min_capacity` nodes,
+    /// whichever is larger.
+    ///
+    /// The arena can only release memory by being rebuilt, so every node is given a new NodeKey.
+    /// Returns a map from each old NodeKey to its replacement so that externally stored keys
+    /// can be updated. The nodes are laid out in order, which also restores iteration locality.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_capacity` - The minimum number of nodes the tree should keep space for
+    ///
+    pub fn shrink_to(&mut self, min_capacity: usize) -> SecondaryMap<NodeKey, NodeKey> {
+        self.poison_transaction();
+        self.bump_generation();
+        let capacity = std::cmp::max(self.len, min_capacity);
+        let mut nodes = self.nodes.new_like(capacity);
+        let mut node_data = SecondaryMap::with_capacity(capacity);
+        let mut augmented = SecondaryMap::with_capacity(capacity);
+        let mut key_map = SecondaryMap::with_capacity(self.len);
+
+        let mut node = self.get_leftmost_node();
+        while let Some(old_key) = node {
+            let new_key = nodes.insert(*self.nodes.get(old_key).unwrap());
+            key_map.insert(old_key, new_key);
+            node = self.get_next(old_key);
+        }
+        for (old_key, &new_key) in key_map.iter() {
+            let remap = |key: Option<NodeKey>| key.map(|key| key_map[key]);
+            let node: &mut Node = nodes.get_mut(new_key).unwrap();
+            node.parent = remap(node.parent);
+            node.left = remap(node.left);
+            node.right = remap(node.right);
+            #[cfg(feature = "threading")]
+            {
+                node.prev = remap(node.prev);
+                node.next = remap(node.next);
+            }
+            if let Some(contents) = self.node_data.remove(old_key) {
+                node_data.insert(new_key, contents);
+            }
+            if let Some(value) = self.augmented.remove(old_key) {
+                augmented.insert(new_key, value);
+            }
         }
+
+        self.root = self.root.map(|root| key_map[root]);
+        self.leftmost = self.leftmost.map(|leftmost| key_map[leftmost]);
+        self.rightmost = self.rightmost.map(|rightmost| key_map[rightmost]);
+        self.nodes = nodes;
+        self.node_data = node_data;
+        self.augmented = augmented;
+        self.remap_side_data(&key_map);
+        key_map
+    }
+
+    /// Returns a compact copy of the tree, with its nodes stored in order in an arena no larger
+    /// than needed. The copied nodes are given new NodeKeys, returns the copy along with a map from
+    /// the NodeKeys of the nodes in this tree to the NodeKeys of their copies.
+    pub fn clone_with_key_map(&self) -> (Tree<T, A, S, B>, SecondaryMap<NodeKey, NodeKey>)
+    where
+        A: Clone,
+        A::Value: Clone,
+        S: Clone,
+    {
+        let mut tree = self.clone();
+        let key_map = tree.shrink_to_fit();
+        (tree, key_map)
+    }
+
+    /// Returns a number that changes whenever the structure of the tree changes: when nodes are
+    /// added or removed, when they are moved by rebalancing and when they are given new NodeKeys.
+    /// Changing the contents of a node leaves it unchanged. Comparing generations tells whether
+    /// positions and NodeKeys saved earlier still describe the tree.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub(crate) fn bump_generation(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
     }
 
     /// Utility functon to check if the tree has a root node or not
@@ -67,6 +551,33 @@ impl<T: Clone + fmt::Debug> Tree<T> {
         self.root.is_some()
     }
 
+    /// Returns the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the tree contains no nodes
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all nodes from the tree.
+    /// The memory allocated for the nodes is kept so that the tree can be refilled without
+    /// reallocating. All existing NodeKeys are invalidated.
+    pub fn clear(&mut self) {
+        self.log_delete_all();
+        self.notify_delete_all();
+        self.bump_generation();
+        self.nodes.clear();
+        self.node_data.clear();
+        self.augmented.clear();
+        self.clear_side_data();
+        self.root = None;
+        self.len = 0;
+        self.leftmost = None;
+        self.rightmost = None;
+    }
+
     /// Creates a new root node for the tree and returns the NodeKey of the created node.
     ///
     /// # Arguments
@@ -75,10 +586,18 @@ impl<T: Clone + fmt::Debug> Tree<T> {
     ///
     pub fn create_root(&mut self, value: T) -> NodeKey {
         debug_assert!(!self.has_root());
+        self.begin_counting();
         let root = self.nodes.insert(Node::new());
         self.node_data.insert(root, value);
-        self.set_color(root, Color::BLACK);
+        self.update_augmented_node(root);
         self.root = Some(root);
+        self.leftmost = Some(root);
+        self.rightmost = Some(root);
+        B::inserted(self, root);
+        self.len += 1;
+        self.log_insert(root);
+        trace_event!(node = ?root, "create root");
+        self.validate_after_mutation();
         root
     }
 
@@ -91,6 +610,7 @@ impl<T: Clone + fmt::Debug> Tree<T> {
     /// * `value` - The value to populate the newly created node with
     ///
     pub fn insert_after(&mut self, existing_node: NodeKey, value: T) -> NodeKey {
+        self.begin_counting();
         let new_node = self.nodes.insert(Node::new());
         self.node_data.insert(new_node, value);
         let existing_node_next = self.get_next(existing_node);
@@ -103,15 +623,23 @@ impl<T: Clone + fmt::Debug> Tree<T> {
         }
 
         self.set_next(new_node, existing_node_next);
-        match self.get_next(new_node) {
-            Some(next) => self.set_prev(next, Some(new_node)),
-            None => {}
+        if let Some(next) = existing_node_next {
+            self.set_prev(next, Some(new_node));
         }
         self.set_prev(new_node, Some(existing_node));
         self.set_next(existing_node, Some(new_node));
+        if self.rightmost == Some(existing_node) {
+            self.rightmost = Some(new_node);
+        }
 
         // Balance the tree
-        self.insert_rebalance(new_node);
+        self.update_ancestor_sizes(new_node, |size| size + 1);
+        self.update_augmented(new_node);
+        B::inserted(self, new_node);
+        self.len += 1;
+        self.log_insert(new_node);
+        trace_event!(node = ?new_node, "insert");
+        self.validate_after_mutation();
 
         new_node
     }
@@ -125,6 +653,7 @@ impl<T: Clone + fmt::Debug> Tree<T> {
     /// * `value` - The value to populate the newly created node with
     ///
     pub fn insert_before(&mut self, existing_node: NodeKey, value: T) -> NodeKey {
+        self.begin_counting();
         let new_node = self.nodes.insert(Node::new());
         self.node_data.insert(new_node, value);
         let existing_node_prev = self.get_prev(existing_node);
@@ -136,84 +665,296 @@ impl<T: Clone + fmt::Debug> Tree<T> {
             self.set_parent(new_node, existing_node_prev);
         }
         self.set_prev(new_node, existing_node_prev);
-        if existing_node_prev.is_some() {
-            self.set_next(existing_node_prev.unwrap(), Some(new_node));
+        if let Some(prev) = existing_node_prev {
+            self.set_next(prev, Some(new_node));
         }
         self.set_next(new_node, Some(existing_node));
         self.set_prev(existing_node, Some(new_node));
+        if self.leftmost == Some(existing_node) {
+            self.leftmost = Some(new_node);
+        }
 
         // Balance the tree
-        self.insert_rebalance(new_node);
+        self.update_ancestor_sizes(new_node, |size| size + 1);
+        self.update_augmented(new_node);
+        B::inserted(self, new_node);
+        self.len += 1;
+        self.log_insert(new_node);
+        trace_event!(node = ?new_node, "insert");
+        self.validate_after_mutation();
 
         new_node
     }
 
-    /// Delete the specified node from the tree and rebalance the remaining nodes
+    /// Create and insert a new node at the position found by a binary descent of the tree and
+    /// rebalance the tree. The new node is placed after any nodes that compare equal.
+    /// Returns the NodeKey of the newly created node.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to populate the newly created node with
+    /// * `f` - Returns the ordering of a node's contents relative to the new value
+    ///
+    pub fn insert_by<F: FnMut(&T) -> Ordering>(&mut self, value: T, f: F) -> NodeKey {
+        let position = self.find_insert_position(f);
+        self.insert_at_position(position, value)
+    }
+
+    /// Create and insert a new node next to a hint node if the value belongs there, otherwise at
+    /// the position found by a binary descent of the tree, and rebalance the tree. Checking the
+    /// hint compares the value with at most two nodes, so inserting mostly sorted values with the
+    /// last inserted node as the hint avoids a descent for most of them. As with `insert_by` the
+    /// new node is placed after any nodes that compare equal.
+    /// Returns the NodeKey of the newly created node.
+    ///
+    /// # Arguments
+    ///
+    /// * `hint` - A node the value is expected to be inserted next to
+    /// * `value` - The value to populate the newly created node with
+    /// * `f` - Returns the ordering of a node's contents relative to the new value
+    ///
+    pub fn insert_with_hint<F: FnMut(&T) -> Ordering>(
+        &mut self,
+        hint: NodeKey,
+        value: T,
+        mut f: F,
+    ) -> NodeKey {
+        if f(self.get_contents(hint)) == Ordering::Greater {
+            let prev = self.get_prev(hint);
+            if prev.is_none_or(|prev| f(self.get_contents(prev)) != Ordering::Greater) {
+                return self.insert_before(hint, value);
+            }
+        } else {
+            let next = self.get_next(hint);
+            if next.is_none_or(|next| f(self.get_contents(next)) == Ordering::Greater) {
+                return self.insert_after(hint, value);
+            }
+        }
+        self.insert_by(value, f)
+    }
+
+    // Moves a node to the position found by a binary descent of the tree, after any nodes that
+    // compare equal as with `insert_by`, keeping its NodeKey. The node is cut out of the tree
+    // and spliced back in at its new position.
+    pub(crate) fn reposition_by<F: FnMut(&T) -> Ordering>(&mut self, node: NodeKey, f: F) {
+        self.begin_counting();
+        self.bump_generation();
+        self.without_structure_hook(|tree| {
+            tree.cut_run(node, node);
+            let prev = match tree.find_insert_position(f) {
+                InsertPosition::Root => None,
+                InsertPosition::Before(next) => tree.get_prev(next),
+                InsertPosition::After(prev) => Some(prev),
+            };
+            let root = B::link(tree, &[node]).unwrap();
+            B::make_root(tree, root);
+            let rank = B::rank(tree, Some(root));
+            tree.splice_after(prev, (root, rank), node, node);
+        });
+        trace_event!(?node, "reposition");
+        self.validate_after_mutation();
+    }
+
+    /// Create and insert a new node so that it ends up at the given position in the tree's
+    /// sequence and rebalance the tree. Returns the NodeKey of the newly created node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of nodes in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The zero based position the new node will have
+    /// * `value` - The value to populate the newly created node with
+    ///
+    pub fn insert_at(&mut self, index: usize, value: T) -> NodeKey {
+        assert!(
+            index <= self.len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            self.len
+        );
+        if index == self.len {
+            match self.rightmost {
+                Some(last) => self.insert_after(last, value),
+                None => self.create_root(value),
+            }
+        } else {
+            let existing_node = self.nth(index).unwrap();
+            self.insert_before(existing_node, value)
+        }
+    }
+
+    /// Replaces a node with three consecutive nodes, as when an arc of a beachline is split in
+    /// two by a new arc. The middle node reuses the existing node so its NodeKey stays valid.
+    /// Returns the NodeKeys of the left, middle and right nodes.
+    ///
+    /// The node is cut out of the tree and the three nodes are spliced back in as one balanced
+    /// subtree, so the tree is rebalanced and checked once for the whole replacement and is never
+    /// seen holding only two of the three nodes. The rotations made while splicing are not
+    /// reported to the callback registered by `set_on_structure_change`.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey of the node to replace
+    /// * `left_value` - The value to populate the new node before the middle with
+    /// * `middle_value` - The value to replace the contents of the existing node with
+    /// * `right_value` - The value to populate the new node after the middle with
+    ///
+    pub fn split_node(
+        &mut self,
+        node: NodeKey,
+        left_value: T,
+        middle_value: T,
+        right_value: T,
+    ) -> (NodeKey, NodeKey, NodeKey) {
+        self.begin_counting();
+        self.bump_generation();
+        let prev = self.get_prev(node);
+        self.log_contents(node);
+        let left = self.nodes.insert(Node::new());
+        self.node_data.insert(left, left_value);
+        self.node_data[node] = middle_value;
+        let right = self.nodes.insert(Node::new());
+        self.node_data.insert(right, right_value);
+
+        self.without_structure_hook(|tree| {
+            tree.cut_run(node, node);
+            tree.set_next(left, Some(node));
+            tree.set_prev(node, Some(left));
+            tree.set_next(node, Some(right));
+            tree.set_prev(right, Some(node));
+            let root = B::link(tree, &[left, node, right]).unwrap();
+            B::make_root(tree, root);
+            let rank = B::rank(tree, Some(root));
+            tree.splice_after(prev, (root, rank), left, right);
+        });
+        self.len += 2;
+        self.log_insert(left);
+        self.log_insert(right);
+        trace_event!(?node, "split node");
+        self.validate_after_mutation();
+        (left, node, right)
+    }
+
+    /// Delete the specified node from the tree and rebalance the remaining nodes.
+    /// Returns the contents of the deleted node.
     ///
     /// # Arguments
     ///
     /// * `node` - The NodeKey of the node to delete from the tree
     ///
-    pub fn delete_node(&mut self, node: NodeKey) {
+    pub fn delete_node(&mut self, node: NodeKey) -> T {
+        self.begin_counting();
+        self.bump_generation();
+        self.log_delete(node);
+        self.notify_delete(node);
+        if self.leftmost == Some(node) {
+            self.leftmost = self.get_next(node);
+        }
+        if self.rightmost == Some(node) {
+            self.rightmost = self.get_prev(node);
+        }
         if self.get_left(node).is_some() && self.get_right(node).is_some() {
             self.swap_nodes(node, self.get_next(node).unwrap());
         }
 
-        let replacement = self.get_replacement_node(node);
-        let both_black = self.get_color(Some(node)) == Color::BLACK
-            && self.get_color(replacement) == Color::BLACK;
-        if replacement.is_none() {
-            // The node is a leaf
-            if Some(node) == self.root {
-                // node is the root so set the root to None
-                self.root = None;
-            } else {
-                if both_black {
-                    // Both the node and the replacement are black
-                    // As v is a leaf we need to fix the double black at v
-                    self.fix_double_black(node);
-                } else {
-                    // The node must be red
-                    let sibling = self.get_sibling(node);
-                    if sibling.is_some() {
-                        self.set_color(sibling.unwrap(), Color::RED);
-                    }
-                }
-                let parent = self.get_parent(node);
-                match self.get_node_type(node) {
-                    NodeType::LeftChild => self.set_left(parent.unwrap(), None),
-                    NodeType::RightChild => self.set_right(parent.unwrap(), None),
-                    NodeType::Orphan => panic!("None root node can't be an orphan"),
-                }
-            }
-            self.update_order_for_deletion(node);
-            self.nodes.remove(node);
-        } else {
-            if Some(node) == self.root {
-                // Removing the root node
-                self.swap_nodes(node, replacement.unwrap());
-                self.set_left(replacement.unwrap(), None);
-                self.set_right(replacement.unwrap(), None);
-                self.nodes.remove(node);
+        // The node is removed from the subtree sizes straight away so that any rotations made while
+        // rebalancing see the final sizes
+        self.update_ancestor_sizes(node, |size| size - 1);
+        self.set_size(node, self.get_size(Some(node)) - 1);
+
+        B::remove(self, node);
+        self.update_order_for_deletion(node);
+        self.nodes.remove(node);
+        self.len -= 1;
+        self.augmented.remove(node);
+        self.remove_side_data(node);
+        let contents = self.node_data.remove(node).unwrap();
+        trace_event!(?node, "delete");
+        self.validate_after_mutation();
+        contents
+    }
+
+    // Descends the tree to find the leaf position for a new node, the comparison function gives the
+    // ordering of each node's contents relative to the new value. New nodes are placed after any
+    // nodes that compare equal so that insertion is stable.
+    fn find_insert_position<F: FnMut(&T) -> Ordering>(&self, mut f: F) -> InsertPosition {
+        let mut position = InsertPosition::Root;
+        let mut node = self.root;
+        while let Some(current) = node {
+            if f(self.get_contents(current)) == Ordering::Greater {
+                position = InsertPosition::Before(current);
+                node = self.get_left(current);
             } else {
-                let parent = self.get_parent(node);
-                match self.get_node_type(node) {
-                    NodeType::LeftChild => self.set_left(parent.unwrap(), replacement),
-                    NodeType::RightChild => self.set_right(parent.unwrap(), replacement),
-                    NodeType::Orphan => panic!("None root node can't be an orphan"),
-                }
-                if replacement.is_some() {
-                    self.set_parent(replacement.unwrap(), parent);
-                }
-                self.update_order_for_deletion(node);
-                self.nodes.remove(node);
-                if both_black {
-                    self.fix_double_black(node);
-                } else {
-                    self.set_color(replacement.unwrap(), Color::BLACK);
-                }
+                position = InsertPosition::After(current);
+                node = self.get_right(current);
             }
         }
+        position
+    }
+
+    // Inserts a new node at a position found by descending the tree
+    fn insert_at_position(&mut self, position: InsertPosition, value: T) -> NodeKey {
+        match position {
+            InsertPosition::Root => self.create_root(value),
+            InsertPosition::Before(node) => self.insert_before(node, value),
+            InsertPosition::After(node) => self.insert_after(node, value),
+        }
+    }
+
+    /// Removes the node at the given position in the tree's sequence and returns its contents, or
+    /// None if the index is out of bounds
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The zero based position of the node to remove
+    ///
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        let node = self.nth(index)?;
+        Some(self.delete_node(node))
+    }
+
+    /// Removes the first node in the tree and returns its contents, or None if the tree is empty
+    pub fn pop_first(&mut self) -> Option<T> {
+        let first = self.get_leftmost_node()?;
+        Some(self.delete_node(first))
+    }
+
+    /// Removes the last node in the tree and returns its contents, or None if the tree is empty
+    pub fn pop_last(&mut self) -> Option<T> {
+        let last = self.get_rightmost_node()?;
+        Some(self.delete_node(last))
+    }
+
+    /// Returns a copy of the contents of every node in the tree, in order
+    pub fn to_vec(&self) -> Vec<T> {
+        let mut values = Vec::with_capacity(self.len);
+        let mut node = self.get_leftmost_node();
+        while let Some(current) = node {
+            values.push(self.get_contents(current).clone());
+            node = self.get_next(current);
+        }
+        values
+    }
+
+    /// Returns an iterator over every node in the tree along with a reference to its contents, in
+    /// order
+    pub fn iter_with_keys(&self) -> impl Iterator<Item = (NodeKey, &T)> + '_ {
+        std::iter::successors(self.get_leftmost_node(), move |&node| self.get_next(node))
+            .map(move |node| (node, self.get_contents(node)))
+    }
+
+    /// Consumes the tree and returns the contents of every node, in order.
+    /// If the nodes were inserted with `insert_sorted` the returned vector is sorted.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut values = Vec::with_capacity(self.len);
+        let mut node = self.get_leftmost_node();
+        while let Some(current) = node {
+            values.push(self.node_data.remove(current).unwrap());
+            node = self.get_next(current);
+        }
+        values
     }
 
     // Finds the node that will replace a deleted node in the tree
@@ -237,104 +978,111 @@ impl<T: Clone + fmt::Debug> Tree<T> {
 
     // Updates the prev and next entrys of a node that is being deleted to ensure that the order of the nodes is correct
     fn update_order_for_deletion(&mut self, deleted_node: NodeKey) {
+        if cfg!(not(feature = "threading")) {
+            // The node may already be partly unlinked, so its neighbours can't be found by walking
+            return;
+        }
         let next = self.get_next(deleted_node);
         let prev = self.get_prev(deleted_node);
-        if next.is_some() {
-            self.set_prev(next.unwrap(), prev);
+        if let Some(next) = next {
+            self.set_prev(next, prev);
         }
-        if prev.is_some() {
-            self.set_next(prev.unwrap(), next);
+        if let Some(prev) = prev {
+            self.set_next(prev, next);
         }
     }
 
     // Fix a double black node that has been caused by deleting a node
     fn fix_double_black(&mut self, mut node: NodeKey) {
         while Some(node) != self.root {
-            let sibling = self.get_sibling(node);
+            self.count_fixup_iteration();
             let parent = self.get_parent(node);
-            if sibling.is_none() {
-                // No sibling, double black is pushed up
-                node = parent.unwrap()
-            } else {
-                if self.get_color(sibling) == Color::RED {
-                    // Sibling is red
-                    self.set_color(parent.unwrap(), Color::RED);
-                    self.set_color(sibling.unwrap(), Color::BLACK);
-                    match self.get_node_type(sibling.unwrap()) {
-                        NodeType::LeftChild => self.right_rotate(parent.unwrap()),
-                        NodeType::RightChild => self.left_rotate(parent.unwrap()),
-                        NodeType::Orphan => panic!("A sibling node can't be an orphan"),
+            let sibling = match self.get_sibling(node) {
+                Some(sibling) => sibling,
+                None => {
+                    // No sibling, double black is pushed up
+                    node = parent.unwrap();
+                    continue;
+                }
+            };
+            if self.get_color(Some(sibling)) == Color::Red {
+                // Sibling is red
+                self.set_color(parent.unwrap(), Color::Red);
+                self.set_color(sibling, Color::Black);
+                match self.get_node_type(sibling) {
+                    NodeType::LeftChild => self.right_rotate(parent.unwrap()),
+                    NodeType::RightChild => self.left_rotate(parent.unwrap()),
+                    NodeType::Orphan => panic!("A sibling node can't be an orphan"),
+                }
+            } else if self.get_color(self.get_left(sibling)) == Color::Red {
+                let left = self.get_left(sibling).unwrap();
+                match self.get_node_type(sibling) {
+                    NodeType::LeftChild => {
+                        // Left-Left
+                        self.set_color(left, self.get_color(Some(sibling)));
+                        self.set_color(sibling, self.get_color(parent));
+                        self.right_rotate(parent.unwrap());
                     }
-                } else {
-                    if self.get_color(self.get_left(sibling.unwrap())) == Color::RED {
-                        let left = self.get_left(sibling.unwrap()).unwrap();
-                        match self.get_node_type(sibling.unwrap()) {
-                            NodeType::LeftChild => {
-                                // Left-Left
-                                self.set_color(left, self.get_color(sibling));
-                                self.set_color(sibling.unwrap(), self.get_color(parent));
-                                self.right_rotate(parent.unwrap());
-                            }
-                            NodeType::RightChild => {
-                                // Right-Left
-                                self.set_color(left, self.get_color(parent));
-                                self.right_rotate(sibling.unwrap());
-                                self.left_rotate(parent.unwrap());
-                            }
-                            NodeType::Orphan => panic!("A sibling node can't be an orphan"),
-                        }
-                        self.set_color(parent.unwrap(), Color::BLACK);
-                        break;
-                    } else if self.get_color(self.get_right(sibling.unwrap())) == Color::RED {
-                        let right = self.get_right(sibling.unwrap()).unwrap();
-                        match self.get_node_type(sibling.unwrap()) {
-                            NodeType::LeftChild => {
-                                // Left-Right
-                                self.set_color(right, self.get_color(parent));
-                                self.left_rotate(sibling.unwrap());
-                                self.right_rotate(parent.unwrap());
-                            }
-                            NodeType::RightChild => {
-                                // Right-Right
-                                self.set_color(right, self.get_color(sibling));
-                                self.set_color(sibling.unwrap(), self.get_color(parent));
-                                self.left_rotate(parent.unwrap());
-                            }
-                            NodeType::Orphan => panic!("A sibling node can't be an orphan"),
-                        }
-                        self.set_color(parent.unwrap(), Color::BLACK);
-                        break;
-                    } else {
-                        // 2 Black children
-                        self.set_color(sibling.unwrap(), Color::RED);
-                        if self.get_color(parent) == Color::BLACK {
-                            node = parent.unwrap();
-                        } else {
-                            self.set_color(parent.unwrap(), Color::BLACK);
-                            break;
-                        }
+                    NodeType::RightChild => {
+                        // Right-Left
+                        self.set_color(left, self.get_color(parent));
+                        self.right_rotate(sibling);
+                        self.left_rotate(parent.unwrap());
                     }
+                    NodeType::Orphan => panic!("A sibling node can't be an orphan"),
+                }
+                self.set_color(parent.unwrap(), Color::Black);
+                break;
+            } else if self.get_color(self.get_right(sibling)) == Color::Red {
+                let right = self.get_right(sibling).unwrap();
+                match self.get_node_type(sibling) {
+                    NodeType::LeftChild => {
+                        // Left-Right
+                        self.set_color(right, self.get_color(parent));
+                        self.left_rotate(sibling);
+                        self.right_rotate(parent.unwrap());
+                    }
+                    NodeType::RightChild => {
+                        // Right-Right
+                        self.set_color(right, self.get_color(Some(sibling)));
+                        self.set_color(sibling, self.get_color(parent));
+                        self.left_rotate(parent.unwrap());
+                    }
+                    NodeType::Orphan => panic!("A sibling node can't be an orphan"),
+                }
+                self.set_color(parent.unwrap(), Color::Black);
+                break;
+            } else {
+                // 2 Black children
+                self.set_color(sibling, Color::Red);
+                if self.get_color(parent) == Color::Black {
+                    node = parent.unwrap();
+                } else {
+                    self.set_color(parent.unwrap(), Color::Black);
+                    break;
                 }
             }
         }
     }
 
     // Rebalances the tree after inserting a new node
-    fn insert_rebalance(&mut self, mut node: NodeKey) {
-        while self.get_color(self.get_parent(node)) == Color::RED {
+    // Returns true if the root had to be recolored black, which grows the black height of the tree
+    fn insert_rebalance(&mut self, mut node: NodeKey) -> bool {
+        while self.get_color(self.get_parent(node)) == Color::Red {
+            self.count_fixup_iteration();
             // Only get here for cases 3, 4 and 5, cases 1 and 2 are trivial
             // Parent is RED so it exists
             let mut parent = self.get_parent(node).unwrap();
             // As parent is red it isn't the root so can get a grandparent
             let grandparent = self.get_parent(parent).unwrap();
             let uncle = self.get_uncle(node);
-            if self.get_color(uncle) == Color::RED {
+            if self.get_color(uncle) == Color::Red {
                 // Uncle is red so we can balance by re-coloring parent and uncle red
-                self.set_color(uncle.unwrap(), Color::BLACK);
+                self.set_color(uncle.unwrap(), Color::Black);
                 // Uncle is red so can safely unwrap
-                self.set_color(parent, Color::BLACK);
+                self.set_color(parent, Color::Black);
                 // Set grandparent to red and recurse up up the tree
-                self.set_color(grandparent, Color::RED);
+                self.set_color(grandparent, Color::Red);
                 node = grandparent;
             } else {
                 // Uncle is black We will need to do some rotations
@@ -352,8 +1100,8 @@ impl<T: Clone + fmt::Debug> Tree<T> {
                 }
                 // Left-Left or Right-Right Case
                 // Uncle is black so we will need to rotate the grandparent away from the conflict(to the right)
-                self.set_color(parent, Color::BLACK);
-                self.set_color(grandparent, Color::RED);
+                self.set_color(parent, Color::Black);
+                self.set_color(grandparent, Color::Red);
                 if self.get_node_type(parent) == NodeType::LeftChild {
                     self.right_rotate(grandparent);
                 } else {
@@ -361,7 +1109,10 @@ impl<T: Clone + fmt::Debug> Tree<T> {
                 }
             }
         }
-        self.set_color(self.root.unwrap(), Color::BLACK);
+        let root = self.root.unwrap();
+        let grew = self.get_color(Some(root)) == Color::Red;
+        self.set_color(root, Color::Black);
+        grew
     }
 
     // Roates the nodes to the left
@@ -371,14 +1122,25 @@ impl<T: Clone + fmt::Debug> Tree<T> {
     //     / \        / \
     //    b   c      a   b
     fn left_rotate(&mut self, rotation_root: NodeKey) {
+        self.count_rotation();
         // Left rotation so pivot is to the right
         let pivot = self.get_right(rotation_root).unwrap();
         let pivot_left = self.get_left(pivot);
         let parent = self.get_parent(rotation_root);
+
+        // The pivot takes over the whole subtree, the rotation root loses the pivot's right subtree
+        let rotation_root_size = self.get_size(Some(rotation_root));
+        let pivot_size = self.get_size(Some(pivot));
+        self.set_size(
+            rotation_root,
+            rotation_root_size - pivot_size + self.get_size(pivot_left),
+        );
+        self.set_size(pivot, rotation_root_size);
+
         // The left child of the pivot becomes the right child of the rotation root
         self.set_right(rotation_root, pivot_left);
-        if pivot_left.is_some() {
-            self.set_parent(pivot_left.unwrap(), Some(rotation_root));
+        if let Some(pivot_left) = pivot_left {
+            self.set_parent(pivot_left, Some(rotation_root));
         }
 
         // The pivot replaces the rotation root in the tree
@@ -392,6 +1154,14 @@ impl<T: Clone + fmt::Debug> Tree<T> {
         // Set the left child of the pivot to be the rotation root
         self.set_left(pivot, Some(rotation_root));
         self.set_parent(rotation_root, Some(pivot));
+
+        self.update_augmented_node(rotation_root);
+        self.update_augmented_node(pivot);
+        trace_event!(root = ?rotation_root, ?pivot, "left rotate");
+        self.notify_structure_change(StructureChange::LeftRotate {
+            root: rotation_root,
+            pivot,
+        });
     }
 
     // Rotates the nodes to the right
@@ -401,14 +1171,25 @@ impl<T: Clone + fmt::Debug> Tree<T> {
     //  / \               / \
     // a   b             b   c
     fn right_rotate(&mut self, rotation_root: NodeKey) {
+        self.count_rotation();
         // Right rotation so pivot is to the left
         let pivot = self.get_left(rotation_root).unwrap();
         let pivot_right = self.get_right(pivot);
         let parent = self.get_parent(rotation_root);
+
+        // The pivot takes over the whole subtree, the rotation root loses the pivot's left subtree
+        let rotation_root_size = self.get_size(Some(rotation_root));
+        let pivot_size = self.get_size(Some(pivot));
+        self.set_size(
+            rotation_root,
+            rotation_root_size - pivot_size + self.get_size(pivot_right),
+        );
+        self.set_size(pivot, rotation_root_size);
+
         // The right child of the pivot becomes the left child of the rotation root
         self.set_left(rotation_root, pivot_right);
-        if pivot_right.is_some() {
-            self.set_parent(pivot_right.unwrap(), Some(rotation_root));
+        if let Some(pivot_right) = pivot_right {
+            self.set_parent(pivot_right, Some(rotation_root));
         }
 
         // The pivot replaces the rotation root in the tree
@@ -422,6 +1203,14 @@ impl<T: Clone + fmt::Debug> Tree<T> {
         // Set the right child of the pivot to be the rotation root
         self.set_right(pivot, Some(rotation_root));
         self.set_parent(rotation_root, Some(pivot));
+
+        self.update_augmented_node(rotation_root);
+        self.update_augmented_node(pivot);
+        trace_event!(root = ?rotation_root, ?pivot, "right rotate");
+        self.notify_structure_change(StructureChange::RightRotate {
+            root: rotation_root,
+            pivot,
+        });
     }
 
     // Swap the location in the tree of two nodes
@@ -454,50 +1243,70 @@ impl<T: Clone + fmt::Debug> Tree<T> {
         // Swap Left Children
         if node_2_left != Some(node_1) {
             self.set_left(node_1, node_2_left);
-            if node_2_left.is_some() {
-                self.set_parent(node_2_left.unwrap(), Some(node_1));
+            if let Some(node_2_left) = node_2_left {
+                self.set_parent(node_2_left, Some(node_1));
             }
         }
         if node_1_left != Some(node_2) {
             self.set_left(node_2, node_1_left);
-            if node_1_left.is_some() {
-                self.set_parent(node_1_left.unwrap(), Some(node_2));
+            if let Some(node_1_left) = node_1_left {
+                self.set_parent(node_1_left, Some(node_2));
             }
         }
 
         // Swap Right Children
         if node_2_right != Some(node_1) {
             self.set_right(node_1, node_2_right);
-            if node_2_right.is_some() {
-                self.set_parent(node_2_right.unwrap(), Some(node_1));
+            if let Some(node_2_right) = node_2_right {
+                self.set_parent(node_2_right, Some(node_1));
             }
         }
         if node_1_right != Some(node_2) {
             self.set_right(node_2, node_1_right);
-            if node_1_right.is_some() {
-                self.set_parent(node_1_right.unwrap(), Some(node_2));
+            if let Some(node_1_right) = node_1_right {
+                self.set_parent(node_1_right, Some(node_2));
             }
         }
 
-        // Swap Colors
-        let node_1_color = self.get_color(Some(node_1));
-        self.set_color(node_1, self.get_color(Some(node_2)));
-        self.set_color(node_2, node_1_color);
+        // Swap the balance data, such as the colors
+        let node_1_rank = self.get_rank(Some(node_1));
+        self.set_rank(node_1, self.get_rank(Some(node_2)));
+        self.set_rank(node_2, node_1_rank);
+
+        // Swap subtree sizes
+        let node_1_size = self.get_size(Some(node_1));
+        self.set_size(node_1, self.get_size(Some(node_2)));
+        self.set_size(node_2, node_1_size);
+
+        // Both nodes have new subtrees. If one is below the other the walk up from the lower one
+        // recomputes the upper one again, so the order of the updates doesn't matter.
+        self.update_augmented(node_1);
+        self.update_augmented(node_2);
+        self.notify_structure_change(StructureChange::Swap(node_1, node_2));
+    }
+
+    // Applies an update to the subtree sizes of all the ancestors of a node
+    fn update_ancestor_sizes<F: Fn(usize) -> usize>(&mut self, node: NodeKey, update: F) {
+        let mut ancestor = self.get_parent(node);
+        while let Some(current) = ancestor {
+            self.set_size(current, update(self.get_size(Some(current))));
+            ancestor = self.get_parent(current);
+        }
     }
 
     // Returns a NodeType enum indicating if the given node is a left child, right child in
     // relation to it's parent or an orphan
     fn get_node_type(&self, node: NodeKey) -> NodeType {
-        let parent = self.get_parent(node);
-        if parent.is_some() {
-            if self.get_left(parent.unwrap()) == Some(node) {
-                NodeType::LeftChild
-            } else {
-                NodeType::RightChild
+        match self.get_parent(node) {
+            Some(parent) => {
+                if self.get_left(parent) == Some(node) {
+                    NodeType::LeftChild
+                } else {
+                    NodeType::RightChild
+                }
             }
-        } else {
             // Not a child node at all
-            NodeType::Orphan
+            None => NodeType::Orphan,
         }
     }
 
@@ -513,20 +1322,17 @@ impl<T: Clone + fmt::Debug> Tree<T> {
 
     // Returns the uncle node of the current node, that is the sibling of the parent node if it exists.
     fn get_uncle(&self, node: NodeKey) -> Option<NodeKey> {
-        let parent = self.get_parent(node);
-        if parent.is_some() {
-            match self.get_node_type(parent.unwrap()) {
-                NodeType::LeftChild => self.get_right(self.get_parent(parent.unwrap()).unwrap()),
-                NodeType::RightChild => self.get_left(self.get_parent(parent.unwrap()).unwrap()),
-                NodeType::Orphan => None,
-            }
-        } else {
-            None
+        let parent = self.get_parent(node)?;
+        match self.get_node_type(parent) {
+            NodeType::LeftChild => self.get_right(self.get_parent(parent).unwrap()),
+            NodeType::RightChild => self.get_left(self.get_parent(parent).unwrap()),
+            NodeType::Orphan => None,
         }
     }
 
     // Getter and setters
     fn set_right(&mut self, node: NodeKey, right: Option<NodeKey>) {
+        self.bump_generation();
         let node = self.nodes.get_mut(node).unwrap();
         node.right = right;
     }
@@ -537,6 +1343,7 @@ impl<T: Clone + fmt::Debug> Tree<T> {
     }
 
     fn set_left(&mut self, node: NodeKey, left: Option<NodeKey>) {
+        self.bump_generation();
         let node = self.nodes.get_mut(node).unwrap();
         node.left = left;
     }
@@ -547,6 +1354,7 @@ impl<T: Clone + fmt::Debug> Tree<T> {
     }
 
     fn set_parent(&mut self, node: NodeKey, parent: Option<NodeKey>) {
+        self.bump_generation();
         let node = self.nodes.get_mut(node).unwrap();
         node.parent = parent;
     }
@@ -556,39 +1364,134 @@ impl<T: Clone + fmt::Debug> Tree<T> {
         node.parent
     }
 
+    #[cfg(feature = "threading")]
     fn set_prev(&mut self, node: NodeKey, prev: Option<NodeKey>) {
         let node = self.nodes.get_mut(node).unwrap();
         node.prev = prev;
     }
 
+    // Without threading the order is implied by the tree structure alone
+    #[cfg(not(feature = "threading"))]
+    fn set_prev(&mut self, _node: NodeKey, _prev: Option<NodeKey>) {}
+
+    /// Returns the node before the specified node
+    #[cfg(feature = "threading")]
     pub fn get_prev(&self, node: NodeKey) -> Option<NodeKey> {
         let node = self.nodes.get(node).unwrap();
         node.prev
     }
 
+    /// Returns the node before the specified node, found by walking the tree structure
+    #[cfg(not(feature = "threading"))]
+    pub fn get_prev(&self, node: NodeKey) -> Option<NodeKey> {
+        self.structural_neighbor(
+            node,
+            |tree, node| tree.get_left(node),
+            |tree, node| tree.get_right(node),
+        )
+    }
+
+    #[cfg(feature = "threading")]
     fn set_next(&mut self, node: NodeKey, next: Option<NodeKey>) {
         let node = self.nodes.get_mut(node).unwrap();
         node.next = next;
     }
 
+    #[cfg(not(feature = "threading"))]
+    fn set_next(&mut self, _node: NodeKey, _next: Option<NodeKey>) {}
+
+    /// Returns the node after the specified node
+    #[cfg(feature = "threading")]
     pub fn get_next(&self, node: NodeKey) -> Option<NodeKey> {
         let node = self.nodes.get(node).unwrap();
         node.next
     }
 
+    /// Returns the node after the specified node, found by walking the tree structure
+    #[cfg(not(feature = "threading"))]
+    pub fn get_next(&self, node: NodeKey) -> Option<NodeKey> {
+        self.structural_neighbor(
+            node,
+            |tree, node| tree.get_right(node),
+            |tree, node| tree.get_left(node),
+        )
+    }
+
+    // Finds the neighbour of a node on one side by walking the tree. `towards` gives the child on
+    // the side of the neighbour and `away` the child on the other side. The neighbour is the
+    // nearest node of the subtree towards that side, or else the first ancestor reached from the
+    // away side.
+    #[cfg(not(feature = "threading"))]
+    fn structural_neighbor<F, G>(&self, node: NodeKey, towards: F, away: G) -> Option<NodeKey>
+    where
+        F: Fn(&Self, NodeKey) -> Option<NodeKey>,
+        G: Fn(&Self, NodeKey) -> Option<NodeKey>,
+    {
+        if let Some(mut current) = towards(self, node) {
+            while let Some(child) = away(self, current) {
+                current = child;
+            }
+            return Some(current);
+        }
+        let mut current = node;
+        while let Some(parent) = self.get_parent(current) {
+            if away(self, parent) == Some(current) {
+                return Some(parent);
+            }
+            current = parent;
+        }
+        None
+    }
+
     fn set_color(&mut self, node: NodeKey, color: Color) {
+        let links = self.nodes.get_mut(node).unwrap();
+        if links.color() != color {
+            links.set_color(color);
+            trace_event!(?node, ?color, "recolor");
+            self.count_recoloring();
+        }
+    }
+
+    // Without the counters feature the rebalancing work isn't recorded
+    #[cfg(not(feature = "counters"))]
+    fn begin_counting(&mut self) {}
+
+    #[cfg(not(feature = "counters"))]
+    fn count_rotation(&mut self) {}
+
+    #[cfg(not(feature = "counters"))]
+    fn count_recoloring(&mut self) {}
+
+    #[cfg(not(feature = "counters"))]
+    fn count_fixup_iteration(&mut self) {}
+
+    fn set_size(&mut self, node: NodeKey, size: usize) {
         let node = self.nodes.get_mut(node).unwrap();
-        node.color = color;
+        node.set_size(size);
+    }
+
+    fn get_size(&self, node: Option<NodeKey>) -> usize {
+        match node.and_then(|node| self.nodes.get(node)) {
+            Some(node) => node.size(),
+            None => 0,
+        }
     }
 
     fn get_color(&self, node: Option<NodeKey>) -> Color {
-        if node.is_none() {
-            Color::BLACK
-        } else {
-            match self.nodes.get(node.unwrap()) {
-                Some(node) => node.color,
-                None => Color::BLACK,
-            }
+        match node.and_then(|node| self.nodes.get(node)) {
+            Some(node) if B::is_red(node) => Color::Red,
+            _ => Color::Black,
+        }
+    }
+
+    fn set_rank(&mut self, node: NodeKey, rank: usize) {
+        self.nodes.get_mut(node).unwrap().set_rank(rank);
+    }
+
+    fn get_rank(&self, node: Option<NodeKey>) -> usize {
+        match node.and_then(|node| self.nodes.get(node)) {
+            Some(node) => node.rank(),
+            None => 0,
         }
     }
 
@@ -600,37 +1503,381 @@ impl<T: Clone + fmt::Debug> Tree<T> {
     /// * `contents` - The new contents to populate the node with
     ///
     pub fn set_contents(&mut self, node: NodeKey, contents: T) {
+        self.log_contents(node);
         self.node_data[node] = contents;
+        self.update_augmented(node);
     }
 
-    /// Returns a refernence to the contents of the specified node
+    /// Replaces the contents of the specified node, returning the old contents
     ///
     /// # Arguments
     ///
-    /// * `node` - The node to return the contents of
+    /// * `node` - The node to replace the contents of
+    /// * `contents` - The new contents to populate the node with
     ///
-    pub fn get_contents(&self, node: NodeKey) -> &T {
+    pub fn replace(&mut self, node: NodeKey, contents: T) -> T {
+        self.log_contents(node);
+        let old = std::mem::replace(&mut self.node_data[node], contents);
+        self.update_augmented(node);
+        old
+    }
+
+    /// Exchanges the contents of two nodes, leaving the structure of the tree unchanged
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The first node
+    /// * `b` - The second node
+    ///
+    pub fn swap_contents(&mut self, a: NodeKey, b: NodeKey) {
+        if a == b {
+            return;
+        }
+        self.log_contents(a);
+        self.log_contents(b);
+        let a_contents = self.node_data.remove(a).unwrap();
+        let b_contents = self.node_data.remove(b).unwrap();
+        self.node_data.insert(a, b_contents);
+        self.node_data.insert(b, a_contents);
+        self.update_augmented(a);
+        self.update_augmented(b);
+    }
+
+    /// Returns a refernence to the contents of the specified node
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to return the contents of
+    ///
+    pub fn get_contents(&self, node: NodeKey) -> &T {
         &self.node_data[node]
     }
 
-    /// Returns a mutable refernence to the contents of the specified node
+    /// Returns a mutable refernence to the contents of the specified node.
+    /// If the tree is augmented, `update_augmented` must be called after modifying the contents.
     ///
     /// # Arguments
     ///
     /// * `node` - The node to return the contents of
     ///
     pub fn get_mut_contents(&mut self, node: NodeKey) -> &mut T {
+        self.log_contents(node);
         &mut self.node_data[node]
     }
 
+    /// Returns the nodes immediately before and after the specified node
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to return the neighbors of
+    ///
+    pub fn neighbors(&self, node: NodeKey) -> (Option<NodeKey>, Option<NodeKey>) {
+        (self.get_prev(node), self.get_next(node))
+    }
+
+    /// Returns references to the contents of the nodes immediately before and after the
+    /// specified node
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to return the neighbors of
+    ///
+    pub fn neighbor_contents(&self, node: NodeKey) -> (Option<&T>, Option<&T>) {
+        let (prev, next) = self.neighbors(node);
+        (
+            prev.map(|prev| self.get_contents(prev)),
+            next.map(|next| self.get_contents(next)),
+        )
+    }
+
+    /// Returns the first node in the tree, or None if the tree is empty
     pub fn get_leftmost_node(&self) -> Option<NodeKey> {
+        self.leftmost
+    }
+
+    /// Returns the last node in the tree, or None if the tree is empty
+    pub fn get_rightmost_node(&self) -> Option<NodeKey> {
+        self.rightmost
+    }
+
+    /// Returns the node at the given position in the tree's sequence, or None if the index is out
+    /// of bounds
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The zero based position of the node to find
+    ///
+    pub fn nth(&self, mut index: usize) -> Option<NodeKey> {
         let mut node = self.root;
-        if node.is_some() {
-            while self.get_left(node.unwrap()).is_some() {
-                node = self.get_left(node.unwrap());
+        while let Some(current) = node {
+            let left = self.get_left(current);
+            let left_size = self.get_size(left);
+            match index.cmp(&left_size) {
+                Ordering::Less => node = left,
+                Ordering::Equal => return Some(current),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    node = self.get_right(current);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the zero based position of the specified node in the tree's sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to find the position of
+    ///
+    pub fn rank_of(&self, node: NodeKey) -> usize {
+        let mut rank = self.get_size(self.get_left(node));
+        let mut current = node;
+        while let Some(parent) = self.get_parent(current) {
+            if self.get_node_type(current) == NodeType::RightChild {
+                rank += self.get_size(self.get_left(parent)) + 1;
             }
+            current = parent;
+        }
+        rank
+    }
+
+    /// Returns the number of nodes on the longest path from the root down to a leaf, 0 for an
+    /// empty tree. Every node is visited. Red-black balancing keeps this within
+    /// `2 * log2(len + 1)` and AVL balancing within `1.44 * log2(len + 2)`.
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack: Vec<(NodeKey, usize)> =
+            self.root.map(|root| (root, 1)).into_iter().collect();
+        while let Some((node, depth)) = stack.pop() {
+            height = height.max(depth);
+            for child in [self.get_left(node), self.get_right(node)].iter().flatten() {
+                stack.push((*child, depth + 1));
+            }
+        }
+        height
+    }
+
+    /// Returns the number of black nodes on every path from the root down to a leaf, 0 for an
+    /// empty tree
+    pub fn black_height(&self) -> usize {
+        self.subtree_black_height(self.root)
+    }
+
+    /// Returns the number of edges between the specified node and the root, 0 for the root
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to find the depth of
+    ///
+    pub fn depth(&self, node: NodeKey) -> usize {
+        let mut depth = 0;
+        let mut current = node;
+        while let Some(parent) = self.get_parent(current) {
+            depth += 1;
+            current = parent;
+        }
+        depth
+    }
+
+    /// Returns an iterator over the ancestors of the specified node, from its parent up to the
+    /// root. The iterator is empty for the root.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to walk up from
+    ///
+    pub fn ancestors(&self, node: NodeKey) -> impl Iterator<Item = NodeKey> + '_ {
+        std::iter::successors(self.get_parent(node), move |&parent| {
+            self.get_parent(parent)
+        })
+    }
+
+    /// Returns an iterator over the nodes of the subtree rooted at the specified node, in the order
+    /// they appear in the sequence. The subtree is a contiguous run of the sequence, so the nodes
+    /// are walked from its first node for as many steps as there are nodes in it.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The root of the subtree to iterate over
+    ///
+    pub fn iter_subtree(&self, node: NodeKey) -> impl Iterator<Item = NodeKey> + '_ {
+        let mut first = node;
+        while let Some(left) = self.get_left(first) {
+            first = left;
         }
-        node
+        std::iter::successors(Some(first), move |&current| self.get_next(current))
+            .take(self.get_size(Some(node)))
+    }
+
+    /// Returns true if the first node lies on the path from the second node up to the root. A node
+    /// is not its own ancestor.
+    ///
+    /// # Arguments
+    ///
+    /// * `ancestor` - The node to look for above the other node
+    /// * `node` - The node to walk up from
+    ///
+    pub fn is_ancestor(&self, ancestor: NodeKey, node: NodeKey) -> bool {
+        self.ancestors(node).any(|current| current == ancestor)
+    }
+
+    /// Returns true if the specified node is in the tree, false if the NodeKey is stale
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey to check
+    ///
+    pub fn contains_node(&self, node: NodeKey) -> bool {
+        self.nodes.contains_key(node)
+    }
+
+    /// Returns the right child of the specified node, or None if the NodeKey is stale
+    pub fn try_get_right(&self, node: NodeKey) -> Option<Option<NodeKey>> {
+        self.nodes.get(node).map(|node| node.right)
+    }
+
+    /// Returns the left child of the specified node, or None if the NodeKey is stale
+    pub fn try_get_left(&self, node: NodeKey) -> Option<Option<NodeKey>> {
+        self.nodes.get(node).map(|node| node.left)
+    }
+
+    /// Returns the parent of the specified node, or None if the NodeKey is stale
+    pub fn try_get_parent(&self, node: NodeKey) -> Option<Option<NodeKey>> {
+        self.nodes.get(node).map(|node| node.parent)
+    }
+
+    /// Returns the node before the specified node, or None if the NodeKey is stale
+    pub fn try_get_prev(&self, node: NodeKey) -> Option<Option<NodeKey>> {
+        if self.nodes.contains_key(node) {
+            Some(self.get_prev(node))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the node after the specified node, or None if the NodeKey is stale
+    pub fn try_get_next(&self, node: NodeKey) -> Option<Option<NodeKey>> {
+        if self.nodes.contains_key(node) {
+            Some(self.get_next(node))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the contents of the specified node, or None if the NodeKey is stale
+    pub fn try_get_contents(&self, node: NodeKey) -> Option<&T> {
+        self.node_data.get(node)
+    }
+
+    /// Returns a mutable reference to the contents of the specified node, or None if the NodeKey
+    /// is stale
+    pub fn try_get_mut_contents(&mut self, node: NodeKey) -> Option<&mut T> {
+        if self.node_data.contains_key(node) {
+            self.log_contents(node);
+        }
+        self.node_data.get_mut(node)
+    }
+
+    /// Sets the contents of the specified node.
+    /// Returns the contents back as an error if the NodeKey is stale.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to set the contents on
+    /// * `contents` - The new contents to populate the node with
+    ///
+    pub fn try_set_contents(&mut self, node: NodeKey, contents: T) -> Result<(), T> {
+        if !self.node_data.contains_key(node) {
+            return Err(contents);
+        }
+        self.set_contents(node, contents);
+        Ok(())
+    }
+
+    // Returns an error if the NodeKey doesn't refer to a node in the tree
+    fn check_node(&self, node: NodeKey) -> Result<(), TreeError> {
+        if self.contains_node(node) {
+            Ok(())
+        } else {
+            Err(TreeError::StaleKey(node))
+        }
+    }
+
+    /// Creates a new root node for the tree and returns the NodeKey of the created node.
+    /// Returns an error rather than panicking if the tree already has a root.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to populate the new node with
+    ///
+    pub fn try_create_root(&mut self, value: T) -> Result<NodeKey, TreeError> {
+        if self.has_root() {
+            return Err(TreeError::RootAlreadyExists);
+        }
+        Ok(self.create_root(value))
+    }
+
+    /// Create and insert a new node immediately after the specified node and rebalance the tree.
+    /// Returns an error rather than panicking if the existing node is not in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `existing_node` - The NodeKey of the existing node to insert the new node after
+    /// * `value` - The value to populate the newly created node with
+    ///
+    pub fn try_insert_after(
+        &mut self,
+        existing_node: NodeKey,
+        value: T,
+    ) -> Result<NodeKey, TreeError> {
+        self.check_node(existing_node)?;
+        Ok(self.insert_after(existing_node, value))
+    }
+
+    /// Create and insert a new node immediately before the specified node and rebalance the tree.
+    /// Returns an error rather than panicking if the existing node is not in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `existing_node` - The NodeKey of the existing node to insert the new node before
+    /// * `value` - The value to populate the newly created node with
+    ///
+    pub fn try_insert_before(
+        &mut self,
+        existing_node: NodeKey,
+        value: T,
+    ) -> Result<NodeKey, TreeError> {
+        self.check_node(existing_node)?;
+        Ok(self.insert_before(existing_node, value))
+    }
+
+    /// Delete the specified node from the tree and rebalance the remaining nodes.
+    /// Returns the contents of the deleted node, or an error rather than panicking if the node is
+    /// not in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey of the node to delete from the tree
+    ///
+    pub fn try_delete_node(&mut self, node: NodeKey) -> Result<T, TreeError> {
+        self.check_node(node)?;
+        Ok(self.delete_node(node))
+    }
+}
+
+impl<T: Clone + fmt::Debug + Ord, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Inserts a new node in sorted position and rebalance the tree, treating the tree as a
+    /// classic binary search tree. Values equal to existing nodes are inserted after them.
+    /// Returns the NodeKey of the newly created node.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to populate the newly created node with
+    ///
+    pub fn insert_sorted(&mut self, value: T) -> NodeKey {
+        let position = self.find_insert_position(|contents| contents.cmp(&value));
+        self.insert_at_position(position, value)
     }
 }
 
@@ -638,31 +1885,39 @@ impl<T: Clone + fmt::Debug> Tree<T> {
 mod tests {
     use super::*;
 
-    impl<T: Clone + fmt::Debug> Tree<T> {
+    impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+        // Checks every structural invariant of the tree, panicking if any are broken
+        pub fn assert_valid(&self) {
+            if let Err(violations) = self.check_invariants() {
+                panic!("invalid tree: {:?}", violations);
+            }
+        }
+
         fn check_black_heights(&self, node: Option<NodeKey>) -> usize {
-            if node.is_none() {
-                1
-            } else {
-                let left_height = self.check_black_heights(self.get_left(node.unwrap()));
-                let right_height = self.check_black_heights(self.get_right(node.unwrap()));
-                if left_height != right_height {
-                    panic!(
-                        "Invalid black height for node at {:?}",
-                        self.get_contents(node.unwrap())
-                    )
-                }
-                if self.get_color(node) == Color::RED {
-                    left_height
-                } else {
-                    left_height + 1
+            match node {
+                None => 1,
+                Some(node) => {
+                    let left_height = self.check_black_heights(self.get_left(node));
+                    let right_height = self.check_black_heights(self.get_right(node));
+                    if left_height != right_height {
+                        panic!(
+                            "Invalid black height for node at {:?}",
+                            self.get_contents(node)
+                        )
+                    }
+                    if self.get_color(Some(node)) == Color::Red {
+                        left_height
+                    } else {
+                        left_height + 1
+                    }
                 }
             }
         }
 
         pub fn get_level_order(&self) -> String {
             let mut out = "".to_string();
-            if self.root.is_some() {
-                let mut queue = vec![self.root.unwrap()];
+            if let Some(root) = self.root {
+                let mut queue = vec![root];
                 let mut current_node: Option<&NodeKey>;
 
                 while !queue.is_empty() {
@@ -672,11 +1927,11 @@ mod tests {
 
                     let left = self.get_left(*current_node.unwrap());
                     let right = self.get_right(*current_node.unwrap());
-                    if left.is_some() {
-                        queue.push(left.unwrap());
+                    if let Some(left) = left {
+                        queue.push(left);
                     }
-                    if right.is_some() {
-                        queue.push(right.unwrap());
+                    if let Some(right) = right {
+                        queue.push(right);
                     }
 
                     queue.remove(0);
@@ -776,4 +2031,666 @@ mod tests {
 
         assert_eq!(tree.check_black_heights(tree.root), 3);
     }
+
+    #[test]
+    fn delete_root_with_one_child_test() {
+        let mut tree: Tree<usize> = Tree::new();
+
+        let one = tree.create_root(1);
+        let two = tree.insert_after(one, 2);
+        tree.delete_node(one);
+        assert_eq!(tree.root, Some(two));
+        assert_eq!(tree.get_prev(two), None);
+        assert_eq!(tree.get_leftmost_node(), Some(two));
+        assert_eq!(tree.get_nodes_order(), "2 ");
+
+        let three = tree.insert_after(two, 3);
+        tree.delete_node(two);
+        assert_eq!(tree.get_prev(three), None);
+        assert_eq!(tree.get_nodes_order(), "3 ");
+    }
+
+    #[test]
+    fn len_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        assert_eq!(tree.len(), 0);
+        assert!(tree.is_empty());
+
+        let two = tree.create_root(2);
+        let one = tree.insert_before(two, 1);
+        let three = tree.insert_after(two, 3);
+        assert_eq!(tree.len(), 3);
+        assert!(!tree.is_empty());
+
+        assert_eq!(tree.delete_node(two), 2);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.delete_node(one), 1);
+        assert_eq!(tree.delete_node(three), 3);
+        assert_eq!(tree.len(), 0);
+        assert!(tree.is_empty());
+        assert!(!tree.has_root());
+    }
+
+    #[test]
+    fn node_packing_test() {
+        // The color takes no space of its own
+        let links = if cfg!(feature = "threading") { 5 } else { 3 };
+        assert_eq!(
+            std::mem::size_of::<Node>(),
+            links * std::mem::size_of::<Option<NodeKey>>() + std::mem::size_of::<usize>()
+        );
+        let mut node = Node::new();
+        assert_eq!((node.color(), node.size()), (Color::Red, 1));
+        node.set_color(Color::Black);
+        node.set_size(1000);
+        assert_eq!((node.color(), node.size()), (Color::Black, 1000));
+        node.set_color(Color::Red);
+        assert_eq!((node.color(), node.size()), (Color::Red, 1000));
+    }
+
+    #[test]
+    fn clear_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        let mut node = tree.create_root(0);
+        for i in 1..100 {
+            node = tree.insert_after(node, i);
+        }
+        let capacity = tree.nodes.capacity();
+
+        tree.clear();
+        assert!(tree.is_empty());
+        assert!(!tree.has_root());
+        assert_eq!(tree.get_leftmost_node(), None);
+        assert_eq!(tree.nodes.capacity(), capacity);
+
+        let two = tree.create_root(2);
+        tree.insert_before(two, 1);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.get_nodes_order(), "1 2 ");
+    }
+
+    #[test]
+    fn capacity_test() {
+        let mut tree: Tree<usize> = Tree::with_capacity(50);
+        assert!(tree.nodes.capacity() >= 50);
+        assert!(tree.is_empty());
+
+        let mut node = tree.create_root(0);
+        for i in 1..50 {
+            node = tree.insert_after(node, i);
+        }
+        tree.reserve(100);
+        assert!(tree.nodes.capacity() >= 150);
+        assert_eq!(tree.len(), 50);
+    }
+
+    #[test]
+    fn shrink_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        let mut nodes = vec![tree.create_root(0)];
+        for i in 1..200 {
+            nodes.push(tree.insert_after(nodes[i - 1], i));
+        }
+        for (i, &node) in nodes.iter().enumerate() {
+            if i % 10 != 0 {
+                tree.delete_node(node);
+            }
+        }
+        let level_order = tree.get_level_order();
+        let black_height = tree.check_black_heights(tree.root);
+        assert!(tree.capacity() >= 200);
+
+        let key_map = tree.shrink_to_fit();
+        assert!(tree.capacity() < 200);
+        assert_eq!(tree.len(), 20);
+        assert_eq!(tree.get_level_order(), level_order);
+        assert_eq!(tree.check_black_heights(tree.root), black_height);
+        for (i, &node) in nodes.iter().enumerate().step_by(10) {
+            assert_eq!(*tree.get_contents(key_map[node]), i);
+        }
+
+        let node = tree.insert_after(key_map[nodes[0]], 1);
+        assert_eq!(tree.get_prev(node), Some(key_map[nodes[0]]));
+        assert_eq!(tree.len(), 21);
+    }
+
+    #[test]
+    fn compact_test() {
+        let mut tree: Tree<usize> = (0..500).collect();
+        let mut node = tree.get_leftmost_node();
+        while let Some(current) = node {
+            node = tree.get_next(current);
+            if !tree.get_contents(current).is_multiple_of(7) {
+                tree.delete_node(current);
+            }
+        }
+        let keys = |tree: &Tree<usize>| -> Vec<NodeKey> {
+            std::iter::successors(tree.get_leftmost_node(), |&node| tree.get_next(node)).collect()
+        };
+        let old_keys = keys(&tree);
+        let capacity = tree.capacity();
+
+        let key_map = tree.compact();
+        tree.assert_valid();
+        assert_eq!(tree.capacity(), capacity);
+        // The nodes now sit in consecutive slots in order
+        let slots: Vec<u64> = keys(&tree)
+            .into_iter()
+            .map(|node| slotmap::KeyData::from(node).as_ffi() & 0xffff_ffff)
+            .collect();
+        assert!(slots.windows(2).all(|pair| pair[1] == pair[0] + 1));
+        for (i, &old_key) in old_keys.iter().enumerate() {
+            assert_eq!(*tree.get_contents(key_map[old_key]), i * 7);
+        }
+    }
+
+    #[test]
+    fn try_accessors_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        let two = tree.create_root(2);
+        let one = tree.insert_before(two, 1);
+        let three = tree.insert_after(two, 3);
+
+        assert!(tree.contains_node(one));
+        assert_eq!(tree.try_get_left(two), Some(Some(one)));
+        assert_eq!(tree.try_get_right(two), Some(Some(three)));
+        assert_eq!(tree.try_get_parent(one), Some(Some(two)));
+        assert_eq!(tree.try_get_prev(two), Some(Some(one)));
+        assert_eq!(tree.try_get_next(three), Some(None));
+        assert_eq!(tree.try_get_contents(three), Some(&3));
+        assert_eq!(tree.try_set_contents(three, 4), Ok(()));
+        *tree.try_get_mut_contents(three).unwrap() += 1;
+        assert_eq!(tree.get_contents(three), &5);
+
+        tree.delete_node(one);
+        assert!(!tree.contains_node(one));
+        assert_eq!(tree.try_get_left(one), None);
+        assert_eq!(tree.try_get_right(one), None);
+        assert_eq!(tree.try_get_parent(one), None);
+        assert_eq!(tree.try_get_prev(one), None);
+        assert_eq!(tree.try_get_next(one), None);
+        assert_eq!(tree.try_get_contents(one), None);
+        assert_eq!(tree.try_get_mut_contents(one), None);
+        assert_eq!(tree.try_set_contents(one, 1), Err(1));
+    }
+
+    #[test]
+    fn fallible_mutation_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        let two = tree.try_create_root(2).unwrap();
+        assert_eq!(tree.try_create_root(0), Err(TreeError::RootAlreadyExists));
+        let one = tree.try_insert_before(two, 1).unwrap();
+        let three = tree.try_insert_after(two, 3).unwrap();
+        assert_eq!(tree.get_nodes_order(), "1 2 3 ");
+
+        assert_eq!(tree.try_delete_node(one), Ok(1));
+        assert_eq!(tree.try_delete_node(one), Err(TreeError::StaleKey(one)));
+        assert_eq!(tree.try_insert_after(one, 4), Err(TreeError::StaleKey(one)));
+        assert_eq!(
+            tree.try_insert_before(one, 4),
+            Err(TreeError::StaleKey(one))
+        );
+        assert_eq!(tree.get_nodes_order(), "2 3 ");
+        assert_eq!(tree.try_delete_node(three), Ok(3));
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn pop_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        assert_eq!(tree.pop_first(), None);
+        assert_eq!(tree.pop_last(), None);
+
+        let mut node = tree.create_root(0);
+        for i in 1..10 {
+            node = tree.insert_after(node, i);
+        }
+        assert_eq!(tree.pop_first(), Some(0));
+        assert_eq!(tree.pop_last(), Some(9));
+        assert_eq!(tree.pop_first(), Some(1));
+        assert_eq!(tree.get_nodes_order(), "2 3 4 5 6 7 8 ");
+        tree.check_black_heights(tree.root);
+
+        let mut popped = vec![];
+        while let Some(value) = tree.pop_last() {
+            popped.push(value);
+        }
+        assert_eq!(popped, vec![8, 7, 6, 5, 4, 3, 2]);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn to_vec_test() {
+        let mut tree: Tree<String> = Tree::new();
+        assert!(tree.to_vec().is_empty());
+        for word in ["pear", "apple", "fig", "banana"].iter() {
+            tree.insert_sorted(word.to_string());
+        }
+        assert_eq!(tree.to_vec(), vec!["apple", "banana", "fig", "pear"]);
+        assert_eq!(tree.len(), 4);
+        assert_eq!(
+            tree.into_sorted_vec(),
+            vec!["apple", "banana", "fig", "pear"]
+        );
+    }
+
+    #[test]
+    fn split_node_test() {
+        let mut tree: Tree<char> = Tree::new();
+        let arc = tree.create_root('a');
+        let (left, middle, right) = tree.split_node(arc, 'a', 'b', 'a');
+        assert_eq!(middle, arc);
+        assert_eq!(tree.to_vec(), vec!['a', 'b', 'a']);
+        let (_, _, last) = tree.split_node(right, 'a', 'c', 'a');
+        tree.split_node(left, 'a', 'd', 'a');
+        assert_eq!(tree.to_vec().iter().collect::<String>(), "adabaca");
+        assert_eq!(tree.get_rightmost_node(), Some(last));
+        tree.assert_valid();
+
+        // Every balancing scheme, at every position of trees of a range of sizes
+        fn split_every_node<B: Balance>() {
+            for size in 1..20 {
+                for at in 0..size {
+                    let mut tree: Tree<usize, (), SlotMap<NodeKey, Node>, B> = (0..size).collect();
+                    let node = tree.nth(at).unwrap();
+                    let (left, middle, right) = tree.split_node(node, 100, 200, 300);
+                    tree.assert_valid();
+                    assert_eq!(middle, node);
+                    assert_eq!(tree.len(), size + 2);
+                    let mut expected: Vec<usize> = (0..at).collect();
+                    expected.extend([100, 200, 300]);
+                    expected.extend(at + 1..size);
+                    assert_eq!(tree.to_vec(), expected);
+                    assert_eq!(tree.rank_of(left), at);
+                    assert_eq!(tree.rank_of(right), at + 2);
+                }
+            }
+        }
+        split_every_node::<RedBlack>();
+        split_every_node::<Avl>();
+        split_every_node::<Llrb>();
+        split_every_node::<WeightBalanced>();
+        split_every_node::<Splay>();
+        split_every_node::<Treap>();
+    }
+
+    #[test]
+    fn neighbors_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        let two = tree.create_root(2);
+        assert_eq!(tree.neighbors(two), (None, None));
+        let one = tree.insert_before(two, 1);
+        let three = tree.insert_after(two, 3);
+        assert_eq!(tree.neighbors(two), (Some(one), Some(three)));
+        assert_eq!(tree.neighbors(one), (None, Some(two)));
+        assert_eq!(tree.neighbor_contents(two), (Some(&1), Some(&3)));
+        assert_eq!(tree.neighbor_contents(three), (Some(&2), None));
+    }
+
+    #[test]
+    fn clone_test() {
+        let mut tree: Tree<usize> = (0..20).collect();
+        let removed = tree.nth(3).unwrap();
+        tree.delete_node(removed);
+        let node = tree.nth(10).unwrap();
+
+        let mut copy = tree.clone();
+        copy.assert_valid();
+        assert_eq!(copy.get_contents(node), &11);
+        copy.set_contents(node, 100);
+        assert_eq!(tree.get_contents(node), &11);
+        assert_eq!(copy.to_vec().len(), 19);
+
+        let (compact, key_map) = tree.clone_with_key_map();
+        compact.assert_valid();
+        assert_eq!(compact.to_vec(), tree.to_vec());
+        assert_eq!(compact.get_contents(key_map[node]), &11);
+        assert!(!key_map.contains_key(removed));
+    }
+
+    #[test]
+    fn equality_test() {
+        let sorted: Tree<usize> = (0..10).collect();
+        let mut shuffled: Tree<usize> = Tree::new();
+        for i in [5, 2, 8, 0, 9, 1, 7, 3, 6, 4].iter() {
+            shuffled.insert_sorted(*i);
+        }
+        assert!(sorted == shuffled);
+        shuffled.pop_last();
+        assert!(sorted != shuffled);
+        shuffled.insert_sorted(10);
+        assert!(sorted != shuffled);
+        assert!(Tree::<usize>::new() == Tree::new());
+    }
+
+    #[test]
+    fn replace_test() {
+        let mut tree: Tree<String> = Tree::new();
+        let node = tree.create_root("old".to_string());
+        assert_eq!(tree.replace(node, "new".to_string()), "old");
+        assert_eq!(tree.get_contents(node), "new");
+    }
+
+    #[test]
+    fn swap_contents_test() {
+        let mut tree: Tree<String> = Tree::new();
+        let first = tree.create_root("first".to_string());
+        let second = tree.insert_after(first, "second".to_string());
+        tree.swap_contents(first, second);
+        assert_eq!(tree.get_contents(first), "second");
+        assert_eq!(tree.get_contents(second), "first");
+        tree.swap_contents(first, first);
+        assert_eq!(tree.get_contents(first), "second");
+        assert_eq!(tree.get_leftmost_node(), Some(first));
+    }
+
+    #[test]
+    fn end_nodes_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        assert_eq!(tree.get_leftmost_node(), None);
+        assert_eq!(tree.get_rightmost_node(), None);
+
+        let two = tree.create_root(2);
+        assert_eq!(tree.get_leftmost_node(), Some(two));
+        assert_eq!(tree.get_rightmost_node(), Some(two));
+
+        let one = tree.insert_before(two, 1);
+        let four = tree.insert_after(two, 4);
+        let three = tree.insert_before(four, 3);
+        assert_eq!(tree.get_leftmost_node(), Some(one));
+        assert_eq!(tree.get_rightmost_node(), Some(four));
+
+        tree.delete_node(one);
+        tree.delete_node(four);
+        assert_eq!(tree.get_leftmost_node(), Some(two));
+        assert_eq!(tree.get_rightmost_node(), Some(three));
+
+        tree.delete_node(two);
+        assert_eq!(tree.get_leftmost_node(), Some(three));
+        assert_eq!(tree.get_rightmost_node(), Some(three));
+        tree.delete_node(three);
+        assert_eq!(tree.get_leftmost_node(), None);
+        assert_eq!(tree.get_rightmost_node(), None);
+    }
+
+    #[test]
+    fn insert_sorted_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        for &value in [5, 2, 8, 2, 9, 1, 5, 7].iter() {
+            tree.insert_sorted(value);
+            tree.check_black_heights(tree.root);
+        }
+        assert_eq!(tree.len(), 8);
+        assert_eq!(tree.get_nodes_order(), "1 2 2 5 5 7 8 9 ");
+
+        let mut tree: Tree<(usize, char)> = Tree::new();
+        tree.insert_sorted((1, 'b'));
+        let first = tree.insert_sorted((1, 'a'));
+        tree.insert_sorted((0, 'c'));
+        assert_eq!(
+            tree.get_prev(first),
+            Some(tree.get_leftmost_node().unwrap())
+        );
+        assert_eq!(tree.get_nodes_order(), "(0, 'c') (1, 'a') (1, 'b') ");
+    }
+
+    #[test]
+    fn insert_by_test() {
+        let mut tree: Tree<f64> = Tree::new();
+        for &x in [0.5, -1.0, 3.25, 0.0, 2.0].iter() {
+            tree.insert_by(x, |&contents| contents.partial_cmp(&x).unwrap());
+        }
+        assert_eq!(tree.get_nodes_order(), "-1.0 0.0 0.5 2.0 3.25 ");
+
+        // Order by the distance from a sweep coordinate rather than the payload itself
+        let sweep: f64 = 1.0;
+        let mut tree: Tree<f64> = Tree::new();
+        for &x in [0.5, -1.0, 3.25, 0.0, 2.0].iter() {
+            let distance = (x - sweep).abs();
+            tree.insert_by(x, |&contents| {
+                (contents - sweep).abs().partial_cmp(&distance).unwrap()
+            });
+        }
+        assert_eq!(tree.get_nodes_order(), "0.5 0.0 2.0 -1.0 3.25 ");
+        tree.check_black_heights(tree.root);
+    }
+
+    #[test]
+    fn order_statistics_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        assert_eq!(tree.nth(0), None);
+
+        let mut nodes = vec![tree.create_root(0)];
+        for i in 1..64 {
+            let node = if i % 2 == 0 {
+                tree.insert_after(nodes[i - 1], i)
+            } else {
+                tree.insert_before(nodes[0], i)
+            };
+            nodes.push(node);
+        }
+        let mut node = tree.get_leftmost_node();
+        let mut index = 0;
+        while let Some(current) = node {
+            assert_eq!(tree.nth(index), Some(current));
+            assert_eq!(tree.rank_of(current), index);
+            node = tree.get_next(current);
+            index += 1;
+        }
+        assert_eq!(tree.nth(64), None);
+
+        for &node in nodes.iter().step_by(3) {
+            tree.delete_node(node);
+        }
+        assert_eq!(tree.get_size(tree.root), tree.len());
+        for index in 0..tree.len() {
+            assert_eq!(tree.rank_of(tree.nth(index).unwrap()), index);
+        }
+        assert_eq!(tree.nth(tree.len()), None);
+    }
+
+    #[test]
+    fn height_test() {
+        let mut tree: Tree<u32> = Tree::new();
+        assert_eq!((tree.height(), tree.black_height()), (0, 0));
+
+        let root = tree.create_root(0);
+        assert_eq!((tree.height(), tree.black_height()), (1, 1));
+        assert_eq!(tree.depth(root), 0);
+
+        let mut last = root;
+        for i in 1..100 {
+            last = tree.insert_after(last, i);
+        }
+        assert_eq!(tree.black_height() + 1, tree.check_black_heights(tree.root));
+        assert!(tree.height() <= 2 * 7);
+        let deepest = (0..100)
+            .map(|i| tree.depth(tree.nth(i).unwrap()))
+            .max()
+            .unwrap();
+        assert_eq!(deepest + 1, tree.height());
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn trace_events_test() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        // Records the message of every event
+        struct Messages(Arc<Mutex<Vec<String>>>);
+
+        impl Visit for Messages {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.0.lock().unwrap().push(format!("{:?}", value));
+                }
+            }
+        }
+
+        impl tracing::Subscriber for Messages {
+            fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+                metadata.target() == "binary_search_tree"
+            }
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                event.record(&mut Messages(self.0.clone()));
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let messages = Arc::new(Mutex::new(vec![]));
+        tracing::subscriber::with_default(Messages(messages.clone()), || {
+            let mut tree: Tree<u32> = Tree::new();
+            let root = tree.create_root(0);
+            let second = tree.insert_after(root, 1);
+            tree.insert_after(second, 2);
+            tree.delete_node(root);
+        });
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.last().unwrap(), "delete");
+        for message in ["create root", "insert", "left rotate", "recolor"].iter() {
+            assert!(messages.iter().any(|logged| logged == message));
+        }
+    }
+
+    #[test]
+    fn insert_at_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        let mut model = vec![];
+        for i in 0..50 {
+            let index = (i * 7) % (model.len() + 1);
+            let node = tree.insert_at(index, i);
+            model.insert(index, i);
+            assert_eq!(tree.rank_of(node), index);
+        }
+        let mut node = tree.get_leftmost_node();
+        for value in model {
+            assert_eq!(*tree.get_contents(node.unwrap()), value);
+            node = tree.get_next(node.unwrap());
+        }
+        tree.check_black_heights(tree.root);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_at_out_of_bounds_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        tree.insert_at(0, 0);
+        tree.insert_at(2, 2);
+    }
+
+    #[test]
+    fn remove_at_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        let mut model: Vec<usize> = (0..50).collect();
+        for &value in model.iter() {
+            tree.insert_at(value, value);
+        }
+        assert_eq!(tree.remove_at(50), None);
+        while !model.is_empty() {
+            let index = (model.len() * 7 / 3) % model.len();
+            assert_eq!(tree.remove_at(index), Some(model.remove(index)));
+            assert_eq!(tree.len(), model.len());
+            tree.check_black_heights(tree.root);
+        }
+        assert_eq!(tree.remove_at(0), None);
+    }
+
+    #[test]
+    fn ancestors_test() {
+        let tree: Tree<usize> = (0..100).collect();
+        let root = tree.root.unwrap();
+        assert_eq!(tree.ancestors(root).count(), 0);
+        let mut node = tree.get_leftmost_node();
+        while let Some(current) = node {
+            let ancestors: Vec<_> = tree.ancestors(current).collect();
+            assert_eq!(ancestors.len(), tree.depth(current));
+            assert_eq!(ancestors.first().copied(), tree.get_parent(current));
+            if current != root {
+                assert_eq!(ancestors.last(), Some(&root));
+                assert!(tree.is_ancestor(root, current));
+                assert!(!tree.is_ancestor(current, root));
+            }
+            assert!(!tree.is_ancestor(current, current));
+            node = tree.get_next(current);
+        }
+    }
+
+    #[test]
+    fn iter_subtree_test() {
+        let tree: Tree<usize> = (0..100).collect();
+        let root = tree.root.unwrap();
+        assert_eq!(
+            tree.iter_subtree(root)
+                .map(|node| *tree.get_contents(node))
+                .collect::<Vec<_>>(),
+            tree.to_vec()
+        );
+        let mut node = tree.get_leftmost_node();
+        while let Some(current) = node {
+            let nodes: Vec<_> = tree.iter_subtree(current).collect();
+            assert_eq!(nodes.len(), tree.get_size(Some(current)));
+            assert!(nodes.contains(&current));
+            for pair in nodes.windows(2) {
+                assert_eq!(tree.get_next(pair[0]), Some(pair[1]));
+            }
+            assert!(nodes
+                .iter()
+                .all(|&inner| inner == current || tree.is_ancestor(current, inner)));
+            node = tree.get_next(current);
+        }
+    }
+
+    #[test]
+    fn index_test() {
+        let mut tree: Tree<usize> = (0..10).collect();
+        let node = tree.nth(4).unwrap();
+        assert_eq!(tree[node], 4);
+        tree[node] = 40;
+        assert_eq!(tree[node], 40);
+        assert_eq!(tree.get_contents(node), &40);
+    }
+
+    #[test]
+    fn iter_with_keys_test() {
+        let tree: Tree<usize> = (0..50).collect();
+        let pairs: Vec<(NodeKey, &usize)> = tree.iter_with_keys().collect();
+        assert_eq!(pairs.len(), 50);
+        for (i, (node, value)) in pairs.into_iter().enumerate() {
+            assert_eq!(*value, i);
+            assert_eq!(tree.rank_of(node), i);
+        }
+        assert_eq!(Tree::<usize>::new().iter_with_keys().count(), 0);
+    }
+
+    #[test]
+    fn insert_with_hint_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        let mut hint = tree.create_root(0);
+        // Mostly ascending values, with every seventh one out of place
+        for i in 1..200 {
+            let value = if i % 7 == 0 { i / 2 } else { i };
+            hint = tree.insert_with_hint(hint, value, |existing| existing.cmp(&value));
+        }
+        let mut expected: Vec<usize> = (0..200)
+            .map(|i| if i % 7 == 0 { i / 2 } else { i })
+            .collect();
+        expected.sort();
+        assert_eq!(tree.to_vec(), expected);
+        tree.assert_valid();
+
+        // Equal values go after the existing ones, whichever side of them the hint is
+        let first = tree.get_leftmost_node().unwrap();
+        let node = tree.insert_with_hint(first, 7, |existing| existing.cmp(&7));
+        assert_eq!(tree.get_contents(tree.get_next(node).unwrap()), &8);
+        assert_eq!(tree.get_contents(tree.get_prev(node).unwrap()), &7);
+    }
 }
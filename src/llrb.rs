@@ -0,0 +1,399 @@
+use crate::balance::{Rebalance, Subtree};
+use crate::{Augment, Balance, Color, InvariantViolation, Node, NodeKey, NodeStore, Tree};
+use slotmap::SlotMap;
+use std::fmt;
+
+/// Left-leaning red-black balancing. A red-black tree in which every red node is a left child and
+/// no node has two red children, so each node and its red child stand for a node of a 2-3 tree.
+/// Ruling out right leaning red nodes leaves far fewer cases to fix up after an insert or delete,
+/// at the cost of more rotations than the default `RedBlack` balancing.
+#[derive(PartialEq, Eq, Copy, Clone, Default, Debug)]
+pub struct Llrb;
+
+/// A tree kept balanced as a left-leaning red-black tree, see `Llrb`
+pub type LlrbTree<T, A = ()> = Tree<T, A, SlotMap<NodeKey, Node>, Llrb>;
+
+impl Rebalance for Llrb {
+    fn is_red(node: &Node) -> bool {
+        node.color() == Color::Red
+    }
+
+    fn inserted<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        tree.llrb_insert_rebalance(node);
+    }
+
+    fn remove<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        tree.llrb_remove(node);
+    }
+
+    fn rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: Option<NodeKey>,
+    ) -> usize {
+        tree.subtree_black_height(node)
+    }
+
+    fn child_rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        parent: NodeKey,
+        parent_rank: usize,
+        _child: Option<NodeKey>,
+    ) -> usize {
+        parent_rank - tree.is_black(parent)
+    }
+
+    fn parent_rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        parent: NodeKey,
+        child_rank: usize,
+    ) -> usize {
+        child_rank + tree.is_black(parent)
+    }
+
+    fn join<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        left: Subtree,
+        pivot: NodeKey,
+        right: Subtree,
+    ) -> (NodeKey, usize) {
+        tree.red_black_join(left, pivot, right, Tree::llrb_join_fixup)
+    }
+
+    fn make_root<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        root: NodeKey,
+    ) {
+        tree.set_color(root, Color::Black);
+    }
+
+    fn link<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        keys: &[NodeKey],
+    ) -> Option<NodeKey> {
+        // The greatest black height the nodes can be spread over, every 2-3 tree of black height
+        // h holds between 2^h - 1 and 3^h - 1 nodes
+        let mut black_height = 0;
+        while 2 << black_height <= keys.len() + 1 {
+            black_height += 1;
+        }
+        tree.llrb_link_range(keys, None, black_height)
+    }
+
+    fn check<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+        violations: &mut Vec<InvariantViolation>,
+    ) -> usize {
+        if let Some(right) = tree.get_right(node) {
+            if tree.contains_node(right) && tree.is_black(right) == 0 {
+                violations.push(InvariantViolation::RightLeaningRed(right));
+            }
+        }
+        tree.red_black_check(node, left, right, violations)
+    }
+
+    fn check_root<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        root: NodeKey,
+        violations: &mut Vec<InvariantViolation>,
+    ) {
+        if tree.is_black(root) == 0 {
+            violations.push(InvariantViolation::RedRoot(root));
+        }
+    }
+
+    fn restore<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+    ) -> Result<usize, &'static str> {
+        if tree.get_color(tree.get_right(node)) == Color::Red {
+            return Err("a red node is a right child");
+        }
+        tree.red_black_restore(node, left, right)
+    }
+
+    fn restore_root<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        root: NodeKey,
+    ) -> Result<(), &'static str> {
+        if tree.is_black(root) == 0 {
+            return Err("the root is red");
+        }
+        Ok(())
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    fn is_red(&self, node: Option<NodeKey>) -> bool {
+        self.get_color(node) == Color::Red
+    }
+
+    // Rotates a right leaning red node over to the left, returning the new root of the subtree
+    fn llrb_rotate_left(&mut self, node: NodeKey) -> NodeKey {
+        let pivot = self.get_right(node).unwrap();
+        self.left_rotate(node);
+        self.set_color(pivot, self.get_color(Some(node)));
+        self.set_color(node, Color::Red);
+        pivot
+    }
+
+    // Rotates a left leaning red node over to the right, returning the new root of the subtree
+    fn llrb_rotate_right(&mut self, node: NodeKey) -> NodeKey {
+        let pivot = self.get_left(node).unwrap();
+        self.right_rotate(node);
+        self.set_color(pivot, self.get_color(Some(node)));
+        self.set_color(node, Color::Red);
+        pivot
+    }
+
+    // Flips the colors of a node and both of its children, splitting or merging 2-3 tree nodes
+    fn llrb_flip_colors(&mut self, node: NodeKey) {
+        for node in [Some(node), self.get_left(node), self.get_right(node)]
+            .iter()
+            .flatten()
+        {
+            let color = match self.get_color(Some(*node)) {
+                Color::Red => Color::Black,
+                Color::Black => Color::Red,
+            };
+            self.set_color(*node, color);
+        }
+    }
+
+    // Restores the left-leaning invariants at a node whose subtrees are valid, returning the new
+    // root of the subtree
+    fn llrb_balance(&mut self, mut node: NodeKey) -> NodeKey {
+        if self.is_red(self.get_right(node)) && !self.is_red(self.get_left(node)) {
+            node = self.llrb_rotate_left(node);
+        }
+        let left = self.get_left(node);
+        if self.is_red(left) && self.is_red(left.and_then(|left| self.get_left(left))) {
+            node = self.llrb_rotate_right(node);
+        }
+        if self.is_red(self.get_left(node)) && self.is_red(self.get_right(node)) {
+            self.llrb_flip_colors(node);
+        }
+        node
+    }
+
+    // Balances every node from the specified one up to the root
+    fn llrb_balance_path(&mut self, mut node: Option<NodeKey>) {
+        while let Some(current) = node {
+            self.count_fixup_iteration();
+            let root = self.llrb_balance(current);
+            node = self.get_parent(root);
+        }
+    }
+
+    // Rebalances the tree after a red leaf has been inserted
+    fn llrb_insert_rebalance(&mut self, node: NodeKey) {
+        self.llrb_balance_path(self.get_parent(node));
+        let root = self.root.unwrap();
+        self.set_color(root, Color::Black);
+    }
+
+    // Rebalances a red pivot joined into a subtree, which is the whole tree while this runs. The
+    // root is left red if the pivot is pushed all the way up, the black height is unchanged.
+    fn llrb_join_fixup(&mut self, pivot: NodeKey) -> bool {
+        self.llrb_balance_path(self.get_parent(pivot));
+        false
+    }
+
+    // Makes the left child of a node, or one of its children, red so the walk down to a node
+    // being deleted from the left subtree never reaches a 2-node
+    fn llrb_move_red_left(&mut self, mut node: NodeKey) -> NodeKey {
+        self.llrb_flip_colors(node);
+        let right = self.get_right(node).unwrap();
+        if self.is_red(self.get_left(right)) {
+            self.llrb_rotate_right(right);
+            node = self.llrb_rotate_left(node);
+            self.llrb_flip_colors(node);
+        }
+        node
+    }
+
+    // Makes the right child of a node, or one of its children, red, see llrb_move_red_left
+    fn llrb_move_red_right(&mut self, mut node: NodeKey) -> NodeKey {
+        self.llrb_flip_colors(node);
+        let left = self.get_left(node).unwrap();
+        if self.is_red(self.get_left(left)) {
+            node = self.llrb_rotate_right(node);
+            self.llrb_flip_colors(node);
+        }
+        node
+    }
+
+    // Returns true if the target is in the left subtree of the node
+    fn llrb_is_left_of(&self, node: NodeKey, target: NodeKey) -> bool {
+        let mut current = target;
+        while let Some(parent) = self.get_parent(current) {
+            if parent == node {
+                return self.get_left(node) == Some(current);
+            }
+            current = parent;
+        }
+        false
+    }
+
+    // Removes a node with at most one child. The tree is walked from the root down to the node,
+    // rotating a red link down along the path so the node is a red leaf, or in a 3-node, by the
+    // time it is reached. The nodes on the path are then balanced on the way back up.
+    //
+    // The tree is not ordered by a key, so the direction of each step is found by walking up from
+    // the node, taking O(log² n) time in all.
+    fn llrb_remove(&mut self, target: NodeKey) {
+        let root = self.root.unwrap();
+        if !self.is_red(self.get_left(root)) && !self.is_red(self.get_right(root)) {
+            self.set_color(root, Color::Red);
+        }
+
+        let mut node = root;
+        loop {
+            self.count_fixup_iteration();
+            if self.llrb_is_left_of(node, target) {
+                let left = self.get_left(node).unwrap();
+                if !self.is_red(Some(left)) && !self.is_red(self.get_left(left)) {
+                    node = self.llrb_move_red_left(node);
+                }
+                node = self.get_left(node).unwrap();
+            } else {
+                if self.is_red(self.get_left(node)) {
+                    node = self.llrb_rotate_right(node);
+                }
+                if node == target && self.get_right(node).is_none() {
+                    break;
+                }
+                let right = self.get_right(node).unwrap();
+                if !self.is_red(Some(right)) && !self.is_red(self.get_left(right)) {
+                    node = self.llrb_move_red_right(node);
+                }
+                node = self.get_right(node).unwrap();
+            }
+        }
+
+        debug_assert!(self.get_left(target).is_none());
+        let parent = self.unlink(target);
+        self.llrb_balance_path(parent);
+        if let Some(root) = self.root {
+            self.set_color(root, Color::Black);
+        }
+    }
+
+    // Links a range of nodes into a subtree with the specified black height, as a 2-3 tree that
+    // is as close to perfectly balanced as possible, returning its root
+    fn llrb_link_range(
+        &mut self,
+        keys: &[NodeKey],
+        parent: Option<NodeKey>,
+        black_height: usize,
+    ) -> Option<NodeKey> {
+        if keys.is_empty() {
+            return None;
+        }
+        // The most nodes a subtree a level down can hold
+        let child_capacity = 3usize.saturating_pow(black_height as u32 - 1) - 1;
+        let node = if keys.len() <= 2 * child_capacity + 1 {
+            // A 2-node, a black node with two children
+            let middle = keys.len() / 2;
+            let node = keys[middle];
+            let left = self.llrb_link_range(&keys[..middle], Some(node), black_height - 1);
+            let right = self.llrb_link_range(&keys[middle + 1..], Some(node), black_height - 1);
+            self.llrb_link_node(node, left, right, Color::Black);
+            node
+        } else {
+            // A 3-node, a black node with a red left child and three children between them
+            let third = (keys.len() - 2) / 3;
+            let remainder = (keys.len() - 2) % 3;
+            let red_index = third + (remainder > 0) as usize;
+            let black_index = red_index + 1 + third + (remainder > 1) as usize;
+            let (red, black) = (keys[red_index], keys[black_index]);
+            let first = self.llrb_link_range(&keys[..red_index], Some(red), black_height - 1);
+            let second = self.llrb_link_range(
+                &keys[red_index + 1..black_index],
+                Some(red),
+                black_height - 1,
+            );
+            let third =
+                self.llrb_link_range(&keys[black_index + 1..], Some(black), black_height - 1);
+            self.llrb_link_node(red, first, second, Color::Red);
+            self.set_parent(red, Some(black));
+            self.llrb_link_node(black, Some(red), third, Color::Black);
+            black
+        };
+        self.set_parent(node, parent);
+        Some(node)
+    }
+
+    // Links a node to its children, setting its color, size and augmented value
+    fn llrb_link_node(
+        &mut self,
+        node: NodeKey,
+        left: Option<NodeKey>,
+        right: Option<NodeKey>,
+        color: Color,
+    ) {
+        self.set_left(node, left);
+        self.set_right(node, right);
+        self.set_size(node, self.get_size(left) + self.get_size(right) + 1);
+        self.set_color(node, color);
+        self.update_augmented_node(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn llrb_insert_delete_test() {
+        let mut tree: LlrbTree<usize> = Tree::default();
+        let mut nodes = vec![tree.create_root(0)];
+        for i in 1..300 {
+            // Mix the insertion order up to get a variety of shapes
+            let node = if i % 3 == 0 {
+                tree.insert_before(nodes[i / 2], i)
+            } else {
+                tree.insert_after(nodes[i - 1], i)
+            };
+            nodes.push(node);
+            tree.assert_valid();
+        }
+        let mut expected = tree.to_vec();
+        for (i, node) in nodes.into_iter().enumerate() {
+            if i % 4 != 1 {
+                assert_eq!(tree.delete_node(node), i);
+                expected.retain(|&x| x != i);
+                tree.assert_valid();
+                assert_eq!(tree.to_vec(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn llrb_build_split_append_test() {
+        for size in 0..60 {
+            let tree: LlrbTree<usize> = (0..size).collect();
+            tree.assert_valid();
+            for at in 0..size {
+                let mut tree = tree.clone();
+                let (mut other, _) = tree.split_off_at(at);
+                tree.assert_valid();
+                other.assert_valid();
+                assert_eq!(other.to_vec(), (at..size).collect::<Vec<_>>());
+                tree.append(&mut other);
+                tree.assert_valid();
+                assert_eq!(tree.to_vec(), (0..size).collect::<Vec<_>>());
+            }
+        }
+    }
+}
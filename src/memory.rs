@@ -0,0 +1,63 @@
+use crate::{Augment, Balance, Node, NodeStore, Tree};
+use std::fmt;
+use std::mem;
+
+/// A breakdown of the memory used by a tree, returned by `Tree::memory_usage`
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct MemoryUsage {
+    /// The number of bytes allocated for the node links, contents and augmented values. Memory
+    /// owned by the contents themselves, such as the buffer of a `String`, is not included.
+    pub allocated_bytes: usize,
+    /// The number of nodes in the tree
+    pub live_nodes: usize,
+    /// The number of nodes that can be added before the arena has to grow
+    pub free_slots: usize,
+    /// The number of bytes each node takes up on top of its contents
+    pub per_node_overhead: usize,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Returns an estimate of the memory used by the tree, worked out from the capacities of its
+    /// arrays and the sizes of their elements.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        // Every slot of the arena and the secondary maps also holds a 32 bit version
+        let version = mem::size_of::<u32>();
+        let node_bytes = mem::size_of::<Node>() + version;
+        let contents_bytes = mem::size_of::<T>() + version;
+        let augmented_bytes = mem::size_of::<A::Value>() + version;
+        let capacity = self.nodes.capacity();
+        MemoryUsage {
+            allocated_bytes: capacity * node_bytes
+                + self.node_data.capacity() * contents_bytes
+                + self.augmented.capacity() * augmented_bytes,
+            live_nodes: self.len,
+            free_slots: capacity.saturating_sub(self.len),
+            // Only the version of the slot holding the contents is overhead
+            per_node_overhead: node_bytes + augmented_bytes + version,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_usage_test() {
+        let mut tree: Tree<u64> = Tree::with_capacity(100);
+        let empty = tree.memory_usage();
+        assert_eq!(empty.live_nodes, 0);
+        assert!(empty.free_slots >= 100);
+        assert!(empty.allocated_bytes >= 100 * (empty.per_node_overhead + 8));
+
+        tree.extend(0..60);
+        let usage = tree.memory_usage();
+        assert_eq!(usage.live_nodes, 60);
+        assert_eq!(usage.free_slots, tree.capacity() - 60);
+        assert_eq!(usage.per_node_overhead, empty.per_node_overhead);
+        assert!(usage.per_node_overhead > mem::size_of::<Node>());
+
+        tree.shrink_to_fit();
+        assert!(tree.memory_usage().allocated_bytes < usage.allocated_bytes);
+    }
+}
@@ -0,0 +1,629 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum Color {
+    Red,
+    Black,
+}
+
+type Link<T> = Option<Arc<PersistentNode<T>>>;
+
+// A node of a persistent tree. Nodes are never modified once they are shared, a change to a
+// subtree copies the nodes on the path down to the change and shares the rest.
+struct PersistentNode<T> {
+    color: Color,
+    // The number of black nodes on every path from this node down to a missing child
+    black_height: usize,
+    size: usize,
+    left: Link<T>,
+    value: T,
+    right: Link<T>,
+}
+
+/// An immutable sequence backed by a red-black tree. Inserting or removing a value returns a new
+/// tree that shares every subtree the change didn't touch with the original, so both versions
+/// stay valid and each change only copies O(log n) nodes. Cloning a tree is O(1).
+///
+/// The values are indexed by position, as with the positional methods of `Tree`, and the tree is
+/// kept balanced by joining subtrees along the path of each change.
+pub struct PersistentTree<T: Clone> {
+    root: Link<T>,
+}
+
+impl<T: Clone> Clone for PersistentTree<T> {
+    fn clone(&self) -> Self {
+        PersistentTree {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Default for PersistentTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + fmt::Debug> fmt::Debug for PersistentTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for PersistentTree<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Clone + Eq> Eq for PersistentTree<T> {}
+
+impl<T: Clone> FromIterator<T> for PersistentTree<T> {
+    /// Creates a perfectly balanced tree holding the items in the order they are iterated
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut values: Vec<Option<T>> = iter.into_iter().map(Some).collect();
+        // As in `Tree::from_sorted_iter`, only the nodes on the deepest level are red
+        let mut red_depth = 0;
+        while 2 << red_depth <= values.len() {
+            red_depth += 1;
+        }
+        PersistentTree {
+            root: build_balanced(&mut values, 0, red_depth),
+        }
+    }
+}
+
+// Builds a balanced subtree from a run of values, taking them out of their slots
+fn build_balanced<T>(values: &mut [Option<T>], depth: usize, red_depth: usize) -> Link<T> {
+    if values.is_empty() {
+        return None;
+    }
+    let middle = values.len() / 2;
+    let (left, rest) = values.split_at_mut(middle);
+    let (value, right) = rest.split_first_mut().unwrap();
+    let left = build_balanced(left, depth + 1, red_depth);
+    let right = build_balanced(right, depth + 1, red_depth);
+    let color = if depth == red_depth && depth > 0 {
+        Color::Red
+    } else {
+        Color::Black
+    };
+    Some(node(color, left, value.take().unwrap(), right))
+}
+
+// Creates a node, working out its size and black height from its children
+fn node<T>(color: Color, left: Link<T>, value: T, right: Link<T>) -> Arc<PersistentNode<T>> {
+    Arc::new(PersistentNode {
+        color,
+        black_height: black_height(&left) + (color == Color::Black) as usize,
+        size: size(&left) + size(&right) + 1,
+        left,
+        value,
+        right,
+    })
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+fn black_height<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.black_height)
+}
+
+fn color<T>(link: &Link<T>) -> Color {
+    link.as_ref().map_or(Color::Black, |node| node.color)
+}
+
+// Returns a copy of a node with a different color
+fn recolor<T: Clone>(node: &Arc<PersistentNode<T>>, color: Color) -> Arc<PersistentNode<T>> {
+    self::node(
+        color,
+        node.left.clone(),
+        node.value.clone(),
+        node.right.clone(),
+    )
+}
+
+// Joins two subtrees and a value that sits between them into a single valid subtree, whose root
+// may be red. This takes time proportional to the difference in the black heights of the
+// subtrees.
+fn join<T: Clone>(left: Link<T>, value: T, right: Link<T>) -> Arc<PersistentNode<T>> {
+    // Red roots are made black so both subtrees are valid red-black trees in their own right
+    let left = left.and_then(blacken);
+    let right = right.and_then(blacken);
+    let left_height = black_height(&left);
+    let right_height = black_height(&right);
+    if left_height > right_height {
+        let joined = join_right(left.unwrap(), value, right, right_height);
+        if joined.color == Color::Red && color(&joined.right) == Color::Red {
+            return recolor(&joined, Color::Black);
+        }
+        joined
+    } else if right_height > left_height {
+        let joined = join_left(left, value, right.unwrap(), left_height);
+        if joined.color == Color::Red && color(&joined.left) == Color::Red {
+            return recolor(&joined, Color::Black);
+        }
+        joined
+    } else {
+        node(Color::Red, left, value, right)
+    }
+}
+
+// Joins a shorter subtree onto the right spine of a taller one, at the first black node with the
+// same black height. A red node left with a red right child is fixed by a left rotation.
+fn join_right<T: Clone>(
+    tall: Arc<PersistentNode<T>>,
+    value: T,
+    short: Link<T>,
+    short_height: usize,
+) -> Arc<PersistentNode<T>> {
+    if tall.color == Color::Black && tall.black_height == short_height {
+        return node(Color::Red, Some(tall), value, short);
+    }
+    let right = match tall.right.clone() {
+        Some(right) => join_right(right, value, short, short_height),
+        None => node(Color::Red, None, value, short),
+    };
+    if tall.color == Color::Black && right.color == Color::Red && color(&right.right) == Color::Red
+    {
+        // Rotate left, blackening the outer grandchild
+        let outer = recolor(right.right.as_ref().unwrap(), Color::Black);
+        let left = node(
+            Color::Black,
+            tall.left.clone(),
+            tall.value.clone(),
+            right.left.clone(),
+        );
+        return node(Color::Red, Some(left), right.value.clone(), Some(outer));
+    }
+    node(
+        tall.color,
+        tall.left.clone(),
+        tall.value.clone(),
+        Some(right),
+    )
+}
+
+// The mirror image of join_right, joining a shorter subtree onto the left spine of a taller one
+fn join_left<T: Clone>(
+    short: Link<T>,
+    value: T,
+    tall: Arc<PersistentNode<T>>,
+    short_height: usize,
+) -> Arc<PersistentNode<T>> {
+    if tall.color == Color::Black && tall.black_height == short_height {
+        return node(Color::Red, short, value, Some(tall));
+    }
+    let left = match tall.left.clone() {
+        Some(left) => join_left(short, value, left, short_height),
+        None => node(Color::Red, short, value, None),
+    };
+    if tall.color == Color::Black && left.color == Color::Red && color(&left.left) == Color::Red {
+        // Rotate right, blackening the outer grandchild
+        let outer = recolor(left.left.as_ref().unwrap(), Color::Black);
+        let right = node(
+            Color::Black,
+            left.right.clone(),
+            tall.value.clone(),
+            tall.right.clone(),
+        );
+        return node(Color::Red, Some(outer), left.value.clone(), Some(right));
+    }
+    node(
+        tall.color,
+        Some(left),
+        tall.value.clone(),
+        tall.right.clone(),
+    )
+}
+
+// Joins two subtrees where every value of the left comes before every value of the right
+fn join_two<T: Clone>(left: Link<T>, right: Link<T>) -> Link<T> {
+    match right {
+        None => left,
+        Some(right) => {
+            let (rest, first) = remove(&right, 0);
+            Some(join(left, first, rest))
+        }
+    }
+}
+
+// Splits a subtree into the first `index` values and the rest
+fn split<T: Clone>(link: &Link<T>, index: usize) -> (Link<T>, Link<T>) {
+    let node = match link {
+        Some(node) => node,
+        None => return (None, None),
+    };
+    let left_size = size(&node.left);
+    if index <= left_size {
+        let (left, middle) = split(&node.left, index);
+        let right = join(middle, node.value.clone(), node.right.clone());
+        (left, Some(right))
+    } else {
+        let (middle, right) = split(&node.right, index - left_size - 1);
+        let left = join(node.left.clone(), node.value.clone(), middle);
+        (Some(left), right)
+    }
+}
+
+// Inserts a value so that it ends up at the given position of the subtree
+fn insert<T: Clone>(link: &Link<T>, index: usize, value: T) -> Arc<PersistentNode<T>> {
+    let node = match link {
+        Some(node) => node,
+        None => return self::node(Color::Red, None, value, None),
+    };
+    let left_size = size(&node.left);
+    if index <= left_size {
+        let left = insert(&node.left, index, value);
+        join(Some(left), node.value.clone(), node.right.clone())
+    } else {
+        let right = insert(&node.right, index - left_size - 1, value);
+        join(node.left.clone(), node.value.clone(), Some(right))
+    }
+}
+
+// Removes the value at the given position of a subtree, returning the rest and the value
+fn remove<T: Clone>(node: &Arc<PersistentNode<T>>, index: usize) -> (Link<T>, T) {
+    let left_size = size(&node.left);
+    if index < left_size {
+        let (left, value) = remove(node.left.as_ref().unwrap(), index);
+        let joined = join(left, node.value.clone(), node.right.clone());
+        (Some(joined), value)
+    } else if index == left_size {
+        let joined = join_two(node.left.clone(), node.right.clone());
+        (joined, node.value.clone())
+    } else {
+        let (right, value) = remove(node.right.as_ref().unwrap(), index - left_size - 1);
+        let joined = join(node.left.clone(), node.value.clone(), right);
+        (Some(joined), value)
+    }
+}
+
+// Replaces the value at the given position of a subtree
+fn set<T: Clone>(node: &Arc<PersistentNode<T>>, index: usize, value: T) -> Arc<PersistentNode<T>> {
+    let left_size = size(&node.left);
+    let (left, value, right) = match index.cmp(&left_size) {
+        std::cmp::Ordering::Less => (
+            Some(set(node.left.as_ref().unwrap(), index, value)),
+            node.value.clone(),
+            node.right.clone(),
+        ),
+        std::cmp::Ordering::Equal => (node.left.clone(), value, node.right.clone()),
+        std::cmp::Ordering::Greater => (
+            node.left.clone(),
+            node.value.clone(),
+            Some(set(
+                node.right.as_ref().unwrap(),
+                index - left_size - 1,
+                value,
+            )),
+        ),
+    };
+    self::node(node.color, left, value, right)
+}
+
+// Makes the root of a tree black so that it is a valid red-black tree
+fn blacken<T: Clone>(root: Arc<PersistentNode<T>>) -> Link<T> {
+    if root.color == Color::Red {
+        Some(recolor(&root, Color::Black))
+    } else {
+        Some(root)
+    }
+}
+
+impl<T: Clone> PersistentTree<T> {
+    /// Creates a new empty tree
+    pub fn new() -> Self {
+        PersistentTree { root: None }
+    }
+
+    /// Returns the number of values in the tree
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// Returns true if the tree contains no values
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns a reference to the value at the given position, or None if the index is out of
+    /// bounds
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The zero based position of the value
+    ///
+    pub fn get(&self, mut index: usize) -> Option<&T> {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let left_size = size(&node.left);
+            match index.cmp(&left_size) {
+                std::cmp::Ordering::Less => link = &node.left,
+                std::cmp::Ordering::Equal => return Some(&node.value),
+                std::cmp::Ordering::Greater => {
+                    index -= left_size + 1;
+                    link = &node.right;
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the first value in the tree
+    pub fn first(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Returns the last value in the tree
+    pub fn last(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// Returns a new tree with the value inserted so that it ends up at the given position,
+    /// leaving this tree unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of values in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The zero based position the new value will have
+    /// * `value` - The value to insert
+    ///
+    pub fn insert_at(&self, index: usize, value: T) -> Self {
+        assert!(
+            index <= self.len(),
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            self.len()
+        );
+        PersistentTree {
+            root: blacken(insert(&self.root, index, value)),
+        }
+    }
+
+    /// Returns a new tree with the value added before the first value
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to insert
+    ///
+    pub fn push_front(&self, value: T) -> Self {
+        self.insert_at(0, value)
+    }
+
+    /// Returns a new tree with the value added after the last value
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to insert
+    ///
+    pub fn push_back(&self, value: T) -> Self {
+        self.insert_at(self.len(), value)
+    }
+
+    /// Returns a new tree without the value at the given position along with the removed value,
+    /// or None if the index is out of bounds. This tree is left unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The zero based position of the value to remove
+    ///
+    pub fn remove_at(&self, index: usize) -> Option<(Self, T)> {
+        if index >= self.len() {
+            return None;
+        }
+        let (root, value) = remove(self.root.as_ref().unwrap(), index);
+        let tree = PersistentTree {
+            root: root.and_then(blacken),
+        };
+        Some((tree, value))
+    }
+
+    /// Returns a new tree with the value at the given position replaced, leaving this tree
+    /// unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The zero based position of the value to replace
+    /// * `value` - The new value
+    ///
+    pub fn set(&self, index: usize, value: T) -> Self {
+        assert!(
+            index < self.len(),
+            "index (is {}) should be < len (is {})",
+            index,
+            self.len()
+        );
+        PersistentTree {
+            root: Some(set(self.root.as_ref().unwrap(), index, value)),
+        }
+    }
+
+    /// Returns two new trees, one holding the first `index` values and the other the rest
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of values in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The number of values in the first tree
+    ///
+    pub fn split_at(&self, index: usize) -> (Self, Self) {
+        assert!(
+            index <= self.len(),
+            "split index (is {}) should be <= len (is {})",
+            index,
+            self.len()
+        );
+        let (left, right) = split(&self.root, index);
+        (
+            PersistentTree {
+                root: left.and_then(blacken),
+            },
+            PersistentTree {
+                root: right.and_then(blacken),
+            },
+        )
+    }
+
+    /// Returns a new tree holding the values of this tree followed by the values of another,
+    /// in O(log n) time
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The tree whose values come after the values of this one
+    ///
+    pub fn concat(&self, other: &Self) -> Self {
+        PersistentTree {
+            root: join_two(self.root.clone(), other.root.clone()).and_then(blacken),
+        }
+    }
+
+    /// Returns an iterator over the values of the tree, in order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let mut stack: Vec<&PersistentNode<T>> = vec![];
+        let mut link = self.root.as_deref();
+        std::iter::from_fn(move || {
+            while let Some(node) = link {
+                stack.push(node);
+                link = node.left.as_deref();
+            }
+            let node = stack.pop()?;
+            link = node.right.as_deref();
+            Some(&node.value)
+        })
+    }
+
+    /// Returns a copy of every value in the tree, in order
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: Clone + Ord> PersistentTree<T> {
+    /// Returns a new tree with the value inserted after any equal values, keeping the tree
+    /// sorted if it was already sorted
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to insert
+    ///
+    pub fn insert_sorted(&self, value: T) -> Self {
+        let mut index = 0;
+        let mut link = &self.root;
+        while let Some(node) = link {
+            if value < node.value {
+                link = &node.left;
+            } else {
+                index += size(&node.left) + 1;
+                link = &node.right;
+            }
+        }
+        self.insert_at(index, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Checks the red-black invariants, panicking if any are broken, and returns the black height
+    fn check<T: Clone>(link: &Link<T>) -> usize {
+        match link {
+            None => 0,
+            Some(node) => {
+                if node.color == Color::Red {
+                    assert_eq!(color(&node.left), Color::Black, "red node has a red child");
+                    assert_eq!(color(&node.right), Color::Black, "red node has a red child");
+                }
+                let height = check(&node.left);
+                assert_eq!(height, check(&node.right), "black heights differ");
+                assert_eq!(node.size, size(&node.left) + size(&node.right) + 1);
+                let height = height + (node.color == Color::Black) as usize;
+                assert_eq!(node.black_height, height);
+                height
+            }
+        }
+    }
+
+    fn assert_valid<T: Clone>(tree: &PersistentTree<T>) {
+        assert_eq!(color(&tree.root), Color::Black);
+        check(&tree.root);
+    }
+
+    #[test]
+    fn persistent_versions_test() {
+        let mut versions = vec![PersistentTree::new()];
+        let mut models: Vec<Vec<usize>> = vec![vec![]];
+        for i in 0..300 {
+            let (tree, model) = (versions.last().unwrap(), models.last().unwrap());
+            let (tree, mut model) = (tree.clone(), model.clone());
+            let tree = if i % 5 == 4 {
+                let index = (i * 7) % model.len();
+                let (tree, value) = tree.remove_at(index).unwrap();
+                assert_eq!(value, model.remove(index));
+                tree
+            } else {
+                let index = (i * 13) % (model.len() + 1);
+                model.insert(index, i);
+                tree.insert_at(index, i)
+            };
+            assert_valid(&tree);
+            versions.push(tree);
+            models.push(model);
+        }
+
+        // Every old version is unchanged by the versions made from it
+        for (tree, model) in versions.iter().zip(models.iter()) {
+            assert_eq!(tree.to_vec(), *model);
+            assert_eq!(tree.len(), model.len());
+        }
+        let last = versions.last().unwrap();
+        assert_eq!(last.get(10), models.last().unwrap().get(10));
+        assert_eq!(last.remove_at(last.len()), None);
+    }
+
+    #[test]
+    fn split_concat_test() {
+        let tree: PersistentTree<u32> = (0..100).collect();
+        assert_valid(&tree);
+        for index in [0, 1, 37, 99, 100].iter().copied() {
+            let (left, right) = tree.split_at(index);
+            assert_valid(&left);
+            assert_valid(&right);
+            assert_eq!(left.len(), index);
+            assert_eq!(right.first(), tree.get(index));
+            let joined = left.concat(&right);
+            assert_valid(&joined);
+            assert_eq!(joined, tree);
+        }
+
+        let changed = tree.set(5, 500).push_front(7).push_back(8);
+        assert_eq!(changed.get(6), Some(&500));
+        assert_eq!((changed.first(), changed.last()), (Some(&7), Some(&8)));
+        assert_eq!(tree.get(5), Some(&5));
+    }
+
+    #[test]
+    fn insert_sorted_test() {
+        let mut tree = PersistentTree::new();
+        for i in 0..50 {
+            tree = tree.insert_sorted((i * 17) % 23);
+        }
+        assert_valid(&tree);
+        let values = tree.to_vec();
+        assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
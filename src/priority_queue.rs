@@ -0,0 +1,173 @@
+use crate::{NodeKey, Tree};
+use std::fmt;
+
+/// A min priority queue backed by a red-black tree. Pushing an item returns the NodeKey of the
+/// node storing it, which can be used as a handle to remove the item or change its priority later.
+/// Items with equal priorities are popped in the order they were pushed.
+pub struct TreePriorityQueue<P: Ord + Clone + fmt::Debug, T: Clone + fmt::Debug> {
+    tree: Tree<(P, T)>,
+}
+
+impl<P: Ord + Clone + fmt::Debug, T: Clone + fmt::Debug> Default for TreePriorityQueue<P, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Ord + Clone + fmt::Debug, T: Clone + fmt::Debug> TreePriorityQueue<P, T> {
+    /// Create a new empty priority queue
+    pub fn new() -> Self {
+        TreePriorityQueue { tree: Tree::new() }
+    }
+
+    /// Returns a reference to the underlying tree, for navigating the items in priority order
+    pub fn tree(&self) -> &Tree<(P, T)> {
+        &self.tree
+    }
+
+    /// Returns the number of items in the queue
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the queue contains no items
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Adds an item to the queue, returning a handle to it
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - The priority of the item, lower priorities are popped first
+    /// * `item` - The item to add
+    ///
+    pub fn push(&mut self, priority: P, item: T) -> NodeKey {
+        let key = priority.clone();
+        self.tree
+            .insert_by((priority, item), |(existing, _)| existing.cmp(&key))
+    }
+
+    /// Returns the item with the lowest priority along with its priority
+    pub fn peek_min(&self) -> Option<(&P, &T)> {
+        let node = self.tree.get_leftmost_node()?;
+        let (priority, item) = self.tree.get_contents(node);
+        Some((priority, item))
+    }
+
+    /// Removes and returns the item with the lowest priority along with its priority
+    pub fn pop_min(&mut self) -> Option<(P, T)> {
+        self.tree.pop_first()
+    }
+
+    /// Returns the item with the specified handle along with its priority, or None if the item is
+    /// no longer in the queue
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - The handle returned when the item was pushed
+    ///
+    pub fn get(&self, handle: NodeKey) -> Option<(&P, &T)> {
+        let (priority, item) = self.tree.try_get_contents(handle)?;
+        Some((priority, item))
+    }
+
+    /// Removes the item with the specified handle from the queue, returning it along with its
+    /// priority, or None if the item is no longer in the queue
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - The handle returned when the item was pushed
+    ///
+    pub fn remove(&mut self, handle: NodeKey) -> Option<(P, T)> {
+        self.tree.try_delete_node(handle).ok()
+    }
+
+    /// Changes the priority of an item in the queue, usually to lower it. The handle stays valid
+    /// whether or not the item changes its place in the queue. Returns false if the item is no
+    /// longer in the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - The handle of the item
+    /// * `priority` - The new priority of the item
+    ///
+    pub fn decrease_key(&mut self, handle: NodeKey, priority: P) -> bool {
+        if self.tree.try_get_contents(handle).is_none() {
+            return false;
+        }
+        let fits_before = self
+            .tree
+            .get_prev(handle)
+            .is_none_or(|prev| self.tree.get_contents(prev).0 <= priority);
+        let fits_after = self
+            .tree
+            .get_next(handle)
+            .is_none_or(|next| self.tree.get_contents(next).0 > priority);
+        let key = priority.clone();
+        self.tree.get_mut_contents(handle).0 = priority;
+        if !(fits_before && fits_after) {
+            // The item goes after those with an equal priority, as if it had just been pushed
+            self.tree
+                .reposition_by(handle, |(existing, _)| existing.cmp(&key));
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_queue_test() {
+        let mut queue = TreePriorityQueue::new();
+        assert_eq!(queue.peek_min(), None);
+        let mut handles = vec![];
+        for i in 0..20 {
+            handles.push(queue.push((i * 7) % 10, i));
+        }
+        assert_eq!(queue.peek_min(), Some((&0, &0)));
+
+        // Equal priorities come out in the order they were pushed
+        assert_eq!(queue.pop_min(), Some((0, 0)));
+        assert_eq!(queue.pop_min(), Some((0, 10)));
+        assert_eq!(queue.remove(handles[0]), None);
+
+        assert!(queue.decrease_key(handles[19], 0));
+        assert_eq!(queue.get(handles[19]), Some((&0, &19)));
+        assert_eq!(queue.remove(handles[1]), Some((7, 1)));
+        assert!(!queue.decrease_key(handles[1], 0));
+
+        let mut popped = vec![];
+        while let Some((priority, item)) = queue.pop_min() {
+            popped.push((priority, item));
+        }
+        assert_eq!(popped.len(), 17);
+        assert_eq!(popped[0], (0, 19));
+        assert!(popped.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    }
+
+    #[test]
+    fn decrease_key_in_place_test() {
+        let mut queue = TreePriorityQueue::new();
+        let first = queue.push(10, 'a');
+        let second = queue.push(20, 'b');
+        let third = queue.push(30, 'c');
+        assert!(queue.decrease_key(second, 15));
+        assert!(queue.decrease_key(first, 5));
+
+        // Items that have to move keep their handles, including ones raised past the others
+        assert!(queue.decrease_key(second, 1));
+        queue.tree().assert_valid();
+        assert!(queue.decrease_key(first, 40));
+        queue.tree().assert_valid();
+        assert_eq!(queue.get(second), Some((&1, &'b')));
+        assert_eq!(queue.get(first), Some((&40, &'a')));
+        assert_eq!(queue.pop_min(), Some((1, 'b')));
+        assert!(queue.get(second).is_none());
+        assert!(queue.decrease_key(first, 30));
+        assert_eq!(queue.tree().get_prev(first), Some(third));
+        queue.tree().assert_valid();
+    }
+}
@@ -0,0 +1,157 @@
+//! A Python class wrapping a tree of Python objects. Building the crate as a cdylib with the
+//! python feature, with `cargo rustc --release --lib --features python --crate-type cdylib`,
+//! gives an extension module named `binary_search_tree` holding the `Tree` class.
+//!
+//! Nodes are identified by integer ids, as in the C interface. Passing an id that isn't in the
+//! tree raises a KeyError.
+
+use crate::{NodeKey, Tree};
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use slotmap::KeyData;
+use std::vec;
+
+/// A sequence of Python objects kept in a red-black tree, with stable integer ids for its nodes
+#[pyclass(name = "Tree", module = "binary_search_tree", unsendable)]
+pub struct PyTree {
+    tree: Tree<Py<PyAny>>,
+}
+
+/// An iterator over the contents of a `Tree`, taken when the iterator was created
+#[pyclass(name = "TreeIterator", module = "binary_search_tree", unsendable)]
+pub struct PyTreeIterator {
+    contents: vec::IntoIter<Py<PyAny>>,
+}
+
+fn to_id(node: NodeKey) -> u64 {
+    KeyData::from(node).as_ffi()
+}
+
+impl PyTree {
+    // Returns the node with the given id, raising a KeyError if it isn't in the tree
+    fn node(&self, id: u64) -> PyResult<NodeKey> {
+        let node = NodeKey::from(KeyData::from_ffi(id));
+        if self.tree.contains_node(node) {
+            Ok(node)
+        } else {
+            Err(PyKeyError::new_err(format!("no node with the id {}", id)))
+        }
+    }
+}
+
+#[pymethods]
+impl PyTree {
+    /// Creates an empty tree
+    #[new]
+    fn new() -> Self {
+        PyTree { tree: Tree::new() }
+    }
+
+    fn __len__(&self) -> usize {
+        self.tree.len()
+    }
+
+    fn __iter__(&self, py: Python<'_>) -> PyTreeIterator {
+        let contents: Vec<Py<PyAny>> = self
+            .tree
+            .iter_with_keys()
+            .map(|(_, contents)| contents.clone_ref(py))
+            .collect();
+        PyTreeIterator {
+            contents: contents.into_iter(),
+        }
+    }
+
+    /// Adds the first node to an empty tree and returns its id
+    fn create_root(&mut self, contents: Py<PyAny>) -> PyResult<u64> {
+        self.tree
+            .try_create_root(contents)
+            .map(to_id)
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    /// Inserts an object before the node with the given id and returns the id of its node
+    fn insert_before(&mut self, node: u64, contents: Py<PyAny>) -> PyResult<u64> {
+        let node = self.node(node)?;
+        Ok(to_id(self.tree.insert_before(node, contents)))
+    }
+
+    /// Inserts an object after the node with the given id and returns the id of its node
+    fn insert_after(&mut self, node: u64, contents: Py<PyAny>) -> PyResult<u64> {
+        let node = self.node(node)?;
+        Ok(to_id(self.tree.insert_after(node, contents)))
+    }
+
+    /// Deletes the node with the given id and returns its object
+    fn delete(&mut self, node: u64) -> PyResult<Py<PyAny>> {
+        let node = self.node(node)?;
+        Ok(self.tree.delete_node(node))
+    }
+
+    /// Returns the object held by the node with the given id
+    fn get(&self, py: Python<'_>, node: u64) -> PyResult<Py<PyAny>> {
+        let node = self.node(node)?;
+        Ok(self.tree.get_contents(node).clone_ref(py))
+    }
+
+    /// Returns the ids of every node, in order
+    fn nodes(&self) -> Vec<u64> {
+        self.tree
+            .iter_with_keys()
+            .map(|(node, _)| to_id(node))
+            .collect()
+    }
+}
+
+#[pymethods]
+impl PyTreeIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<Py<PyAny>> {
+        self.contents.next()
+    }
+}
+
+/// The extension module holding the `Tree` class
+#[pymodule]
+fn binary_search_tree(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyTree>()?;
+    module.add_class::<PyTreeIterator>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn python_tree_test() {
+        Python::initialize();
+        Python::attach(|py| {
+            let tree = Bound::new(py, PyTree::new()).unwrap();
+            let locals = pyo3::types::PyDict::new(py);
+            locals.set_item("tree", &tree).unwrap();
+            let script = CString::new(
+                "
+root = tree.create_root('b')
+first = tree.insert_before(root, 'a')
+tree.insert_after(root, 'c')
+assert list(tree) == ['a', 'b', 'c']
+assert len(tree) == 3
+assert tree.delete(first) == 'a'
+assert tree.nodes()[0] == root and tree.get(root) == 'b'
+try:
+    tree.get(first)
+    assert False
+except KeyError:
+    pass
+",
+            )
+            .unwrap();
+            py.run(&script, None, Some(&locals)).unwrap();
+        });
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{Entry, NodeKey, Tree};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// A sorted map backed by a red-black tree, for when the tree is only needed to look values up
+/// by key and the NodeKeys of the entries don't need to be managed.
+pub struct RBMap<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> {
+    tree: Tree<(K, V)>,
+}
+
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> Default for RBMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> RBMap<K, V> {
+    /// Create a new empty map
+    pub fn new() -> Self {
+        RBMap { tree: Tree::new() }
+    }
+
+    /// Returns a reference to the underlying tree, for navigating the entries in order
+    pub fn tree(&self) -> &Tree<(K, V)> {
+        &self.tree
+    }
+
+    /// Returns the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the map contains no entries
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Removes all entries from the map
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    /// Inserts a value into the map, returning the previous value stored for the key if there was
+    /// one.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to store the value under
+    /// * `value` - The value to store
+    ///
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.tree.entry_by(|(existing, _)| existing.cmp(&key)) {
+            Entry::Occupied(mut entry) => Some(std::mem::replace(&mut entry.get_mut().1, value)),
+            Entry::Vacant(entry) => {
+                entry.insert((key, value));
+                None
+            }
+        }
+    }
+
+    /// Returns a reference to the value stored for the key
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to look up
+    ///
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let node = self.find(key)?;
+        Some(&self.tree.get_contents(node).1)
+    }
+
+    /// Returns a mutable reference to the value stored for the key
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to look up
+    ///
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let node = self.find(key)?;
+        Some(&mut self.tree.get_mut_contents(node).1)
+    }
+
+    /// Returns true if the map contains a value for the key
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to look up
+    ///
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Removes the entry for the key from the map, returning its value if there was one
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to remove
+    ///
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let node = self.find(key)?;
+        Some(self.tree.delete_node(node).1)
+    }
+
+    /// Returns the entry with the smallest key
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.tree
+            .get_leftmost_node()
+            .map(|node| self.key_value(node))
+    }
+
+    /// Returns the entry with the largest key
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.tree
+            .get_rightmost_node()
+            .map(|node| self.key_value(node))
+    }
+
+    /// Returns an iterator over the entries of the map, sorted by key
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        std::iter::successors(self.tree.get_leftmost_node(), move |&node| {
+            self.tree.get_next(node)
+        })
+        .map(move |node| self.key_value(node))
+    }
+
+    /// Returns an iterator over the keys of the map, in sorted order
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Returns an iterator over the values of the map, sorted by their keys
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    fn find(&self, key: &K) -> Option<NodeKey> {
+        self.tree.find(|(existing, _)| existing.cmp(key))
+    }
+
+    fn key_value(&self, node: NodeKey) -> (&K, &V) {
+        let (key, value) = self.tree.get_contents(node);
+        (key, value)
+    }
+}
+
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> From<BTreeMap<K, V>> for RBMap<K, V> {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        RBMap {
+            tree: Tree::from_sorted_iter(map),
+        }
+    }
+}
+
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> From<RBMap<K, V>> for BTreeMap<K, V> {
+    fn from(map: RBMap<K, V>) -> Self {
+        map.tree.into_sorted_vec().into_iter().collect()
+    }
+}
+
+/// Converts a vector of entries sorted by strictly increasing key into a map, returning the vector
+/// unchanged if it is not sorted
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> TryFrom<Vec<(K, V)>> for RBMap<K, V> {
+    type Error = Vec<(K, V)>;
+
+    fn try_from(entries: Vec<(K, V)>) -> Result<Self, Self::Error> {
+        if entries.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(entries);
+        }
+        Ok(RBMap {
+            tree: Tree::from_sorted_iter(entries),
+        })
+    }
+}
+
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> From<RBMap<K, V>> for Vec<(K, V)> {
+    fn from(map: RBMap<K, V>) -> Self {
+        map.tree.into_sorted_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rb_map_test() {
+        let mut map: RBMap<i32, String> = RBMap::new();
+        let mut model = BTreeMap::new();
+        for i in 0..200 {
+            let key = (i * 37) % 101;
+            assert_eq!(
+                map.insert(key, i.to_string()),
+                model.insert(key, i.to_string())
+            );
+        }
+        assert_eq!(map.len(), model.len());
+        for key in (0..101).step_by(3) {
+            assert_eq!(map.remove(&key), model.remove(&key));
+            assert_eq!(map.remove(&key), None);
+        }
+        for key in -5..110 {
+            assert_eq!(map.get(&key), model.get(&key));
+            assert_eq!(map.contains_key(&key), model.contains_key(&key));
+        }
+        assert!(map.iter().eq(model.iter()));
+        assert_eq!(map.first_key_value(), model.iter().next());
+        assert_eq!(map.last_key_value(), model.iter().next_back());
+
+        *map.get_mut(&1).unwrap() = "one".to_string();
+        assert_eq!(map.get(&1).map(String::as_str), Some("one"));
+        map.tree().assert_valid();
+    }
+
+    #[test]
+    fn rb_map_conversions_test() {
+        let model: BTreeMap<u32, char> = (0..100).map(|i| (i * 3, 'x')).collect();
+        let map = RBMap::from(model.clone());
+        map.tree().assert_valid();
+        assert!(map.iter().eq(model.iter()));
+        assert_eq!(BTreeMap::from(map), model);
+
+        let entries = vec![(1, 'a'), (2, 'b'), (5, 'c')];
+        let map = RBMap::try_from(entries.clone()).unwrap();
+        assert_eq!(map.get(&5), Some(&'c'));
+        assert_eq!(Vec::from(map), entries);
+        let unsorted = vec![(2, 'b'), (1, 'a')];
+        assert_eq!(RBMap::try_from(unsorted.clone()).err(), Some(unsorted));
+        assert!(RBMap::try_from(vec![(1, 'a'), (1, 'b')]).is_err());
+    }
+}
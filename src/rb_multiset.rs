@@ -0,0 +1,181 @@
+use crate::{NodeKey, Tree};
+use std::fmt;
+
+/// A sorted multiset backed by a red-black tree. Each distinct value is stored once in the tree
+/// along with the number of times it has been inserted.
+pub struct RBMultiSet<T: Ord + Clone + fmt::Debug> {
+    tree: Tree<(T, usize)>,
+    len: usize,
+}
+
+impl<T: Ord + Clone + fmt::Debug> Default for RBMultiSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone + fmt::Debug> RBMultiSet<T> {
+    /// Create a new empty multiset
+    pub fn new() -> Self {
+        RBMultiSet {
+            tree: Tree::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns a reference to the underlying tree, which stores each distinct value with its count
+    pub fn tree(&self) -> &Tree<(T, usize)> {
+        &self.tree
+    }
+
+    /// Returns the number of values in the multiset, counting duplicates
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of distinct values in the multiset
+    pub fn distinct_len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the multiset contains no values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all values from the multiset
+    pub fn clear(&mut self) {
+        self.tree.clear();
+        self.len = 0;
+    }
+
+    /// Adds a value to the multiset, returning the number of times it is now present
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to add
+    ///
+    pub fn insert(&mut self, value: T) -> usize {
+        self.len += 1;
+        let node = self
+            .tree
+            .entry_by(|(existing, _)| existing.cmp(&value))
+            .or_insert_with(|| (value, 0));
+        let count = &mut self.tree.get_mut_contents(node).1;
+        *count += 1;
+        *count
+    }
+
+    /// Returns the number of times the value is present in the multiset
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to count
+    ///
+    pub fn count(&self, value: &T) -> usize {
+        self.find(value)
+            .map_or(0, |node| self.tree.get_contents(node).1)
+    }
+
+    /// Returns true if the value is present in the multiset at least once
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to look up
+    ///
+    pub fn contains(&self, value: &T) -> bool {
+        self.find(value).is_some()
+    }
+
+    /// Removes one occurrence of a value from the multiset, returning true if it was present
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to remove
+    ///
+    pub fn remove(&mut self, value: &T) -> bool {
+        let node = match self.find(value) {
+            Some(node) => node,
+            None => return false,
+        };
+        self.len -= 1;
+        let count = &mut self.tree.get_mut_contents(node).1;
+        *count -= 1;
+        if *count == 0 {
+            self.tree.delete_node(node);
+        }
+        true
+    }
+
+    /// Removes every occurrence of a value from the multiset, returning how many were removed
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to remove
+    ///
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        let count = match self.find(value) {
+            Some(node) => self.tree.delete_node(node).1,
+            None => 0,
+        };
+        self.len -= count;
+        count
+    }
+
+    /// Returns an iterator over the values of the multiset in sorted order, with each value
+    /// repeated as many times as it is present
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.iter_counts()
+            .flat_map(|(value, count)| std::iter::repeat_n(value, count))
+    }
+
+    /// Returns an iterator over the distinct values of the multiset in sorted order, along with
+    /// the number of times each is present
+    pub fn iter_counts(&self) -> impl Iterator<Item = (&T, usize)> + '_ {
+        std::iter::successors(self.tree.get_leftmost_node(), move |&node| {
+            self.tree.get_next(node)
+        })
+        .map(move |node| {
+            let (value, count) = self.tree.get_contents(node);
+            (value, *count)
+        })
+    }
+
+    fn find(&self, value: &T) -> Option<NodeKey> {
+        self.tree.find(|(existing, _)| existing.cmp(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rb_multiset_test() {
+        let mut set = RBMultiSet::new();
+        for value in [3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5].iter() {
+            set.insert(*value);
+        }
+        assert_eq!(set.len(), 11);
+        assert_eq!(set.distinct_len(), 7);
+        assert_eq!(set.count(&5), 3);
+        assert_eq!(set.count(&7), 0);
+        assert_eq!(
+            set.iter().copied().collect::<Vec<_>>(),
+            vec![1, 1, 2, 3, 3, 4, 5, 5, 5, 6, 9]
+        );
+
+        assert!(set.remove(&1));
+        assert!(set.remove(&1));
+        assert!(!set.remove(&1));
+        assert!(!set.contains(&1));
+        assert_eq!(set.remove_all(&5), 3);
+        assert_eq!(set.remove_all(&5), 0);
+        assert_eq!(set.len(), 6);
+        assert_eq!(
+            set.iter_counts().collect::<Vec<_>>(),
+            vec![(&2, 1), (&3, 2), (&4, 1), (&6, 1), (&9, 1)]
+        );
+        set.tree().assert_valid();
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{Entry, NodeKey, Tree};
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// A sorted set backed by a red-black tree, for when the tree is only needed to test membership
+/// and the NodeKeys of the values don't need to be managed.
+pub struct RBSet<T: Ord + Clone + fmt::Debug> {
+    tree: Tree<T>,
+}
+
+impl<T: Ord + Clone + fmt::Debug> Default for RBSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone + fmt::Debug> RBSet<T> {
+    /// Create a new empty set
+    pub fn new() -> Self {
+        RBSet { tree: Tree::new() }
+    }
+
+    /// Returns a reference to the underlying tree, for navigating the values in order
+    pub fn tree(&self) -> &Tree<T> {
+        &self.tree
+    }
+
+    /// Returns the number of values in the set
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns true if the set contains no values
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Removes all values from the set
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    /// Adds a value to the set, returning true if it was not already present
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to add
+    ///
+    pub fn insert(&mut self, value: T) -> bool {
+        match self.tree.entry_by(|existing| existing.cmp(&value)) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                true
+            }
+        }
+    }
+
+    /// Returns true if the set contains the value
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to look up
+    ///
+    pub fn contains(&self, value: &T) -> bool {
+        self.find(value).is_some()
+    }
+
+    /// Removes a value from the set, returning true if it was present
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to remove
+    ///
+    pub fn remove(&mut self, value: &T) -> bool {
+        match self.find(value) {
+            Some(node) => {
+                self.tree.delete_node(node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the smallest value in the set
+    pub fn first(&self) -> Option<&T> {
+        self.tree
+            .get_leftmost_node()
+            .map(|node| self.tree.get_contents(node))
+    }
+
+    /// Returns the largest value in the set
+    pub fn last(&self) -> Option<&T> {
+        self.tree
+            .get_rightmost_node()
+            .map(|node| self.tree.get_contents(node))
+    }
+
+    /// Removes and returns the smallest value in the set
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.tree.pop_first()
+    }
+
+    /// Removes and returns the largest value in the set
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.tree.pop_last()
+    }
+
+    /// Returns an iterator over the values of the set, in sorted order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        std::iter::successors(self.tree.get_leftmost_node(), move |&node| {
+            self.tree.get_next(node)
+        })
+        .map(move |node| self.tree.get_contents(node))
+    }
+
+    fn find(&self, value: &T) -> Option<NodeKey> {
+        self.tree.find(|existing| existing.cmp(value))
+    }
+}
+
+impl<T: Ord + Clone + fmt::Debug> From<BTreeSet<T>> for RBSet<T> {
+    fn from(set: BTreeSet<T>) -> Self {
+        RBSet {
+            tree: Tree::from_sorted_iter(set),
+        }
+    }
+}
+
+impl<T: Ord + Clone + fmt::Debug> From<RBSet<T>> for BTreeSet<T> {
+    fn from(set: RBSet<T>) -> Self {
+        set.tree.into_sorted_vec().into_iter().collect()
+    }
+}
+
+/// Converts a strictly increasing vector into a set, returning the vector unchanged if it is not
+/// sorted or contains duplicates
+impl<T: Ord + Clone + fmt::Debug> TryFrom<Vec<T>> for RBSet<T> {
+    type Error = Vec<T>;
+
+    fn try_from(values: Vec<T>) -> Result<Self, Self::Error> {
+        if values.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(values);
+        }
+        Ok(RBSet {
+            tree: Tree::from_sorted_iter(values),
+        })
+    }
+}
+
+impl<T: Ord + Clone + fmt::Debug> From<RBSet<T>> for Vec<T> {
+    fn from(set: RBSet<T>) -> Self {
+        set.tree.into_sorted_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rb_set_test() {
+        let mut set = RBSet::new();
+        let mut model = BTreeSet::new();
+        assert_eq!(set.first(), None);
+        for i in 0..200 {
+            let value = (i * 53) % 97;
+            assert_eq!(set.insert(value), model.insert(value));
+        }
+        for value in (0..97).step_by(4) {
+            assert_eq!(set.remove(&value), model.remove(&value));
+            assert!(!set.remove(&value));
+        }
+        assert_eq!(set.len(), model.len());
+        for value in -3..100 {
+            assert_eq!(set.contains(&value), model.contains(&value));
+        }
+        assert!(set.iter().eq(model.iter()));
+        assert_eq!(set.first(), model.iter().next());
+        assert_eq!(set.last(), model.iter().next_back());
+        assert_eq!(set.pop_first(), Some(1));
+        assert_eq!(set.pop_last(), Some(95));
+        set.tree().assert_valid();
+    }
+
+    #[test]
+    fn rb_set_conversions_test() {
+        let model: BTreeSet<i32> = (-50..50).step_by(7).collect();
+        let set = RBSet::from(model.clone());
+        set.tree().assert_valid();
+        assert!(set.iter().eq(model.iter()));
+        assert_eq!(BTreeSet::from(set), model);
+
+        let set = RBSet::try_from(vec![1, 4, 9]).unwrap();
+        assert!(set.contains(&4));
+        assert_eq!(Vec::from(set), vec![1, 4, 9]);
+        assert_eq!(RBSet::try_from(vec![1, 1, 2]).err(), Some(vec![1, 1, 2]));
+    }
+}
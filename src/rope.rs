@@ -0,0 +1,252 @@
+use crate::{Augment, NodeKey, Tree};
+use std::fmt;
+use std::ops::Range;
+
+// The largest number of chars stored in a single chunk of a rope
+const MAX_CHUNK_CHARS: usize = 64;
+
+/// Augmentation that tracks the number of chars within each subtree of a rope
+#[derive(Clone, Default)]
+pub struct CharCount;
+
+impl Augment<String> for CharCount {
+    type Value = usize;
+
+    fn update(&self, contents: &String, left: Option<&usize>, right: Option<&usize>) -> usize {
+        contents.chars().count() + left.unwrap_or(&0) + right.unwrap_or(&0)
+    }
+}
+
+/// A text buffer that stores its text as a sequence of chunks in a tree. Every node is augmented
+/// with the number of chars in its subtree, so the chunk holding any char offset can be found in
+/// O(log n) time. All offsets are measured in chars rather than bytes.
+pub struct Rope {
+    tree: Tree<String, CharCount>,
+}
+
+impl Default for Rope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rope {
+    /// Create a new empty rope
+    pub fn new() -> Self {
+        Rope {
+            tree: Tree::with_augment(CharCount),
+        }
+    }
+
+    /// Returns a reference to the underlying tree, for navigating the chunks in order
+    pub fn tree(&self) -> &Tree<String, CharCount> {
+        &self.tree
+    }
+
+    /// Returns the number of chars in the rope
+    pub fn len_chars(&self) -> usize {
+        self.tree
+            .root
+            .map_or(0, |root| *self.tree.get_augmented(root))
+    }
+
+    /// Returns true if the rope contains no text
+    pub fn is_empty(&self) -> bool {
+        self.len_chars() == 0
+    }
+
+    /// Returns the char at the offset, or None if the offset is past the end of the text
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset of the char
+    ///
+    pub fn char_at(&self, offset: usize) -> Option<char> {
+        if offset >= self.len_chars() {
+            return None;
+        }
+        let (node, offset) = self.find_chunk(offset, false);
+        self.tree.get_contents(node).chars().nth(offset)
+    }
+
+    /// Inserts text at the offset, so that its first char ends up at that offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is greater than the number of chars in the rope.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset to insert the text at
+    /// * `text` - The text to insert
+    ///
+    pub fn insert(&mut self, offset: usize, text: &str) {
+        let len = self.len_chars();
+        assert!(
+            offset <= len,
+            "insertion offset (is {}) should be <= len (is {})",
+            offset,
+            len
+        );
+        if text.is_empty() {
+            return;
+        }
+        if self.tree.is_empty() {
+            let mut chunks = Self::chunks(text);
+            let mut node = self.tree.create_root(chunks.remove(0));
+            for chunk in chunks {
+                node = self.tree.insert_after(node, chunk);
+            }
+            return;
+        }
+
+        let (node, offset) = self.find_chunk(offset, true);
+        let chunk = self.tree.get_contents(node);
+        let split = Self::byte_offset(chunk, offset);
+        let combined = [&chunk[..split], text, &chunk[split..]].concat();
+        let mut chunks = Self::chunks(&combined);
+        let mut node = node;
+        self.tree.set_contents(node, chunks.remove(0));
+        for chunk in chunks {
+            node = self.tree.insert_after(node, chunk);
+        }
+    }
+
+    /// Removes the chars within the range from the rope.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range ends before it starts or past the end of the text.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The offsets of the chars to remove
+    ///
+    pub fn remove(&mut self, range: Range<usize>) {
+        let len = self.len_chars();
+        assert!(
+            range.start <= range.end,
+            "the range must not end before it starts"
+        );
+        assert!(
+            range.end <= len,
+            "range end (is {}) should be <= len (is {})",
+            range.end,
+            len
+        );
+        let mut remaining = range.end - range.start;
+        while remaining > 0 {
+            let (node, offset) = self.find_chunk(range.start, false);
+            let chunk = self.tree.get_mut_contents(node);
+            let removed = std::cmp::min(remaining, chunk.chars().count() - offset);
+            let start = Self::byte_offset(chunk, offset);
+            let end = Self::byte_offset(chunk, offset + removed);
+            chunk.replace_range(start..end, "");
+            if chunk.is_empty() {
+                self.tree.delete_node(node);
+            } else {
+                self.tree.update_augmented(node);
+            }
+            remaining -= removed;
+        }
+    }
+
+    // Returns the chunk holding the char at the offset, along with the offset of the char within
+    // that chunk. When `at_end` is true an offset at the end of a chunk is found in that chunk
+    // rather than at the start of the next one.
+    fn find_chunk(&self, mut offset: usize, at_end: bool) -> (NodeKey, usize) {
+        let mut node = self.tree.root.unwrap();
+        loop {
+            let left = self.tree.get_left(node);
+            let left_len = left.map_or(0, |left| *self.tree.get_augmented(left));
+            if offset < left_len {
+                node = left.unwrap();
+                continue;
+            }
+            offset -= left_len;
+            let chunk_len = self.tree.get_contents(node).chars().count();
+            if offset < chunk_len || (at_end && offset == chunk_len) {
+                return (node, offset);
+            }
+            offset -= chunk_len;
+            node = self.tree.get_right(node).unwrap();
+        }
+    }
+
+    // Returns the byte offset of the char at the offset within a string
+    fn byte_offset(text: &str, offset: usize) -> usize {
+        text.char_indices()
+            .nth(offset)
+            .map_or(text.len(), |(index, _)| index)
+    }
+
+    // Splits text into chunks that are no longer than the maximum chunk size
+    fn chunks(text: &str) -> Vec<String> {
+        let chars: Vec<char> = text.chars().collect();
+        chars
+            .chunks(MAX_CHUNK_CHARS)
+            .map(|chunk| chunk.iter().collect())
+            .collect()
+    }
+}
+
+impl From<&str> for Rope {
+    fn from(text: &str) -> Self {
+        let mut rope = Rope::new();
+        rope.insert(0, text);
+        rope
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut node = self.tree.get_leftmost_node();
+        while let Some(current) = node {
+            f.write_str(self.tree.get_contents(current))?;
+            node = self.tree.get_next(current);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rope_test() {
+        let mut rope = Rope::new();
+        let mut model: Vec<char> = vec![];
+        assert_eq!(rope.char_at(0), None);
+        for i in 0..200 {
+            let text = format!("{}é", i).repeat(i % 7 + 1);
+            let offset = (i * 31) % (model.len() + 1);
+            rope.insert(offset, &text);
+            model.splice(offset..offset, text.chars());
+        }
+        assert_eq!(rope.len_chars(), model.len());
+        assert_eq!(rope.to_string(), model.iter().collect::<String>());
+
+        for i in 0..100 {
+            let start = (i * 17) % model.len();
+            let end = std::cmp::min(model.len(), start + (i * 13) % 40);
+            rope.remove(start..end);
+            model.drain(start..end);
+        }
+        assert_eq!(rope.to_string(), model.iter().collect::<String>());
+        for (offset, &c) in model.iter().enumerate() {
+            assert_eq!(rope.char_at(offset), Some(c));
+        }
+        assert!(rope
+            .tree()
+            .to_vec()
+            .iter()
+            .all(|chunk| !chunk.is_empty() && chunk.chars().count() <= MAX_CHUNK_CHARS));
+
+        let len = rope.len_chars();
+        rope.remove(0..len);
+        assert!(rope.is_empty());
+        assert!(rope.tree().is_empty());
+        assert_eq!(Rope::from("héllo").char_at(1), Some('é'));
+    }
+}
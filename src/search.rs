@@ -0,0 +1,107 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use std::cmp::Ordering;
+use std::fmt;
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Binary searches the tree using a comparison function and returns the NodeKey of a matching
+    /// node, or None if there is no match. If several nodes match, any one of them may be
+    /// returned. The tree must be ordered consistently with the comparison function, which follows
+    /// the same convention as `slice::binary_search_by`.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Returns the ordering of a node's contents relative to the target
+    ///
+    pub fn find<F: FnMut(&T) -> Ordering>(&self, mut f: F) -> Option<NodeKey> {
+        let mut node = self.root;
+        while let Some(current) = node {
+            node = match f(self.get_contents(current)) {
+                Ordering::Less => self.get_right(current),
+                Ordering::Greater => self.get_left(current),
+                Ordering::Equal => return Some(current),
+            }
+        }
+        None
+    }
+
+    /// Returns the NodeKey of the first node that is not less than the target, or None if every
+    /// node is less than the target.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Returns the ordering of a node's contents relative to the target
+    ///
+    pub fn lower_bound<F: FnMut(&T) -> Ordering>(&self, mut f: F) -> Option<NodeKey> {
+        self.first_node_where(|contents| f(contents) != Ordering::Less)
+    }
+
+    /// Returns the NodeKey of the first node that is greater than the target, or None if no node
+    /// is greater than the target.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Returns the ordering of a node's contents relative to the target
+    ///
+    pub fn upper_bound<F: FnMut(&T) -> Ordering>(&self, mut f: F) -> Option<NodeKey> {
+        self.first_node_where(|contents| f(contents) == Ordering::Greater)
+    }
+
+    // Returns the first node for which the predicate holds, the predicate must be false for a
+    // prefix of the sequence and true for the rest of it
+    fn first_node_where<F: FnMut(&T) -> bool>(&self, mut predicate: F) -> Option<NodeKey> {
+        let mut found = None;
+        let mut node = self.root;
+        while let Some(current) = node {
+            if predicate(self.get_contents(current)) {
+                found = Some(current);
+                node = self.get_left(current);
+            } else {
+                node = self.get_right(current);
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        assert_eq!(tree.find(|contents| contents.cmp(&1)), None);
+
+        let mut nodes = vec![tree.create_root(0)];
+        for i in 1..20 {
+            nodes.push(tree.insert_after(nodes[i - 1], i * 2));
+        }
+        for (i, &node) in nodes.iter().enumerate() {
+            assert_eq!(tree.find(|contents| contents.cmp(&(i * 2))), Some(node));
+            assert_eq!(tree.find(|contents| contents.cmp(&(i * 2 + 1))), None);
+        }
+    }
+
+    #[test]
+    fn bounds_test() {
+        let mut tree: Tree<usize> = Tree::new();
+        assert_eq!(tree.lower_bound(|contents| contents.cmp(&1)), None);
+        assert_eq!(tree.upper_bound(|contents| contents.cmp(&1)), None);
+
+        let one = tree.create_root(1);
+        let three_a = tree.insert_after(one, 3);
+        let three_b = tree.insert_after(three_a, 3);
+        let five = tree.insert_after(three_b, 5);
+
+        assert_eq!(tree.lower_bound(|contents| contents.cmp(&0)), Some(one));
+        assert_eq!(tree.lower_bound(|contents| contents.cmp(&1)), Some(one));
+        assert_eq!(tree.lower_bound(|contents| contents.cmp(&2)), Some(three_a));
+        assert_eq!(tree.lower_bound(|contents| contents.cmp(&3)), Some(three_a));
+        assert_eq!(tree.lower_bound(|contents| contents.cmp(&6)), None);
+
+        assert_eq!(tree.upper_bound(|contents| contents.cmp(&0)), Some(one));
+        assert_eq!(tree.upper_bound(|contents| contents.cmp(&1)), Some(three_a));
+        assert_eq!(tree.upper_bound(|contents| contents.cmp(&3)), Some(five));
+        assert_eq!(tree.upper_bound(|contents| contents.cmp(&5)), None);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::build::NodeParts;
+use crate::{Augment, Balance, Color, Node, NodeKey, NodeStore, Tree};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use slotmap::SecondaryMap;
+use std::fmt;
+
+// The serialized form of a tree. The nodes are listed in order and refer to their children by
+// their position in the list, the NodeKeys they had when serialized are kept so that external
+// references to them can be remapped once the tree is deserialized.
+#[derive(Serialize, Deserialize)]
+struct SerializedTree<T> {
+    root: Option<usize>,
+    nodes: Vec<SerializedNode<T>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedNode<T> {
+    key: NodeKey,
+    contents: T,
+    color: Color,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+impl<T, A, N: NodeStore<Node>, B: Balance> Serialize for Tree<T, A, N, B>
+where
+    T: Clone + fmt::Debug + Serialize,
+    A: Augment<T>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut ranks = SecondaryMap::with_capacity(self.len);
+        let mut node = self.leftmost;
+        while let Some(current) = node {
+            ranks.insert(current, ranks.len());
+            node = self.get_next(current);
+        }
+
+        let mut nodes = Vec::with_capacity(self.len);
+        let mut node = self.leftmost;
+        while let Some(current) = node {
+            nodes.push(SerializedNode {
+                key: current,
+                contents: self.get_contents(current),
+                color: self.get_color(Some(current)),
+                left: self.get_left(current).map(|left| ranks[left]),
+                right: self.get_right(current).map(|right| ranks[right]),
+            });
+            node = self.get_next(current);
+        }
+        SerializedTree {
+            root: self.root.map(|root| ranks[root]),
+            nodes,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T, A, S: NodeStore<Node> + Default, B: Balance> Deserialize<'de> for Tree<T, A, S, B>
+where
+    T: Clone + fmt::Debug + Deserialize<'de>,
+    A: Augment<T> + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (tree, _) = Tree::deserialize_with_key_map(deserializer)?;
+        Ok(tree)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T> + Default, S: NodeStore<Node> + Default, B: Balance>
+    Tree<T, A, S, B>
+{
+    /// Deserializes a tree with exactly the structure it had when it was serialized. The nodes
+    /// are given new NodeKeys, returns the tree along with a map from the NodeKeys the nodes had
+    /// when serialized to their new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `deserializer` - The deserializer to read the tree from
+    ///
+    pub fn deserialize_with_key_map<'de, D>(
+        deserializer: D,
+    ) -> Result<(Self, SecondaryMap<NodeKey, NodeKey>), D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let serialized = SerializedTree::<T>::deserialize(deserializer)?;
+        let mut old_keys = Vec::with_capacity(serialized.nodes.len());
+        let parts = serialized
+            .nodes
+            .into_iter()
+            .map(|node| {
+                old_keys.push(node.key);
+                NodeParts {
+                    contents: node.contents,
+                    color: node.color,
+                    left: node.left,
+                    right: node.right,
+                }
+            })
+            .collect();
+        let (tree, keys) =
+            Tree::from_parts(parts, serialized.root, A::default()).map_err(D::Error::custom)?;
+
+        let mut key_map = SecondaryMap::with_capacity(keys.len());
+        for (old_key, new_key) in old_keys.into_iter().zip(keys) {
+            if key_map.insert(old_key, new_key).is_some() {
+                return Err(D::Error::custom("two nodes have the same key"));
+            }
+        }
+        Ok((tree, key_map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_round_trip_test() {
+        let mut tree: Tree<String> = Tree::new();
+        let mut nodes = vec![tree.create_root("0".to_string())];
+        for i in 1..50 {
+            nodes.push(tree.insert_before(nodes[i / 2], i.to_string()));
+        }
+        let json = serde_json::to_string(&tree).unwrap();
+
+        let (copy, key_map) = Tree::<String>::deserialize_with_key_map(
+            &mut serde_json::Deserializer::from_str(&json),
+        )
+        .unwrap();
+        copy.assert_valid();
+        assert!(copy == tree);
+        assert_eq!(copy.get_level_order(), tree.get_level_order());
+        for &node in nodes.iter() {
+            assert_eq!(copy.get_contents(key_map[node]), tree.get_contents(node));
+        }
+
+        let empty: Tree<u8> =
+            serde_json::from_str(&serde_json::to_string(&Tree::<u8>::new()).unwrap()).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn serde_invalid_structure_test() {
+        let tree: Tree<u8> = (0..3).collect();
+        let json = serde_json::to_string(&tree).unwrap();
+        // Recolouring the root red breaks the red-black properties
+        let recoloured = json.replacen("\"Black\"", "\"Red\"", 1);
+        assert!(serde_json::from_str::<Tree<u8>>(&recoloured).is_err());
+        let reordered = json.replace("\"left\":0", "\"left\":2");
+        assert!(serde_json::from_str::<Tree<u8>>(&reordered).is_err());
+    }
+}
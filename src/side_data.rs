@@ -0,0 +1,225 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use slotmap::SecondaryMap;
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// The id given to the next tree created, so that a side data handle can be matched to its tree
+static NEXT_SIDE_DATA_ID: AtomicU64 = AtomicU64::new(0);
+
+// Returns an id that no other tree has been given
+pub(crate) fn new_side_data_id() -> u64 {
+    NEXT_SIDE_DATA_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A handle to a `SecondaryMap` of side data registered with a tree by `register_side_data`.
+///
+/// The tree removes the entry for a node from every registered map when the node is removed, and
+/// moves the entries along with the nodes when it gives them new NodeKeys in `shrink_to`. Nodes
+/// moved into another tree, for example by `split_off`, leave their entries behind.
+///
+/// A handle is only valid for the tree that returned it and the clones of that tree, using it with
+/// any other tree panics.
+pub struct SideData<V> {
+    // The id of the tree that returned the handle
+    tree: u64,
+    index: usize,
+    marker: PhantomData<fn() -> V>,
+}
+
+impl<V> Clone for SideData<V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for SideData<V> {}
+
+impl<V> fmt::Debug for SideData<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SideData").field(&self.index).finish()
+    }
+}
+
+// A side data map with its value type erased, so that a tree can keep maps of any type
+pub(crate) trait SideMap: Send + Sync {
+    fn remove(&mut self, node: NodeKey);
+    fn clear(&mut self);
+    fn remap(&mut self, key_map: &SecondaryMap<NodeKey, NodeKey>);
+    fn clone_box(&self) -> Box<dyn SideMap>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<V: Clone + Send + Sync + 'static> SideMap for SecondaryMap<NodeKey, V> {
+    fn remove(&mut self, node: NodeKey) {
+        SecondaryMap::remove(self, node);
+    }
+
+    fn clear(&mut self) {
+        SecondaryMap::clear(self);
+    }
+
+    fn remap(&mut self, key_map: &SecondaryMap<NodeKey, NodeKey>) {
+        let mut remapped = SecondaryMap::with_capacity(self.len());
+        for (old_key, value) in self.drain() {
+            if let Some(&new_key) = key_map.get(old_key) {
+                remapped.insert(new_key, value);
+            }
+        }
+        *self = remapped;
+    }
+
+    fn clone_box(&self) -> Box<dyn SideMap> {
+        let mut copy = SecondaryMap::with_capacity(self.capacity());
+        for (node, value) in self.iter() {
+            copy.insert(node, value.clone());
+        }
+        Box::new(copy)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Registers a new empty `SecondaryMap` with the tree and returns a handle to it. The tree
+    /// keeps the map in step with its nodes, so data stored against a node is dropped as soon as
+    /// the node is removed instead of lingering under a stale NodeKey.
+    pub fn register_side_data<V: Clone + Send + Sync + 'static>(&mut self) -> SideData<V> {
+        let map: SecondaryMap<NodeKey, V> = SecondaryMap::new();
+        self.side_data.push(Box::new(map));
+        SideData {
+            tree: self.side_data_id,
+            index: self.side_data.len() - 1,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a reference to a side data map registered with the tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handle was returned by a tree other than this one or one it was cloned from.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - The handle returned when the map was registered
+    ///
+    pub fn side_data<V: 'static>(&self, handle: SideData<V>) -> &SecondaryMap<NodeKey, V> {
+        self.check_side_data_handle(handle);
+        self.side_data
+            .get(handle.index)
+            .and_then(|map| map.as_any().downcast_ref())
+            .expect("side data handle belongs to a different tree")
+    }
+
+    /// Returns a mutable reference to a side data map registered with the tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handle was returned by a tree other than this one or one it was cloned from.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - The handle returned when the map was registered
+    ///
+    pub fn side_data_mut<V: 'static>(
+        &mut self,
+        handle: SideData<V>,
+    ) -> &mut SecondaryMap<NodeKey, V> {
+        self.check_side_data_handle(handle);
+        self.side_data
+            .get_mut(handle.index)
+            .and_then(|map| map.as_any_mut().downcast_mut())
+            .expect("side data handle belongs to a different tree")
+    }
+
+    fn check_side_data_handle<V>(&self, handle: SideData<V>) {
+        assert_eq!(
+            handle.tree, self.side_data_id,
+            "side data handle belongs to a different tree"
+        );
+    }
+
+    pub(crate) fn remove_side_data(&mut self, node: NodeKey) {
+        for map in self.side_data.iter_mut() {
+            map.remove(node);
+        }
+    }
+
+    pub(crate) fn clear_side_data(&mut self) {
+        for map in self.side_data.iter_mut() {
+            map.clear();
+        }
+    }
+
+    pub(crate) fn remap_side_data(&mut self, key_map: &SecondaryMap<NodeKey, NodeKey>) {
+        for map in self.side_data.iter_mut() {
+            map.remap(key_map);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn side_data_cleanup_test() {
+        let mut tree: Tree<u32> = (0..20).collect();
+        let labels = tree.register_side_data::<String>();
+        let weights = tree.register_side_data::<f64>();
+        let mut node = tree.get_leftmost_node();
+        while let Some(current) = node {
+            let contents = *tree.get_contents(current);
+            tree.side_data_mut(labels)
+                .insert(current, contents.to_string());
+            tree.side_data_mut(weights)
+                .insert(current, f64::from(contents));
+            node = tree.get_next(current);
+        }
+
+        let first = tree.get_leftmost_node().unwrap();
+        tree.delete_node(first);
+        assert!(!tree.side_data(labels).contains_key(first));
+        tree.retain(|value| value % 2 == 0);
+        assert_eq!(tree.side_data(labels).len(), 9);
+        assert_eq!(tree.side_data(weights).len(), 9);
+
+        let second = tree.get_leftmost_node().unwrap();
+        let copy = tree.clone();
+        assert_eq!(copy.side_data(labels)[second], "2");
+
+        let key_map = tree.shrink_to_fit();
+        assert_eq!(tree.side_data(labels)[key_map[second]], "2");
+        assert_eq!(tree.side_data(labels).len(), 9);
+
+        // The nodes left behind by a split keep their entries, the moved ones leave theirs
+        let third = tree.nth(1).unwrap();
+        let (other, _) = tree.split_off_at(2);
+        assert_eq!(other.len(), 7);
+        assert_eq!(tree.side_data(labels)[third], "4");
+        assert_eq!(tree.side_data(labels).len(), 2);
+
+        tree.clear();
+        assert!(tree.side_data(weights).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "side data handle belongs to a different tree")]
+    fn side_data_other_tree_test() {
+        let mut tree: Tree<u32> = (0..5).collect();
+        let mut other: Tree<u32> = (0..5).collect();
+        tree.register_side_data::<String>();
+        let handle = other.register_side_data::<String>();
+        // The same index and type in a different tree is not the same map
+        tree.side_data(handle);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use slotmap::SecondaryMap;
+use std::fmt;
+
+// Every snapshot starts with these bytes so that other data is rejected early
+const MAGIC: &[u8; 4] = b"BSTS";
+// The version of the snapshot format, bumped whenever the serialized form of a tree changes
+const VERSION: u8 = 1;
+
+impl<T: Clone + fmt::Debug + Serialize, A: Augment<T>, S: NodeStore<Node>, B: Balance>
+    Tree<T, A, S, B>
+{
+    /// Returns a compact binary snapshot of the tree, holding its exact structure and contents.
+    /// The augmented values are not stored, they are recomputed when the snapshot is loaded.
+    pub fn to_bytes(&self) -> bincode::Result<Vec<u8>> {
+        let options = bincode::DefaultOptions::new();
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        options.serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T> + Default, S: NodeStore<Node> + Default, B: Balance>
+    Tree<T, A, S, B>
+{
+    /// Loads a tree from a snapshot created by `to_bytes`. The nodes are given new NodeKeys,
+    /// returns the tree along with a map from the NodeKeys the nodes had when the snapshot was
+    /// taken to their new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The snapshot to load
+    ///
+    pub fn from_bytes<'de>(
+        bytes: &'de [u8],
+    ) -> bincode::Result<(Self, SecondaryMap<NodeKey, NodeKey>)>
+    where
+        T: Deserialize<'de>,
+    {
+        let header = MAGIC.len() + 1;
+        if bytes.len() < header || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(Box::new(bincode::ErrorKind::Custom(
+                "the data is not a tree snapshot".to_string(),
+            )));
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(Box::new(bincode::ErrorKind::Custom(format!(
+                "unsupported snapshot version {}",
+                bytes[MAGIC.len()]
+            ))));
+        }
+        let options = bincode::DefaultOptions::new();
+        let mut deserializer = bincode::Deserializer::from_slice(&bytes[header..], options);
+        Tree::deserialize_with_key_map(&mut deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_round_trip_test() {
+        let mut tree: Tree<u32> = Tree::new();
+        let mut nodes = vec![tree.create_root(0)];
+        for i in 1..1000 {
+            nodes.push(tree.insert_after(nodes[(i * 7) % i], i as u32));
+        }
+        let bytes = tree.to_bytes().unwrap();
+        // Varint encoding keeps the snapshot well below the in-memory size
+        assert!(bytes.len() < 1000 * 16);
+
+        let (copy, key_map) = Tree::<u32>::from_bytes(&bytes).unwrap();
+        copy.assert_valid();
+        assert!(copy == tree);
+        assert_eq!(copy.get_level_order(), tree.get_level_order());
+        assert_eq!(copy.get_contents(key_map[nodes[500]]), &500);
+    }
+
+    #[test]
+    fn snapshot_rejects_invalid_data_test() {
+        let tree: Tree<u32> = (0..10).collect();
+        let mut bytes = tree.to_bytes().unwrap();
+        assert!(Tree::<u32>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Tree::<u32>::from_bytes(b"not a snapshot").is_err());
+        bytes[4] = VERSION + 1;
+        assert!(Tree::<u32>::from_bytes(&bytes).is_err());
+    }
+}
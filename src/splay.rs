@@ -0,0 +1,137 @@
+use crate::balance::{Rebalance, Subtree};
+use crate::{Augment, InvariantViolation, Node, NodeKey, NodeStore, Tree};
+use slotmap::SlotMap;
+use std::fmt;
+
+/// Splay balancing. Rather than keeping the tree balanced, every node that is inserted is rotated
+/// up to the root, along with the parent of every node that is deleted, and any other node can be
+/// moved up with `Tree::splay`. Nodes that have been touched recently stay near the root, so
+/// workloads that keep coming back to the same part of the sequence are fast.
+///
+/// Operations take O(log n) amortized time, but a single operation can take time proportional to
+/// the number of nodes, as can the methods that walk down from the root.
+#[derive(PartialEq, Eq, Copy, Clone, Default, Debug)]
+pub struct Splay;
+
+/// A tree that moves the nodes it touches up to the root, see `Splay`
+pub type SplayTree<T, A = ()> = Tree<T, A, SlotMap<NodeKey, Node>, Splay>;
+
+impl Rebalance for Splay {
+    fn inserted<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        tree.splay_to_root(node);
+    }
+
+    fn remove<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        if let Some(parent) = tree.unlink(node) {
+            tree.splay_to_root(parent);
+        }
+    }
+
+    // Splay trees keep no balance, so subtrees are joined whatever their shapes
+    fn rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &Tree<T, A, S, Self>,
+        _node: Option<NodeKey>,
+    ) -> usize {
+        0
+    }
+
+    fn join<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        left: Subtree,
+        pivot: NodeKey,
+        right: Subtree,
+    ) -> (NodeKey, usize) {
+        let left = left.map(|(root, _)| root);
+        let right = right.map(|(root, _)| root);
+        tree.set_left(pivot, left);
+        tree.set_right(pivot, right);
+        for child in left.into_iter().chain(right) {
+            tree.set_parent(child, Some(pivot));
+        }
+        tree.set_size(pivot, tree.get_size(left) + tree.get_size(right) + 1);
+        tree.update_augmented_node(pivot);
+        (pivot, 0)
+    }
+
+    fn check<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &Tree<T, A, S, Self>,
+        _node: NodeKey,
+        _left: Option<usize>,
+        _right: Option<usize>,
+        _violations: &mut Vec<InvariantViolation>,
+    ) -> usize {
+        0
+    }
+
+    fn restore<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &mut Tree<T, A, S, Self>,
+        _node: NodeKey,
+        _left: Option<usize>,
+        _right: Option<usize>,
+    ) -> Result<usize, &'static str> {
+        Ok(0)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>> Tree<T, A, S, Splay> {
+    /// Moves a node up to the root of the tree with splay rotations, which roughly halve the depth
+    /// of every node on the way. Calling this after looking a node up keeps the nodes that are
+    /// used often near the root.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The NodeKey of the node to move to the root
+    ///
+    pub fn splay(&mut self, node: NodeKey) {
+        self.begin_counting();
+        self.splay_to_root(node);
+    }
+
+    fn splay_to_root(&mut self, node: NodeKey) {
+        while let Some(parent) = self.get_parent(node) {
+            self.count_fixup_iteration();
+            if self.get_parent(parent).is_some() {
+                if self.get_node_type(node) == self.get_node_type(parent) {
+                    // Zig-zig, the parent is rotated up first
+                    self.rotate_up(parent);
+                } else {
+                    // Zig-zag
+                    self.rotate_up(node);
+                }
+            }
+            self.rotate_up(node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::tests::{insert_delete_test, split_append_test};
+
+    #[test]
+    fn splay_test() {
+        insert_delete_test::<Splay>(100, |tree, nodes| {
+            // Every new node becomes the root, leaving the earlier nodes in a path down the left
+            assert_eq!(tree.root, nodes.last().copied());
+            assert_eq!(tree.height(), 100);
+
+            // Splaying the deepest node roughly halves the depth of the nodes on the path
+            tree.splay(nodes[0]);
+            assert_eq!(tree.root, Some(nodes[0]));
+            assert!(tree.height() <= 52);
+            tree.assert_valid();
+        });
+    }
+
+    #[test]
+    fn splay_split_append_test() {
+        split_append_test::<Splay>(30, |_| {});
+    }
+}
@@ -0,0 +1,835 @@
+// Splitting a tree and joining trees together. The splits and joins of the structure take
+// O(log n) time, but every tree keeps its nodes in an arena of its own, so the nodes that move
+// from one tree to another are copied into the other tree's arena and given new NodeKeys, which
+// takes time proportional to the number of nodes moved. The nodes are always copied out of the
+// tree giving them up, even when fewer nodes stay behind than move, as copying the nodes that
+// stay would give them new NodeKeys and their old ones could then refer to other nodes. So the
+// nodes that stay in a tree, and the nodes of a tree that nodes are moved into, keep their keys.
+
+use crate::balance::Subtree;
+use crate::{Augment, Balance, Color, Node, NodeKey, NodeStore, NodeType, Tree};
+use slotmap::SecondaryMap;
+use std::fmt;
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Splits the tree in two at the specified node. The node and every node after it are moved
+    /// into a new tree, leaving the nodes before it in this tree.
+    ///
+    /// Takes O(log n + k) time, where k is the number of nodes moved. The moved nodes are given new
+    /// NodeKeys, returns the new tree along with a map from their old NodeKeys to their new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - The first node to move into the new tree
+    ///
+    pub fn split_off(&mut self, at: NodeKey) -> (Tree<T, A, S, B>, SecondaryMap<NodeKey, NodeKey>)
+    where
+        A: Clone,
+    {
+        self.begin_counting();
+        let leftmost = self.leftmost;
+        let rightmost = self.rightmost;
+        // The moved nodes are put back with new keys by a rollback, the new tree keeps its copies
+        self.log_delete_run(at, rightmost.unwrap());
+        let prev = self.get_prev(at);
+        let (left, right) = self.split_subtrees(at);
+
+        // Cut the threading between the two halves
+        self.set_prev(at, None);
+        if let Some(prev) = prev {
+            self.set_next(prev, None);
+        }
+
+        let mut other = Tree::with_store(self.nodes.new_like(0), self.augment.clone());
+        let (right_root, key_map) = self.transplant(right.unwrap().0, &mut other);
+        B::make_root(&mut other, right_root);
+        other.root = Some(right_root);
+        other.len = key_map.len();
+        other.leftmost = Some(key_map[at]);
+        other.rightmost = Some(key_map[rightmost.unwrap()]);
+
+        self.root = left.map(|(root, _)| root);
+        if let Some(root) = self.root {
+            B::make_root(self, root);
+        }
+        self.len -= other.len;
+        self.leftmost = if prev.is_some() { leftmost } else { None };
+        self.rightmost = prev;
+        trace_event!(node = ?at, count = other.len, "split off");
+        self.validate_after_mutation();
+        (other, key_map)
+    }
+
+    /// Splits the tree in two at the specified position, see `split_off` for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of nodes in the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position of the first node to move into the new tree
+    ///
+    pub fn split_off_at(
+        &mut self,
+        index: usize,
+    ) -> (Tree<T, A, S, B>, SecondaryMap<NodeKey, NodeKey>)
+    where
+        A: Clone,
+    {
+        assert!(
+            index <= self.len,
+            "split index (is {}) should be <= len (is {})",
+            index,
+            self.len
+        );
+        match self.nth(index) {
+            Some(at) => self.split_off(at),
+            None => (
+                Tree::with_store(self.nodes.new_like(0), self.augment.clone()),
+                SecondaryMap::new(),
+            ),
+        }
+    }
+
+    /// Removes the subtree rooted at the specified node and returns it as a new tree. The nodes of
+    /// the subtree are a contiguous run of the sequence, the nodes on either side of it are joined
+    /// back together and keep their NodeKeys.
+    ///
+    /// Takes O(log n + k) time, where k is the size of the subtree. The moved nodes are given new
+    /// NodeKeys, returns the new tree along with a map from their old NodeKeys to their new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The root of the subtree to detach
+    ///
+    pub fn detach_subtree(
+        &mut self,
+        node: NodeKey,
+    ) -> (Tree<T, A, S, B>, SecondaryMap<NodeKey, NodeKey>)
+    where
+        A: Clone,
+    {
+        self.begin_counting();
+        let (mut first, mut last) = (node, node);
+        while let Some(left) = self.get_left(first) {
+            first = left;
+        }
+        while let Some(right) = self.get_right(last) {
+            last = right;
+        }
+        self.log_delete_run(first, last);
+        let middle = self.cut_run(first, last);
+
+        let mut other = Tree::with_store(self.nodes.new_like(0), self.augment.clone());
+        let (middle_root, key_map) = self.transplant(middle, &mut other);
+        B::make_root(&mut other, middle_root);
+        other.root = Some(middle_root);
+        other.len = key_map.len();
+        other.leftmost = Some(key_map[first]);
+        other.rightmost = Some(key_map[last]);
+        self.len -= other.len;
+        trace_event!(?node, count = other.len, "detach subtree");
+        self.validate_after_mutation();
+        (other, key_map)
+    }
+
+    /// Removes the nodes from `from` to `to` inclusive and returns their contents in order. The
+    /// run of nodes is cut out of the tree with a split on either side of it and the two sides are
+    /// joined back together, so this takes O(log n) time on top of freeing the removed nodes,
+    /// rather than rebalancing after every one. The NodeKeys of the remaining nodes stay valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `to` comes before `from` in the sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The first node to remove
+    /// * `to` - The last node to remove
+    ///
+    pub fn delete_range(&mut self, from: NodeKey, to: NodeKey) -> Vec<T> {
+        self.begin_counting();
+        assert!(
+            self.rank_of(from) <= self.rank_of(to),
+            "the end of the range comes before its start"
+        );
+        self.bump_generation();
+        let mut nodes = vec![from];
+        let mut node = from;
+        while node != to {
+            node = self.get_next(node).unwrap();
+            nodes.push(node);
+        }
+        // Logged last node first, so that a rollback puts the first node back first
+        for &node in nodes.iter().rev() {
+            self.log_delete(node);
+        }
+        self.cut_run(from, to);
+        let mut contents = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            self.notify_delete(node);
+            self.nodes.remove(node);
+            self.augmented.remove(node);
+            self.remove_side_data(node);
+            contents.push(self.node_data.remove(node).unwrap());
+        }
+        self.len -= contents.len();
+        trace_event!(?from, ?to, count = contents.len(), "delete range");
+        self.validate_after_mutation();
+        contents
+    }
+
+    // Cuts the run of nodes from `first` to `last` out of the tree, joining the nodes on either
+    // side of it back together. Returns the root of the detached run, whose threading is cut off
+    // at both ends. The length of the tree is left for the caller to update.
+    pub(crate) fn cut_run(&mut self, first: NodeKey, last: NodeKey) -> NodeKey {
+        let leftmost = self.leftmost;
+        let rightmost = self.rightmost;
+        let prev = self.get_prev(first);
+        let next = self.get_next(last);
+
+        // Cut the run out of the sequence with a split on either side of it
+        let (left, rest) = self.split_subtrees(first);
+        let (middle, right) = match next {
+            Some(next) => {
+                self.root = rest.map(|(root, _)| root);
+                self.split_subtrees(next)
+            }
+            None => (rest, None),
+        };
+
+        // The last node before the run is taken out and used as the pivot joining the two sides
+        self.root = match (left, prev) {
+            (Some((left_root, _)), Some(prev)) => {
+                self.root = Some(left_root);
+                let (left, _) = self.split_subtrees(prev);
+                Some(self.join_subtrees(left, prev, right).0)
+            }
+            _ => right.map(|(root, _)| root),
+        };
+        if let Some(root) = self.root {
+            B::make_root(self, root);
+        }
+
+        self.set_prev(first, None);
+        self.set_next(last, None);
+        if let Some(prev) = prev {
+            self.set_next(prev, next);
+        }
+        if let Some(next) = next {
+            self.set_prev(next, prev);
+        }
+        self.leftmost = if prev.is_some() { leftmost } else { next };
+        self.rightmost = if next.is_some() { rightmost } else { prev };
+        middle.unwrap().0
+    }
+
+    /// Moves all the nodes of another tree to the end of this one, leaving the other tree empty.
+    ///
+    /// Takes O(log n + m) time, where m is the number of nodes in `other`. The moved nodes are
+    /// given new NodeKeys, returns a map from their old NodeKeys to their new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The tree whose nodes are appended to this one
+    ///
+    pub fn append(&mut self, other: &mut Tree<T, A, S, B>) -> SecondaryMap<NodeKey, NodeKey> {
+        self.begin_counting();
+        let other_leftmost = match other.leftmost {
+            Some(node) => node,
+            None => return SecondaryMap::new(),
+        };
+        let other_rightmost = other.rightmost.unwrap();
+        let other_root = other.root.unwrap();
+        let other_rank = B::rank(other, Some(other_root));
+        let (other_root, key_map) = other.transplant(other_root, self);
+        other.root = None;
+        other.len = 0;
+        other.leftmost = None;
+        other.rightmost = None;
+
+        let first = key_map[other_leftmost];
+        let last = key_map[other_rightmost];
+        self.len += key_map.len();
+        for (_, &new_key) in key_map.iter() {
+            self.log_insert(new_key);
+        }
+        let pivot = match self.rightmost {
+            Some(pivot) => pivot,
+            None => {
+                self.root = Some(other_root);
+                self.leftmost = Some(first);
+                self.rightmost = Some(last);
+                trace_event!(count = key_map.len(), "append");
+                self.validate_after_mutation();
+                return key_map;
+            }
+        };
+
+        // The last node of this tree is taken out and used as the pivot joining the two
+        let (left, _) = self.split_subtrees(pivot);
+        let (root, _) = self.join_subtrees(left, pivot, Some((other_root, other_rank)));
+        B::make_root(self, root);
+        self.root = Some(root);
+        self.set_next(pivot, Some(first));
+        self.set_prev(first, Some(pivot));
+        self.rightmost = Some(last);
+        trace_event!(count = key_map.len(), "append");
+        self.validate_after_mutation();
+        key_map
+    }
+
+    /// Moves all the nodes of another tree into this one, straight after the specified node in the
+    /// sequence.
+    ///
+    /// Takes O(log n + m) time, where m is the number of nodes in `other`, rather than the
+    /// O(log n + log m) of splicing the structures alone, as the inserted nodes are copied into
+    /// this tree's arena. They are given new NodeKeys, even when `other` is the larger tree, so
+    /// that the NodeKeys of this tree's nodes stay valid. Returns a map from the old NodeKeys of
+    /// the inserted nodes to their new ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node the other tree's nodes are inserted after
+    /// * `other` - The tree whose nodes are inserted
+    ///
+    pub fn insert_tree_after(
+        &mut self,
+        node: NodeKey,
+        mut other: Tree<T, A, S, B>,
+    ) -> SecondaryMap<NodeKey, NodeKey> {
+        self.begin_counting();
+        let (other_leftmost, other_rightmost, other_root) =
+            match (other.leftmost, other.rightmost, other.root) {
+                (Some(leftmost), Some(rightmost), Some(root)) => (leftmost, rightmost, root),
+                _ => return SecondaryMap::new(),
+            };
+        let other_rank = B::rank(&other, Some(other_root));
+
+        let (other_root, key_map) = other.transplant(other_root, self);
+        self.len += key_map.len();
+        self.splice_after(
+            Some(node),
+            (other_root, other_rank),
+            key_map[other_leftmost],
+            key_map[other_rightmost],
+        );
+        for (_, &new_key) in key_map.iter() {
+            self.log_insert(new_key);
+        }
+        trace_event!(?node, count = key_map.len(), "insert tree");
+        self.validate_after_mutation();
+        key_map
+    }
+
+    /// Creates nodes holding the items, in the order they are iterated, and inserts them after
+    /// the specified node. The new nodes are built into a perfectly balanced subtree which is
+    /// spliced into the tree in O(log n) time, rather than rebalancing after every insert.
+    /// Returns the NodeKeys of the new nodes in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node the items are inserted after
+    /// * `iter` - The items to insert, in order
+    ///
+    pub fn insert_all_after<I: IntoIterator<Item = T>>(
+        &mut self,
+        node: NodeKey,
+        iter: I,
+    ) -> Vec<NodeKey> {
+        self.begin_counting();
+        let keys: Vec<NodeKey> = iter
+            .into_iter()
+            .map(|value| {
+                let key = self.nodes.insert(Node::new());
+                self.node_data.insert(key, value);
+                key
+            })
+            .collect();
+        let (first, last) = match (keys.first(), keys.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return keys,
+        };
+        self.bump_generation();
+        for pair in keys.windows(2) {
+            self.set_next(pair[0], Some(pair[1]));
+            self.set_prev(pair[1], Some(pair[0]));
+        }
+        let root = B::link(self, &keys).unwrap();
+        B::make_root(self, root);
+        let rank = B::rank(self, Some(root));
+        self.len += keys.len();
+        self.splice_after(Some(node), (root, rank), first, last);
+        for &key in keys.iter() {
+            self.log_insert(key);
+        }
+        trace_event!(?node, count = keys.len(), "insert all");
+        self.validate_after_mutation();
+        keys
+    }
+
+    // Joins a detached subtree into the tree straight after the specified node, or at the start
+    // of the tree if there is none. `first` and `last` are the ends of the subtree's sequence,
+    // whose threading must already be linked up.
+    pub(crate) fn splice_after(
+        &mut self,
+        node: Option<NodeKey>,
+        subtree: (NodeKey, usize),
+        first: NodeKey,
+        last: NodeKey,
+    ) {
+        // The node itself is the pivot joining the subtree onto the nodes up to it, and the node
+        // after it the pivot joining on the rest
+        let next = match node {
+            Some(node) => self.get_next(node),
+            None => self.leftmost,
+        };
+        let right = match next {
+            Some(next) => {
+                let after = self.get_next(next);
+                let right = after.and_then(|after| self.split_subtrees(after).1);
+                self.split_subtrees(next);
+                right
+            }
+            None => None,
+        };
+        let mut joined = match node {
+            Some(node) => {
+                let (left, _) = self.split_subtrees(node);
+                self.join_subtrees(left, node, Some(subtree))
+            }
+            None => subtree,
+        };
+        if let Some(next) = next {
+            joined = self.join_subtrees(Some(joined), next, right);
+        }
+        let (root, _) = joined;
+        B::make_root(self, root);
+        self.root = Some(root);
+
+        match node {
+            Some(node) => {
+                self.set_next(node, Some(first));
+                self.set_prev(first, Some(node));
+            }
+            None => self.leftmost = Some(first),
+        }
+        match next {
+            Some(next) => {
+                self.set_next(last, Some(next));
+                self.set_prev(next, Some(last));
+            }
+            None => self.rightmost = Some(last),
+        }
+    }
+
+    // Returns the black height of a subtree
+    pub(crate) fn subtree_black_height(&self, mut node: Option<NodeKey>) -> usize {
+        let mut height = 0;
+        while let Some(current) = node {
+            if self.get_color(Some(current)) == Color::Black {
+                height += 1;
+            }
+            node = self.get_left(current);
+        }
+        height
+    }
+
+    // Detaches a child subtree from its parent
+    fn detach(&mut self, node: Option<NodeKey>, rank: usize) -> Subtree {
+        let node = node?;
+        self.set_parent(node, None);
+        Some((node, rank))
+    }
+
+    // Clears the links of a node that is about to be used as the pivot of a join
+    fn reset_pivot(&mut self, node: NodeKey) {
+        self.set_parent(node, None);
+        self.set_left(node, None);
+        self.set_right(node, None);
+    }
+
+    // Splits the whole tree into a subtree holding the nodes before `node` and a subtree holding
+    // `node` and the nodes after it. The threading is left untouched and the root is cleared.
+    pub(crate) fn split_subtrees(&mut self, node: NodeKey) -> (Subtree, Subtree) {
+        // The rank of the subtree rooted at the current node of the walk
+        let mut rank = B::rank(self, Some(node));
+        let (left_child, right_child) = (self.get_left(node), self.get_right(node));
+        let left_rank = B::child_rank(self, node, rank, left_child);
+        let right_rank = B::child_rank(self, node, rank, right_child);
+        let mut left = self.detach(left_child, left_rank);
+        let mut right = self.detach(right_child, right_rank);
+        let mut is_right_child = self.get_node_type(node) == NodeType::RightChild;
+        let mut parent = self.get_parent(node);
+        self.reset_pivot(node);
+
+        // Walk up the tree, every ancestor and the subtree on the far side of it are joined onto
+        // whichever half they belong to
+        while let Some(current) = parent {
+            let current_rank = B::parent_rank(self, current, rank);
+            let next_is_right_child = self.get_node_type(current) == NodeType::RightChild;
+            parent = self.get_parent(current);
+            if is_right_child {
+                let sibling = self.get_left(current);
+                let sibling_rank = B::child_rank(self, current, current_rank, sibling);
+                let sibling = self.detach(sibling, sibling_rank);
+                self.reset_pivot(current);
+                left = Some(self.join_subtrees(sibling, current, left));
+            } else {
+                let sibling = self.get_right(current);
+                let sibling_rank = B::child_rank(self, current, current_rank, sibling);
+                let sibling = self.detach(sibling, sibling_rank);
+                self.reset_pivot(current);
+                right = Some(self.join_subtrees(right, current, sibling));
+            }
+            rank = current_rank;
+            is_right_child = next_is_right_child;
+        }
+
+        // Finally the split node becomes the first node of the right half
+        right = Some(self.join_subtrees(None, node, right));
+        self.root = None;
+        (left, right)
+    }
+
+    // Joins two detached subtrees and a detached pivot node that sits between them in the sequence
+    // into a single subtree, balanced by the tree's balancing scheme. Only the tree structure is
+    // updated, the threading is left untouched.
+    pub(crate) fn join_subtrees(
+        &mut self,
+        left: Subtree,
+        pivot: NodeKey,
+        right: Subtree,
+    ) -> (NodeKey, usize) {
+        B::join(self, left, pivot, right)
+    }
+
+    // Moves the nodes of a detached subtree into another tree's arena. Links to nodes outside of
+    // the subtree are cleared. Returns the new key of the subtree root along with a map from the
+    // old keys to the new ones.
+    pub(crate) fn transplant(
+        &mut self,
+        root: NodeKey,
+        other: &mut Tree<T, A, S, B>,
+    ) -> (NodeKey, SecondaryMap<NodeKey, NodeKey>) {
+        // The subtree is a contiguous run of the sequence starting from its leftmost node
+        let count = self.get_size(Some(root));
+        let mut first = root;
+        while let Some(left) = self.get_left(first) {
+            first = left;
+        }
+
+        self.bump_generation();
+        other.bump_generation();
+        let mut key_map = SecondaryMap::with_capacity(count);
+        let mut node = Some(first);
+        for _ in 0..count {
+            let old_key = node.unwrap();
+            let new_key = other.nodes.insert(*self.nodes.get(old_key).unwrap());
+            key_map.insert(old_key, new_key);
+            node = self.get_next(old_key);
+        }
+
+        for (old_key, &new_key) in key_map.iter() {
+            let remap = |key: Option<NodeKey>| key.and_then(|key| key_map.get(key).copied());
+            let node = other.nodes.get_mut(new_key).unwrap();
+            node.parent = remap(node.parent);
+            node.left = remap(node.left);
+            node.right = remap(node.right);
+            #[cfg(feature = "threading")]
+            {
+                node.prev = remap(node.prev);
+                node.next = remap(node.next);
+            }
+            self.nodes.remove(old_key);
+            self.remove_side_data(old_key);
+            if let Some(contents) = self.node_data.remove(old_key) {
+                other.node_data.insert(new_key, contents);
+            }
+            if let Some(value) = self.augmented.remove(old_key) {
+                other.augmented.insert(new_key, value);
+            }
+        }
+        (key_map[root], key_map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents<A: Augment<usize>>(tree: &Tree<usize, A>) -> Vec<usize> {
+        let mut values = vec![];
+        let mut node = tree.get_leftmost_node();
+        while let Some(current) = node {
+            values.push(*tree.get_contents(current));
+            node = tree.get_next(current);
+        }
+        values
+    }
+
+    // A tree holding the values up to its size, the keys of its nodes in order and a model of
+    // the sequence it should hold
+    struct Fixture {
+        tree: Tree<usize>,
+        nodes: Vec<NodeKey>,
+        model: Vec<usize>,
+    }
+
+    // Runs a test on a new fixture for every case at every position of trees of every size below
+    // `max_size`, then checks the tree is valid, holds the sequence of the model and that the
+    // nodes left in it kept their keys. `cases` gives the cases to run at a position.
+    fn for_each_case(
+        max_size: usize,
+        cases: impl Fn(usize, usize) -> std::ops::Range<usize>,
+        mut test: impl FnMut(&mut Fixture, usize, usize),
+    ) {
+        for size in 1..max_size {
+            for at in 0..size {
+                for case in cases(size, at) {
+                    let tree: Tree<usize> = (0..size).collect();
+                    let nodes = tree.iter_with_keys().map(|(node, _)| node).collect();
+                    let model = (0..size).collect();
+                    let mut fixture = Fixture { tree, nodes, model };
+                    test(&mut fixture, at, case);
+
+                    let Fixture { tree, nodes, model } = fixture;
+                    tree.assert_valid();
+                    assert_eq!(contents(&tree), model);
+                    for &value in model.iter().filter(|&&value| value < size) {
+                        assert_eq!(tree.get_contents(nodes[value]), &value);
+                    }
+                }
+            }
+        }
+    }
+
+    // Runs a test once at every position, see for_each_case
+    fn for_each_position(max_size: usize, mut test: impl FnMut(&mut Fixture, usize)) {
+        for_each_case(max_size, |_, _| 0..1, |fixture, at, _| test(fixture, at));
+    }
+
+    #[test]
+    fn split_off_test() {
+        for size in 1..40 {
+            for at in 0..size {
+                let mut tree: Tree<usize> = Tree::new();
+                let mut nodes = vec![tree.create_root(0)];
+                for i in 1..size {
+                    // Mix the insertion order up to get a variety of shapes
+                    let node = if i % 3 == 0 {
+                        tree.insert_before(nodes[i / 2], i)
+                    } else {
+                        tree.insert_after(nodes[i - 1], i)
+                    };
+                    nodes.push(node);
+                }
+                let expected = contents(&tree);
+                let ordered: Vec<NodeKey> = tree.iter_with_keys().map(|(node, _)| node).collect();
+
+                let (other, key_map) = tree.split_off(ordered[at]);
+                tree.assert_valid();
+                other.assert_valid();
+                assert_eq!(tree.len(), at);
+                assert_eq!(other.len(), size - at);
+                assert_eq!(contents(&tree), expected[..at].to_vec());
+                assert_eq!(contents(&other), expected[at..].to_vec());
+                assert_eq!(other.get_leftmost_node(), Some(key_map[ordered[at]]));
+                // The nodes left behind keep their keys, only the moved ones are given new keys
+                assert_eq!(key_map.len(), size - at);
+                for (i, &node) in ordered.iter().enumerate() {
+                    if i < at {
+                        assert!(!key_map.contains_key(node));
+                        assert_eq!(tree.get_contents(node), &expected[i]);
+                    } else {
+                        assert!(!tree.contains_node(node));
+                        assert_eq!(other.get_contents(key_map[node]), &expected[i]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn split_off_keeps_keys_test() {
+        let mut tree: Tree<i32> = (0..20).collect();
+        let first = tree.get_leftmost_node().unwrap();
+        tree.insert_before(first, -1);
+        let nodes: Vec<(NodeKey, i32)> = tree.iter_with_keys().map(|(n, &v)| (n, v)).collect();
+
+        let (other, _) = tree.split_off(tree.nth(3).unwrap());
+        assert_eq!(other.len(), 18);
+        for &(node, value) in &nodes[..3] {
+            assert!(tree.contains_node(node));
+            assert_eq!(tree.get_contents(node), &value);
+        }
+        for &(node, _) in &nodes[3..] {
+            assert!(!tree.contains_node(node));
+        }
+    }
+
+    #[test]
+    fn split_off_at_test() {
+        let mut tree: Tree<_, _> = Tree::with_augment(crate::Aggregate::new(
+            |x: &usize| *x,
+            |a: &usize, b: &usize| a + b,
+        ));
+        let mut node = tree.create_root(0);
+        for i in 1..100 {
+            node = tree.insert_after(node, i);
+        }
+        let (mut other, _) = tree.split_off_at(60);
+        assert_eq!(tree.aggregate_all(), Some((0..60).sum()));
+        assert_eq!(other.aggregate_all(), Some((60..100).sum()));
+
+        let (empty, key_map) = other.split_off_at(40);
+        assert!(empty.is_empty());
+        assert!(key_map.is_empty());
+        let (all, _) = other.split_off_at(0);
+        assert!(other.is_empty());
+        assert_eq!(all.len(), 40);
+        other.assert_valid();
+        all.assert_valid();
+    }
+
+    #[test]
+    fn detach_subtree_test() {
+        for_each_position(40, |fixture, index| {
+            let tree = &mut fixture.tree;
+            let node = fixture.nodes[index];
+            let first = tree.rank_of(tree.iter_subtree(node).next().unwrap());
+            let last = first + tree.get_size(Some(node));
+
+            let (other, key_map) = tree.detach_subtree(node);
+            other.assert_valid();
+            assert!(other.contains_node(key_map[node]));
+            let detached: Vec<usize> = fixture.model.drain(first..last).collect();
+            assert_eq!(contents(&other), detached);
+        });
+
+        let mut tree: crate::AvlTree<usize> = (0..100).collect();
+        let left = tree.get_left(tree.root.unwrap()).unwrap();
+        let (other, _) = tree.detach_subtree(left);
+        tree.assert_valid();
+        other.assert_valid();
+        assert_eq!(tree.len() + other.len(), 100);
+    }
+
+    #[test]
+    fn insert_tree_after_test() {
+        for size in 1..25 {
+            for other_size in 0..40 {
+                for at in 0..size {
+                    let mut tree: Tree<usize> = (0..size).collect();
+                    let nodes: Vec<NodeKey> = (0..size).map(|i| tree.nth(i).unwrap()).collect();
+                    let other: Tree<usize> = (100..100 + other_size).collect();
+                    let other_nodes: Vec<NodeKey> =
+                        (0..other_size).map(|i| other.nth(i).unwrap()).collect();
+
+                    let key_map = tree.insert_tree_after(nodes[at], other);
+                    tree.assert_valid();
+                    let mut expected: Vec<usize> = (0..=at).collect();
+                    expected.extend(100..100 + other_size);
+                    expected.extend(at + 1..size);
+                    assert_eq!(contents(&tree), expected);
+                    // Only the inserted nodes are given new keys, however large the other tree is
+                    assert_eq!(key_map.len(), other_size);
+                    for (i, &node) in nodes.iter().enumerate() {
+                        assert_eq!(tree.get_contents(node), &i);
+                    }
+                    for (i, &node) in other_nodes.iter().enumerate() {
+                        assert_eq!(tree.get_contents(key_map[node]), &(100 + i));
+                    }
+                }
+            }
+        }
+
+        let mut tree: crate::TreapTree<usize> = (0..50).collect();
+        let other: crate::TreapTree<usize> = (50..80).collect();
+        tree.insert_tree_after(tree.nth(20).unwrap(), other);
+        tree.assert_valid();
+        assert_eq!(tree.len(), 80);
+    }
+
+    #[test]
+    fn insert_all_after_test() {
+        for_each_case(
+            25,
+            |_, _| 0..20,
+            |fixture, at, count| {
+                let keys = fixture
+                    .tree
+                    .insert_all_after(fixture.nodes[at], 100..100 + count);
+                fixture.model.splice(at + 1..at + 1, 100..100 + count);
+                for (i, &key) in keys.iter().enumerate() {
+                    assert_eq!(fixture.tree.rank_of(key), at + 1 + i);
+                }
+            },
+        );
+
+        let mut tree: crate::AvlTree<usize> = (0..50).collect();
+        let last = tree.get_rightmost_node().unwrap();
+        tree.insert_all_after(last, 50..500);
+        tree.assert_valid();
+        assert_eq!(tree.to_vec(), (0..500).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn delete_range_test() {
+        for_each_case(
+            30,
+            |size, from| from..size,
+            |fixture, from, to| {
+                let (from_node, to_node) = (fixture.nodes[from], fixture.nodes[to]);
+                let removed = fixture.tree.delete_range(from_node, to_node);
+                assert_eq!(removed, (from..=to).collect::<Vec<_>>());
+                fixture.model.drain(from..=to);
+                assert!(!fixture.tree.contains_node(from_node));
+            },
+        );
+    }
+
+    #[test]
+    fn append_test() {
+        for left_size in 0..30 {
+            for right_size in 0..30 {
+                let mut tree: Tree<usize> = Tree::new();
+                let mut other: Tree<usize> = Tree::new();
+                let mut node = None;
+                for i in 0..left_size {
+                    node = Some(match node {
+                        Some(node) => tree.insert_after(node, i),
+                        None => tree.create_root(i),
+                    });
+                }
+                let mut other_nodes = vec![];
+                for i in left_size..left_size + right_size {
+                    other_nodes.push(match other_nodes.first() {
+                        Some(&first) if i % 2 == 0 => other.insert_before(first, i),
+                        _ => match other_nodes.last() {
+                            Some(&last) => other.insert_after(last, i),
+                            None => other.create_root(i),
+                        },
+                    });
+                }
+                let moved: Vec<(NodeKey, usize)> = other_nodes
+                    .iter()
+                    .map(|&node| (node, *other.get_contents(node)))
+                    .collect();
+                let mut expected = contents(&tree);
+                expected.extend(contents(&other));
+
+                let key_map = tree.append(&mut other);
+                tree.assert_valid();
+                other.assert_valid();
+                assert!(other.is_empty());
+                assert_eq!(tree.len(), left_size + right_size);
+                assert_eq!(contents(&tree), expected);
+                for (node, value) in moved {
+                    assert_eq!(tree.get_contents(key_map[node]), &value);
+                }
+            }
+        }
+    }
+}
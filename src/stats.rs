@@ -0,0 +1,79 @@
+use crate::{Augment, Balance, Color, Node, NodeStore, Tree};
+use std::fmt;
+
+/// A summary of the shape of a tree, returned by `Tree::stats`
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct TreeStats {
+    /// The number of nodes in the tree
+    pub len: usize,
+    /// The number of nodes on the longest path from the root down to a leaf
+    pub height: usize,
+    /// The number of black nodes on every path from the root down to a leaf
+    pub black_height: usize,
+    /// The number of red nodes
+    pub red_nodes: usize,
+    /// The number of black nodes
+    pub black_nodes: usize,
+    /// The number of nodes the arena can hold before it has to grow
+    pub capacity: usize,
+}
+
+impl TreeStats {
+    /// Returns the fraction of the arena's capacity in use, 0 if nothing has been allocated
+    pub fn occupancy(&self) -> f64 {
+        if self.capacity == 0 {
+            0.0
+        } else {
+            self.len as f64 / self.capacity as f64
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Returns a summary of the size, balance and arena usage of the tree in a single call, for
+    /// logging or capacity planning. Every node is visited.
+    pub fn stats(&self) -> TreeStats {
+        let mut red_nodes = 0;
+        let mut node = self.leftmost;
+        while let Some(current) = node {
+            if self.get_color(Some(current)) == Color::Red {
+                red_nodes += 1;
+            }
+            node = self.get_next(current);
+        }
+        TreeStats {
+            len: self.len,
+            height: self.height(),
+            black_height: self.black_height(),
+            red_nodes,
+            black_nodes: self.len - red_nodes,
+            capacity: self.capacity(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_test() {
+        let mut tree: Tree<u32> = Tree::with_capacity(64);
+        let empty = tree.stats();
+        assert_eq!((empty.len, empty.height, empty.red_nodes), (0, 0, 0));
+        assert_eq!(empty.occupancy(), 0.0);
+
+        let mut last = tree.create_root(0);
+        for i in 1..40 {
+            last = tree.insert_after(last, i);
+        }
+        let stats = tree.stats();
+        assert_eq!(stats.len, 40);
+        assert_eq!(stats.red_nodes + stats.black_nodes, 40);
+        assert!(stats.red_nodes > 0);
+        assert_eq!(stats.height, tree.height());
+        assert_eq!(stats.black_height, tree.black_height());
+        assert_eq!(stats.capacity, tree.capacity());
+        assert!(stats.occupancy() > 0.0 && stats.occupancy() <= 1.0);
+    }
+}
@@ -0,0 +1,426 @@
+use crate::NodeKey;
+use allocator_api2::alloc::{Allocator, Global};
+use allocator_api2::vec::Vec;
+use slotmap::{HopSlotMap, KeyData, SlotMap, Slottable};
+
+/// The arena a tree keeps its nodes in. The tree is generic over its store so that the balancing
+/// code can be shared between different arenas, a `SlotMap` is used by default.
+///
+/// A store hands out a NodeKey for every value inserted, and must never return a value for a key
+/// once it has been removed, even if the space it used has since been reused. The contents of the
+/// nodes are kept in `SecondaryMap`s indexed by the same keys, so the keys should be dense.
+pub trait NodeStore<V> {
+    /// Create a new empty store with space for at least `capacity` values, that allocates its
+    /// memory in the same way as this one
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of values to allocate space for
+    ///
+    fn new_like(&self, capacity: usize) -> Self;
+
+    /// Inserts a value into the store and returns the key it can be accessed with
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to insert
+    ///
+    fn insert(&mut self, value: V) -> NodeKey;
+
+    /// Removes and returns the value for the key, or None if the key is not in the store
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the value to remove
+    ///
+    fn remove(&mut self, key: NodeKey) -> Option<V>;
+
+    /// Returns a reference to the value for the key, or None if the key is not in the store
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the value
+    ///
+    fn get(&self, key: NodeKey) -> Option<&V>;
+
+    /// Returns a mutable reference to the value for the key, or None if the key is not in the
+    /// store
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the value
+    ///
+    fn get_mut(&mut self, key: NodeKey) -> Option<&mut V>;
+
+    /// Returns true if the store contains a value for the key
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to look up
+    ///
+    fn contains_key(&self, key: NodeKey) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the number of values in the store
+    fn len(&self) -> usize;
+
+    /// Returns true if the store contains no values
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of values the store can hold without reallocating
+    fn capacity(&self) -> usize;
+
+    /// Reserves space for at least `additional` more values to be inserted without reallocating
+    ///
+    /// # Arguments
+    ///
+    /// * `additional` - The number of extra values to allocate space for
+    ///
+    fn reserve(&mut self, additional: usize);
+
+    /// Removes every value from the store, keeping the allocated memory
+    fn clear(&mut self);
+}
+
+impl<V: Slottable> NodeStore<V> for SlotMap<NodeKey, V> {
+    fn new_like(&self, capacity: usize) -> Self {
+        SlotMap::with_capacity_and_key(capacity)
+    }
+
+    fn insert(&mut self, value: V) -> NodeKey {
+        SlotMap::insert(self, value)
+    }
+
+    fn remove(&mut self, key: NodeKey) -> Option<V> {
+        SlotMap::remove(self, key)
+    }
+
+    fn get(&self, key: NodeKey) -> Option<&V> {
+        SlotMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: NodeKey) -> Option<&mut V> {
+        SlotMap::get_mut(self, key)
+    }
+
+    fn contains_key(&self, key: NodeKey) -> bool {
+        SlotMap::contains_key(self, key)
+    }
+
+    fn len(&self) -> usize {
+        SlotMap::len(self)
+    }
+
+    fn capacity(&self) -> usize {
+        SlotMap::capacity(self)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        SlotMap::reserve(self, additional)
+    }
+
+    fn clear(&mut self) {
+        SlotMap::clear(self)
+    }
+}
+
+impl<V: Slottable> NodeStore<V> for HopSlotMap<NodeKey, V> {
+    fn new_like(&self, capacity: usize) -> Self {
+        HopSlotMap::with_capacity_and_key(capacity)
+    }
+
+    fn insert(&mut self, value: V) -> NodeKey {
+        HopSlotMap::insert(self, value)
+    }
+
+    fn remove(&mut self, key: NodeKey) -> Option<V> {
+        HopSlotMap::remove(self, key)
+    }
+
+    fn get(&self, key: NodeKey) -> Option<&V> {
+        HopSlotMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: NodeKey) -> Option<&mut V> {
+        HopSlotMap::get_mut(self, key)
+    }
+
+    fn contains_key(&self, key: NodeKey) -> bool {
+        HopSlotMap::contains_key(self, key)
+    }
+
+    fn len(&self) -> usize {
+        HopSlotMap::len(self)
+    }
+
+    fn capacity(&self) -> usize {
+        HopSlotMap::capacity(self)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        HopSlotMap::reserve(self, additional)
+    }
+
+    fn clear(&mut self) {
+        HopSlotMap::clear(self)
+    }
+}
+
+/// A simple store that keeps its values in a `Vec`, reusing the slots of removed values through
+/// a free list. Every slot has a version that is bumped when its value is removed, so that the
+/// keys of removed values are never valid again. A slot whose version has run out is retired
+/// rather than wrapping back round to versions that have already been handed out.
+///
+/// The memory is allocated with the allocator `Al`, so the nodes of a tree can be kept in an
+/// arena or bump allocator. Allocators are written against the `allocator-api2` crate, which
+/// mirrors the unstable `allocator_api` of the standard library and uses it directly when the
+/// `nightly` feature is enabled.
+#[derive(Clone, Debug)]
+pub struct VecStore<V, Al: Allocator = Global> {
+    slots: Vec<VecSlot<V>, Al>,
+    free: Vec<u32, Al>,
+    len: usize,
+}
+
+#[derive(Clone, Debug)]
+struct VecSlot<V> {
+    // Odd while the slot is occupied and even while it is free. A slot emptied at the highest
+    // version is retired, it keeps that version and is never put back on the free list.
+    version: u32,
+    value: Option<V>,
+}
+
+impl<V, Al: Allocator + Clone + Default> Default for VecStore<V, Al> {
+    fn default() -> Self {
+        VecStore::new_in(Al::default())
+    }
+}
+
+impl<V, Al: Allocator + Clone> VecStore<V, Al> {
+    /// Create a new empty store that allocates its memory with the given allocator
+    ///
+    /// # Arguments
+    ///
+    /// * `alloc` - The allocator to allocate the slots with
+    ///
+    pub fn new_in(alloc: Al) -> Self {
+        VecStore::with_capacity_in(0, alloc)
+    }
+
+    /// Create a new empty store with space for at least `capacity` values, that allocates its
+    /// memory with the given allocator
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of values to allocate space for
+    /// * `alloc` - The allocator to allocate the slots with
+    ///
+    pub fn with_capacity_in(capacity: usize, alloc: Al) -> Self {
+        VecStore {
+            slots: Vec::with_capacity_in(capacity, alloc.clone()),
+            free: Vec::new_in(alloc),
+            len: 0,
+        }
+    }
+
+    /// Returns a reference to the allocator of the store
+    pub fn allocator(&self) -> &Al {
+        self.slots.allocator()
+    }
+
+    fn slot_index(&self, key: NodeKey) -> Option<usize> {
+        let key_data = KeyData::from(key).as_ffi();
+        let index = (key_data & 0xffff_ffff) as usize;
+        let version = (key_data >> 32) as u32;
+        match self.slots.get(index) {
+            Some(slot) if slot.version == version && slot.value.is_some() => Some(index),
+            _ => None,
+        }
+    }
+}
+
+impl<V, Al: Allocator + Clone> NodeStore<V> for VecStore<V, Al> {
+    fn new_like(&self, capacity: usize) -> Self {
+        VecStore::with_capacity_in(capacity, self.allocator().clone())
+    }
+
+    fn insert(&mut self, value: V) -> NodeKey {
+        let index = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.version += 1;
+                slot.value = Some(value);
+                index
+            }
+            None => {
+                // The largest index is reserved for null keys
+                assert!(self.slots.len() < u32::MAX as usize, "VecStore is full");
+                self.slots.push(VecSlot {
+                    version: 1,
+                    value: Some(value),
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+        self.len += 1;
+        let version = self.slots[index as usize].version;
+        KeyData::from_ffi((u64::from(version) << 32) | u64::from(index)).into()
+    }
+
+    fn remove(&mut self, key: NodeKey) -> Option<V> {
+        let index = self.slot_index(key)?;
+        let slot = &mut self.slots[index];
+        if slot.version < u32::MAX {
+            slot.version += 1;
+            self.free.push(index as u32);
+        }
+        self.len -= 1;
+        slot.value.take()
+    }
+
+    fn get(&self, key: NodeKey) -> Option<&V> {
+        let index = self.slot_index(key)?;
+        self.slots[index].value.as_ref()
+    }
+
+    fn get_mut(&mut self, key: NodeKey) -> Option<&mut V> {
+        let index = self.slot_index(key)?;
+        self.slots[index].value.as_mut()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        let needed = additional.saturating_sub(self.free.len());
+        self.slots.reserve(needed);
+    }
+
+    fn clear(&mut self) {
+        self.free.clear();
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.version == u32::MAX {
+                slot.value = None;
+                continue;
+            }
+            if slot.value.take().is_some() {
+                slot.version += 1;
+            }
+            self.free.push(index as u32);
+        }
+        // Reuse the lowest slots first
+        self.free.reverse();
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Node, Tree};
+    use allocator_api2::alloc::{AllocError, Layout};
+    use std::cell::Cell;
+    use std::ptr::NonNull;
+    use std::rc::Rc;
+
+    // Forwards to the global allocator, counting the bytes that are currently allocated
+    #[derive(Clone, Default)]
+    struct CountingAlloc(Rc<Cell<usize>>);
+
+    unsafe impl Allocator for CountingAlloc {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.set(self.0.get() + layout.size());
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.0.set(self.0.get() - layout.size());
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    fn exercise<S: NodeStore<Node> + Clone + Default>() {
+        let mut tree: Tree<u32, (), S> = Tree::with_augment(());
+        let mut nodes = vec![tree.create_root(0)];
+        for i in 1..300 {
+            nodes.push(tree.insert_after(nodes[(i * 7) % i], i as u32));
+        }
+        for &node in nodes.iter().step_by(3) {
+            tree.delete_node(node);
+        }
+        tree.assert_valid();
+        assert!(!tree.contains_node(nodes[0]));
+        for i in 0..100 {
+            nodes.push(tree.insert_before(nodes[1], 300 + i));
+        }
+        tree.assert_valid();
+        assert_eq!(tree.len(), 300);
+        let (copy, key_map) = tree.clone_with_key_map();
+        copy.assert_valid();
+        assert_eq!(copy.get_contents(key_map[nodes[1]]), &1);
+        tree.clear();
+        assert!(tree.is_empty());
+        assert!(!tree.contains_node(nodes[1]));
+    }
+
+    #[test]
+    fn node_stores_test() {
+        exercise::<SlotMap<NodeKey, Node>>();
+        exercise::<HopSlotMap<NodeKey, Node>>();
+        exercise::<VecStore<Node>>();
+    }
+
+    #[test]
+    fn vec_store_stale_keys_test() {
+        let mut store: VecStore<i32> = VecStore::default();
+        let first = store.insert(1);
+        assert_eq!(store.remove(first), Some(1));
+        let second = store.insert(2);
+        assert_ne!(first, second);
+        assert_eq!(store.get(first), None);
+        assert_eq!(store.get(second), Some(&2));
+        assert_eq!(store.remove(first), None);
+        assert_eq!(store.len(), 1);
+
+        // A slot whose version has run out is retired instead of wrapping round to old keys
+        let key = |index: u64, version: u32| -> NodeKey {
+            KeyData::from_ffi((u64::from(version) << 32) | index).into()
+        };
+        store.slots[0].version = u32::MAX;
+        assert_eq!(store.remove(key(0, u32::MAX)), Some(2));
+        let third = store.insert(3);
+        assert_ne!(third, key(0, 1));
+        assert_eq!(store.get(key(0, 1)), None);
+        assert_eq!(store.get(third), Some(&3));
+        store.clear();
+        for value in 0..3 {
+            let node = store.insert(value);
+            assert_ne!(KeyData::from(node).as_ffi() & 0xffff_ffff, 0);
+        }
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn vec_store_allocator_test() {
+        let alloc = CountingAlloc::default();
+        let mut tree: Tree<u32, (), VecStore<Node, CountingAlloc>> =
+            Tree::with_store(VecStore::new_in(alloc.clone()), ());
+        tree.extend(0..100);
+        assert!(alloc.0.get() >= 100 * std::mem::size_of::<Node>());
+        let (other, _) = tree.split_off_at(50);
+        other.assert_valid();
+        tree.shrink_to_fit();
+        tree.assert_valid();
+        drop(other);
+        drop(tree);
+        assert_eq!(alloc.0.get(), 0);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{Augment, Balance, Node, NodeStore, Tree};
+use proptest::arbitrary::{any, any_with, Arbitrary};
+use proptest::collection::{vec, SizeRange};
+use proptest::prop_oneof;
+use proptest::sample::Index;
+use proptest::strategy::{BoxedStrategy, Just, Strategy};
+use std::fmt;
+
+/// A single mutation of a tree, generated by `tree_ops`. Positions are given as proptest
+/// `Index`es so that they always land inside the tree they are applied to and shrink towards the
+/// front of it.
+#[derive(Clone, Debug)]
+pub enum TreeOp<T> {
+    /// Inserts the value at the position, which may be one past the last node
+    Insert(Index, T),
+    /// Removes the node at the position, if the tree isn't empty
+    Remove(Index),
+    /// Replaces the contents of the node at the position, if the tree isn't empty
+    Replace(Index, T),
+    /// Removes the first node
+    PopFirst,
+    /// Removes the last node
+    PopLast,
+}
+
+impl<T: Clone + fmt::Debug> TreeOp<T> {
+    /// Applies the operation to a tree
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The tree to mutate
+    ///
+    pub fn apply<A: Augment<T>, S: NodeStore<Node>, B: Balance>(
+        &self,
+        tree: &mut Tree<T, A, S, B>,
+    ) {
+        match self {
+            TreeOp::Insert(index, value) => {
+                tree.insert_at(index.index(tree.len() + 1), value.clone());
+            }
+            TreeOp::Remove(index) => {
+                if !tree.is_empty() {
+                    tree.remove_at(index.index(tree.len()));
+                }
+            }
+            TreeOp::Replace(index, value) => {
+                if !tree.is_empty() {
+                    let node = tree.nth(index.index(tree.len())).unwrap();
+                    tree.replace(node, value.clone());
+                }
+            }
+            TreeOp::PopFirst => {
+                tree.pop_first();
+            }
+            TreeOp::PopLast => {
+                tree.pop_last();
+            }
+        }
+    }
+
+    /// Applies the operation to a vector standing in for a tree, so that a tree can be checked
+    /// against it
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The vector to mutate
+    ///
+    pub fn apply_to_vec(&self, model: &mut Vec<T>) {
+        match self {
+            TreeOp::Insert(index, value) => {
+                model.insert(index.index(model.len() + 1), value.clone())
+            }
+            TreeOp::Remove(index) => {
+                if !model.is_empty() {
+                    model.remove(index.index(model.len()));
+                }
+            }
+            TreeOp::Replace(index, value) => {
+                if !model.is_empty() {
+                    let index = index.index(model.len());
+                    model[index] = value.clone();
+                }
+            }
+            TreeOp::PopFirst => {
+                if !model.is_empty() {
+                    model.remove(0);
+                }
+            }
+            TreeOp::PopLast => {
+                model.pop();
+            }
+        }
+    }
+}
+
+/// Returns a strategy generating sequences of operations on a tree, weighted towards inserts so
+/// that the trees they build grow. Failing sequences shrink by dropping operations and simplifying
+/// the values and positions of the rest.
+///
+/// # Arguments
+///
+/// * `element` - The strategy generating the values that are inserted
+/// * `len` - The range of the number of operations in a sequence
+///
+pub fn tree_ops<T: Clone + fmt::Debug + 'static>(
+    element: impl Strategy<Value = T> + Clone + 'static,
+    len: impl Into<SizeRange>,
+) -> impl Strategy<Value = Vec<TreeOp<T>>> {
+    let op = prop_oneof![
+        4 => (any::<Index>(), element.clone()).prop_map(|(index, value)| TreeOp::Insert(index, value)),
+        1 => any::<Index>().prop_map(TreeOp::Remove),
+        1 => (any::<Index>(), element).prop_map(|(index, value)| TreeOp::Replace(index, value)),
+        1 => Just(TreeOp::PopFirst),
+        1 => Just(TreeOp::PopLast),
+    ];
+    vec(op, len)
+}
+
+/// Returns a strategy generating trees by applying a sequence of operations to an empty tree, so
+/// the trees have the shapes left by a mix of inserts and deletes rather than by a bulk build
+///
+/// # Arguments
+///
+/// * `element` - The strategy generating the contents of the nodes
+/// * `len` - The range of the number of operations used to build each tree
+///
+pub fn trees<T: Clone + fmt::Debug + 'static>(
+    element: impl Strategy<Value = T> + Clone + 'static,
+    len: impl Into<SizeRange>,
+) -> impl Strategy<Value = Tree<T>> {
+    tree_ops(element, len).prop_map(|ops| {
+        let mut tree = Tree::new();
+        for op in ops.iter() {
+            op.apply(&mut tree);
+        }
+        tree
+    })
+}
+
+impl<T: Arbitrary + Clone + fmt::Debug + 'static> Arbitrary for Tree<T> {
+    type Parameters = T::Parameters;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(parameters: Self::Parameters) -> Self::Strategy {
+        // The strategy for the contents is boxed so that it can be cloned
+        trees(any_with::<T>(parameters).boxed(), 0..100).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::proptest;
+
+    proptest! {
+        #[test]
+        fn tree_ops_match_vec_test(ops in tree_ops(any::<u8>(), 0..200)) {
+            let mut tree: Tree<u8> = Tree::new();
+            let mut model = vec![];
+            for op in ops.iter() {
+                op.apply(&mut tree);
+                op.apply_to_vec(&mut model);
+            }
+            tree.assert_valid();
+            assert_eq!(tree.to_vec(), model);
+        }
+
+        #[test]
+        fn arbitrary_tree_test(tree in any::<Tree<i32>>()) {
+            assert_eq!(tree.check_invariants(), Ok(()));
+            assert!(tree.len() < 100);
+        }
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, TransactionError, Tree};
+use std::collections::HashMap;
+use std::fmt;
+
+// A change made inside a transaction, along with what is needed to undo it
+pub(crate) enum Undo<T> {
+    // A node was inserted
+    Inserted(NodeKey),
+    // A node was deleted from straight after `prev`, or from the start of the tree
+    Deleted {
+        node: NodeKey,
+        prev: Option<NodeKey>,
+        contents: T,
+    },
+    // The contents of a node were replaced
+    Contents(NodeKey, T),
+}
+
+impl<T> Undo<T> {
+    // Updates the keys of the nodes a rollback gave new keys to
+    fn remap(&mut self, restored: &HashMap<NodeKey, NodeKey>) {
+        let remap = |node: &mut NodeKey| {
+            if let Some(&new_node) = restored.get(node) {
+                *node = new_node;
+            }
+        };
+        match self {
+            Undo::Inserted(node) | Undo::Contents(node, _) => remap(node),
+            Undo::Deleted { node, prev, .. } => {
+                remap(node);
+                if let Some(prev) = prev {
+                    remap(prev);
+                }
+            }
+        }
+    }
+}
+
+// The changes made by the transaction in progress
+pub(crate) struct UndoLog<T> {
+    changes: Vec<Undo<T>>,
+    // Set once an operation that can't be rolled back has run, the changes are then kept
+    poisoned: bool,
+}
+
+impl<T> Default for UndoLog<T> {
+    fn default() -> Self {
+        UndoLog {
+            changes: Vec::new(),
+            poisoned: false,
+        }
+    }
+}
+
+// Ends a transaction when dropped, rolling back the changes it logged unless it was committed or
+// poisoned. Dropping it while a panic unwinds rolls the changes back as well.
+struct Rollback<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> {
+    tree: &'a mut Tree<T, A, S, B>,
+    // The log of the enclosing transaction, if this one is nested in another
+    outer: Option<UndoLog<T>>,
+    committed: bool,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Drop
+    for Rollback<'_, T, A, S, B>
+{
+    fn drop(&mut self) {
+        let log = self.tree.undo_log.take().unwrap_or_default();
+        let mut outer = self.outer.take();
+        if self.committed || log.poisoned {
+            // The changes become part of the enclosing transaction, which can't roll them back
+            // either if this one was poisoned
+            if let Some(outer) = outer.as_mut() {
+                outer.changes.extend(log.changes);
+                outer.poisoned |= log.poisoned;
+            }
+        } else {
+            let restored = self.tree.roll_back(log.changes);
+            if let Some(outer) = outer.as_mut() {
+                for undo in outer.changes.iter_mut() {
+                    undo.remap(&restored);
+                }
+            }
+        }
+        self.tree.undo_log = outer;
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Runs a closure that mutates the tree as a single atomic step. If the closure returns an
+    /// error, or panics, every change it made is rolled back and the error is returned as
+    /// `TransactionError::RolledBack`, or the panic carries on unwinding.
+    ///
+    /// The changes are logged as they are made and the log is played back in reverse to roll
+    /// them back, so a transaction costs time proportional to the changes it makes rather than
+    /// the size of the tree. After a rollback the tree holds the same sequence as before and the
+    /// NodeKeys of its nodes stay valid, apart from the nodes the closure deleted or moved into
+    /// another tree, which are put back with new NodeKeys. The shape of the tree may differ.
+    ///
+    /// `shrink_to`, `shrink_to_fit`, `compact`, `undo` and `redo` give the nodes new NodeKeys or
+    /// replace them outright, so they can't be rolled back. Once one of them runs the changes of
+    /// the transaction are kept whatever the closure returns, and an error is returned as
+    /// `TransactionError::NotRolledBack`. Callbacks registered with the tree are called for the
+    /// changes made by the rollback as well as for the changes it undoes. Only this tree is rolled
+    /// back, a tree split off from it keeps its nodes and a tree appended to it stays empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The closure to run, given the tree to mutate
+    ///
+    pub fn transaction<R, E, F: FnOnce(&mut Self) -> Result<R, E>>(
+        &mut self,
+        f: F,
+    ) -> Result<R, TransactionError<E>> {
+        let outer = self.undo_log.replace(UndoLog::default());
+        let mut rollback = Rollback {
+            tree: self,
+            outer,
+            committed: false,
+        };
+        match f(rollback.tree) {
+            Ok(value) => {
+                rollback.committed = true;
+                Ok(value)
+            }
+            Err(error) => match rollback.tree.undo_log.as_ref() {
+                Some(log) if log.poisoned => Err(TransactionError::NotRolledBack(error)),
+                _ => Err(TransactionError::RolledBack(error)),
+            },
+        }
+    }
+
+    // Undoes the logged changes, latest first. Returns the new keys given to deleted nodes.
+    fn roll_back(&mut self, log: Vec<Undo<T>>) -> HashMap<NodeKey, NodeKey> {
+        let mut restored = HashMap::new();
+        for mut undo in log.into_iter().rev() {
+            undo.remap(&restored);
+            match undo {
+                Undo::Inserted(node) => {
+                    self.delete_node(node);
+                }
+                Undo::Deleted {
+                    node,
+                    prev,
+                    contents,
+                } => {
+                    let new_node = match (prev, self.leftmost) {
+                        (Some(prev), _) => self.insert_after(prev, contents),
+                        (None, Some(first)) => self.insert_before(first, contents),
+                        (None, None) => self.create_root(contents),
+                    };
+                    restored.insert(node, new_node);
+                }
+                Undo::Contents(node, contents) => self.set_contents(node, contents),
+            }
+        }
+        restored
+    }
+
+    // Logs the insert of a node for the transaction in progress
+    pub(crate) fn log_insert(&mut self, node: NodeKey) {
+        self.log(Undo::Inserted(node));
+    }
+
+    // Logs the delete of a node for the transaction in progress, before it is deleted
+    pub(crate) fn log_delete(&mut self, node: NodeKey) {
+        if self.undo_log.is_some() {
+            self.log(Undo::Deleted {
+                node,
+                prev: self.get_prev(node),
+                contents: self.node_data[node].clone(),
+            });
+        }
+    }
+
+    // Logs the deletes of the run of nodes from `first` to `last` for the transaction in
+    // progress, before they are removed. The last node is logged first, so that a rollback puts
+    // the first node back first.
+    pub(crate) fn log_delete_run(&mut self, first: NodeKey, last: NodeKey) {
+        if self.undo_log.is_none() {
+            return;
+        }
+        let mut node = last;
+        loop {
+            let prev = self.get_prev(node);
+            self.log_delete(node);
+            if node == first {
+                break;
+            }
+            node = prev.unwrap();
+        }
+    }
+
+    // Logs the deletes of every node for the transaction in progress, before they are removed
+    pub(crate) fn log_delete_all(&mut self) {
+        if let (Some(first), Some(last)) = (self.leftmost, self.rightmost) {
+            self.log_delete_run(first, last);
+        }
+    }
+
+    // Logs the contents of a node for the transaction in progress, before they are replaced
+    pub(crate) fn log_contents(&mut self, node: NodeKey) {
+        if self.undo_log.is_some() {
+            self.log(Undo::Contents(node, self.node_data[node].clone()));
+        }
+    }
+
+    // Marks the transaction in progress as unable to roll back, before an operation that can't
+    // be logged
+    pub(crate) fn poison_transaction(&mut self) {
+        if let Some(log) = self.undo_log.as_mut() {
+            log.poisoned = true;
+        }
+    }
+
+    // Adds a change to the log of the transaction in progress, if there is one
+    fn log(&mut self, undo: Undo<T>) {
+        if let Some(log) = self.undo_log.as_mut() {
+            log.changes.push(undo);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TreeError;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn transaction_test() {
+        let mut tree: Tree<u32> = (0..5).collect();
+        let middle = tree.nth(2).unwrap();
+
+        // Splitting an arc and then failing to update a deleted neighbour rolls back the split
+        let result = tree.transaction(|tx| {
+            let (left, _, right) = tx.split_node(middle, 20, 21, 22);
+            tx.delete_node(left);
+            tx.try_delete_node(left)?;
+            Ok(right)
+        });
+        assert!(matches!(
+            result,
+            Err(TransactionError::RolledBack(TreeError::StaleKey(_)))
+        ));
+        assert_eq!(tree.to_vec(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(*tree.get_contents(middle), 2);
+        tree.assert_valid();
+
+        let result: Result<_, TransactionError<TreeError>> = tree.transaction(|tx| {
+            let (_, _, right) = tx.split_node(middle, 20, 21, 22);
+            Ok(right)
+        });
+        let right = result.unwrap();
+        assert_eq!(tree.to_vec(), vec![0, 1, 20, 21, 22, 3, 4]);
+        assert_eq!(tree.get_next(right), tree.nth(5));
+    }
+
+    #[test]
+    fn rollback_test() {
+        let mut tree: Tree<u32> = (0..20).collect();
+        let nodes: Vec<NodeKey> = tree.iter_with_keys().map(|(node, _)| node).collect();
+
+        // Deleted nodes are put back in place, including runs of them and the ends of the tree
+        let result: Result<(), _> = tree.transaction(|tx| {
+            tx.delete_range(nodes[5], nodes[9]);
+            tx.delete_node(nodes[0]);
+            tx.delete_node(nodes[19]);
+            let first = tx.get_leftmost_node().unwrap();
+            tx.insert_before(first, 100);
+            tx.insert_all_after(nodes[4], 200..210);
+            tx.replace(nodes[10], 300);
+            Err(())
+        });
+        assert!(result.is_err());
+        tree.assert_valid();
+        assert_eq!(tree.to_vec(), (0..20).collect::<Vec<_>>());
+        for i in (1..5).chain(10..19) {
+            assert_eq!(tree.get_contents(nodes[i]), &(i as u32));
+        }
+
+        // A nested transaction that fails only rolls back its own changes
+        let result: Result<(), _> = tree.transaction(|tx| {
+            tx.delete_node(nodes[12]);
+            let inner: Result<(), _> = tx.transaction(|tx| {
+                tx.delete_node(nodes[13]);
+                Err(())
+            });
+            assert!(inner.is_err());
+            let restored = tx.nth(12).unwrap();
+            assert_eq!(tx.get_contents(restored), &13);
+            tx.delete_node(restored);
+            Err(())
+        });
+        assert!(result.is_err());
+        assert_eq!(tree.to_vec(), (0..20).collect::<Vec<_>>());
+
+        // A panic rolls back as it unwinds
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _: Result<(), TransactionError<()>> = tree.transaction(|tx| {
+                tx.delete_node(nodes[3]);
+                tx.insert_after(nodes[4], 50);
+                panic!("failed part way through");
+            });
+        }));
+        assert!(result.is_err());
+        tree.assert_valid();
+        assert_eq!(tree.to_vec(), (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn bulk_rollback_test() {
+        let mut tree: Tree<u32> = (0..20).collect();
+        let nodes: Vec<NodeKey> = tree.iter_with_keys().map(|(node, _)| node).collect();
+
+        // Nodes inserted before a bulk removal are dropped by it, so are not deleted again
+        let result: Result<(), _> = tree.transaction(|tx| {
+            tx.insert_after(nodes[3], 100);
+            tx.clear();
+            tx.extend(0..5);
+            Err(())
+        });
+        assert_eq!(result, Err(TransactionError::RolledBack(())));
+        tree.assert_valid();
+        assert_eq!(tree.to_vec(), (0..20).collect::<Vec<_>>());
+
+        let nodes: Vec<NodeKey> = tree.iter_with_keys().map(|(node, _)| node).collect();
+        let mut appended: Tree<u32> = (50..60).collect();
+        let result: Result<(), _> = tree.transaction(|tx| {
+            tx.insert_after(nodes[15], 100);
+            let (other, _) = tx.split_off_at(10);
+            assert_eq!(other.len(), 11);
+            tx.retain(|value| value % 2 == 0);
+            tx.append(&mut appended);
+            tx.detach_subtree(tx.root.unwrap());
+            tx.drain().take(2).for_each(drop);
+            Err(())
+        });
+        assert!(result.is_err());
+        tree.assert_valid();
+        assert_eq!(tree.to_vec(), (0..20).collect::<Vec<_>>());
+        assert!(appended.is_empty());
+
+        // The nodes merged in from another tree are taken back out
+        let mut merged: Tree<u32> = (0..10).map(|value| value * 2 + 1).collect();
+        let result: Result<(), _> = tree.transaction(|tx| {
+            tx.merge_by(&mut merged, |a, b| a.cmp(b));
+            assert_eq!(tx.len(), 30);
+            Err(())
+        });
+        assert!(result.is_err());
+        tree.assert_valid();
+        assert_eq!(tree.to_vec(), (0..20).collect::<Vec<_>>());
+
+        // Contents changed through a mutable reference are put back too
+        let nodes: Vec<NodeKey> = tree.iter_with_keys().map(|(node, _)| node).collect();
+        let result: Result<(), _> = tree.transaction(|tx| {
+            *tx.get_mut_contents(nodes[1]) = 100;
+            tx[nodes[2]] = 200;
+            *tx.try_get_mut_contents(nodes[3]).unwrap() = 300;
+            Err(())
+        });
+        assert!(result.is_err());
+        assert_eq!(tree.to_vec(), (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn poisoned_transaction_test() {
+        let mut tree: Tree<u32> = (0..20).collect();
+        let first = tree.get_leftmost_node().unwrap();
+
+        // Compacting can't be rolled back, so the changes are kept and the error says so
+        let result: Result<(), _> = tree.transaction(|tx| {
+            tx.delete_node(first);
+            tx.compact();
+            tx.pop_last();
+            Err(())
+        });
+        assert_eq!(result, Err(TransactionError::NotRolledBack(())));
+        tree.assert_valid();
+        assert_eq!(tree.to_vec(), (1..19).collect::<Vec<_>>());
+
+        // A poisoned nested transaction poisons the one enclosing it
+        let result: Result<(), _> = tree.transaction(|tx| {
+            tx.pop_first();
+            let inner: Result<(), _> = tx.transaction(|tx| {
+                tx.shrink_to_fit();
+                Err(())
+            });
+            assert_eq!(inner, Err(TransactionError::NotRolledBack(())));
+            Err(())
+        });
+        assert_eq!(result, Err(TransactionError::NotRolledBack(())));
+        assert_eq!(tree.len(), 17);
+
+        // A panic after a poisoning operation leaves the tree as it was when the panic started
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _: Result<(), TransactionError<()>> = tree.transaction(|tx| {
+                tx.pop_first();
+                tx.compact();
+                panic!("failed part way through");
+            });
+        }));
+        assert!(result.is_err());
+        tree.assert_valid();
+        assert_eq!(tree.len(), 16);
+
+        // The next transaction starts with a clean log
+        let result: Result<(), _> = tree.transaction(|tx| {
+            tx.pop_first();
+            Err(())
+        });
+        assert_eq!(result, Err(TransactionError::RolledBack(())));
+        assert_eq!(tree.len(), 16);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::balance::{Rebalance, Subtree};
+use crate::{Augment, InvariantViolation, Node, NodeKey, NodeStore, NodeType, Tree, RANK_BITS};
+use slotmap::SlotMap;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+
+// The highest priority that fits in the rank bits of a node
+const MAX_PRIORITY: usize = (1 << RANK_BITS) - 1;
+
+/// Treap balancing. Every node is given a random priority when it is inserted and the tree is
+/// kept in heap order on the priorities, which leaves it shaped as if the nodes had been inserted
+/// in a random order whatever order they really arrived in. The height is logarithmic in the
+/// length with high probability, and splitting and joining are a single walk down the tree
+/// comparing priorities, with no balance data to repair afterwards.
+///
+/// The priorities are those of a zip tree: the number of heads flipped in a row, stored in the
+/// node alongside its size. Ties go to the node that comes first in the sequence.
+#[derive(PartialEq, Eq, Copy, Clone, Default, Debug)]
+pub struct Treap;
+
+/// A tree kept balanced by random priorities, see `Treap`
+pub type TreapTree<T, A = ()> = Tree<T, A, SlotMap<NodeKey, Node>, Treap>;
+
+// Returns a random priority, where each priority is half as likely as the one below it
+fn random_priority() -> usize {
+    let random = RandomState::new().build_hasher().finish();
+    (random.trailing_ones() as usize).min(MAX_PRIORITY)
+}
+
+// Returns the priority a node needs to sit above children with the given priorities. A left child
+// comes first in the sequence, so it has to be strictly lower to lose the tie.
+fn priority_above(left: Option<usize>, right: Option<usize>) -> usize {
+    left.map_or(0, |left| left + 1).max(right.unwrap_or(0))
+}
+
+impl Rebalance for Treap {
+    fn inserted<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        tree.set_rank(node, random_priority());
+        tree.treap_sift_up(node);
+    }
+
+    // A node with at most one child can be removed without breaking the heap order, its child
+    // has a lower priority than anything that was above it
+    fn remove<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        tree.unlink(node);
+    }
+
+    // Joins compare the priorities of the nodes themselves, so subtrees carry no rank
+    fn rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &Tree<T, A, S, Self>,
+        _node: Option<NodeKey>,
+    ) -> usize {
+        0
+    }
+
+    fn join<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        left: Subtree,
+        pivot: NodeKey,
+        right: Subtree,
+    ) -> (NodeKey, usize) {
+        let left = left.map(|(root, _)| root);
+        let right = right.map(|(root, _)| root);
+        (tree.treap_join(left, pivot, right), 0)
+    }
+
+    // A perfectly balanced tree is given the lowest priorities that keep it in heap order, which
+    // are spread the same way as random ones
+    fn linked<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+        _deepest: bool,
+    ) {
+        let left = tree.get_left(node).map(|left| tree.get_rank(Some(left)));
+        let right = tree.get_right(node).map(|right| tree.get_rank(Some(right)));
+        tree.set_rank(node, priority_above(left, right));
+    }
+
+    fn check<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+        violations: &mut Vec<InvariantViolation>,
+    ) -> usize {
+        let priority = tree.get_rank(Some(node));
+        if left.is_some_and(|left| left >= priority) {
+            let child = tree.get_left(node).unwrap();
+            violations.push(InvariantViolation::PriorityOrder {
+                parent: node,
+                child,
+            });
+        }
+        if right.is_some_and(|right| right > priority) {
+            let child = tree.get_right(node).unwrap();
+            violations.push(InvariantViolation::PriorityOrder {
+                parent: node,
+                child,
+            });
+        }
+        priority
+    }
+
+    // Priorities aren't serialized, any shape can be given priorities that put it in heap order
+    fn restore<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+    ) -> Result<usize, &'static str> {
+        let priority = priority_above(left, right);
+        if priority > MAX_PRIORITY {
+            return Err("the tree is too deep to give its nodes priorities");
+        }
+        tree.set_rank(node, priority);
+        Ok(priority)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>> Tree<T, A, S, Treap> {
+    // Rotates a node up until its parent has a higher priority
+    fn treap_sift_up(&mut self, node: NodeKey) {
+        let priority = self.get_rank(Some(node));
+        while let Some(parent) = self.get_parent(node) {
+            let parent_priority = self.get_rank(Some(parent));
+            let outranks = match self.get_node_type(node) {
+                NodeType::LeftChild => priority >= parent_priority,
+                _ => priority > parent_priority,
+            };
+            if !outranks {
+                break;
+            }
+            self.count_fixup_iteration();
+            self.rotate_up(node);
+        }
+    }
+
+    // Joins two detached subtrees and a detached pivot node that sits between them in the sequence
+    // into a single subtree, returning its root. Whichever of the two roots and the pivot has the
+    // highest priority becomes the root, and the join carries on down into the side it left open
+    // until the pivot wins and takes both of what is left as its children.
+    fn treap_join(
+        &mut self,
+        mut left: Option<NodeKey>,
+        pivot: NodeKey,
+        mut right: Option<NodeKey>,
+    ) -> NodeKey {
+        let pivot_priority = self.get_rank(Some(pivot));
+        let mut root = pivot;
+        // The node the next winner hangs from, and whether it is hung as a left child
+        let mut attach: Option<(NodeKey, bool)> = None;
+        loop {
+            // Ties go to the node that comes first in the sequence
+            let left_priority = left.map(|node| self.get_rank(Some(node)));
+            let right_priority = right.map(|node| self.get_rank(Some(node)));
+            let (top, next) = match (left, right) {
+                (Some(node), _)
+                    if left_priority >= Some(pivot_priority) && left_priority >= right_priority =>
+                {
+                    // The rest of the join goes where the right subtree of the left root was
+                    left = self.get_right(node);
+                    (node, Some((node, false)))
+                }
+                (_, Some(node)) if right_priority > Some(pivot_priority) => {
+                    right = self.get_left(node);
+                    (node, Some((node, true)))
+                }
+                _ => (pivot, None),
+            };
+
+            match attach {
+                None => root = top,
+                Some((parent, true)) => self.set_left(parent, Some(top)),
+                Some((parent, false)) => self.set_right(parent, Some(top)),
+            }
+            self.set_parent(top, attach.map(|(parent, _)| parent));
+            match next {
+                Some(next) => attach = Some(next),
+                None => break,
+            }
+        }
+
+        self.set_left(pivot, left);
+        self.set_right(pivot, right);
+        for child in left.into_iter().chain(right) {
+            self.set_parent(child, Some(pivot));
+        }
+
+        // Every node that changed children lies on the path from the pivot up to the root
+        let mut node = Some(pivot);
+        while let Some(current) = node {
+            let size =
+                self.get_size(self.get_left(current)) + self.get_size(self.get_right(current));
+            self.set_size(current, size + 1);
+            self.update_augmented_node(current);
+            node = self.get_parent(current);
+        }
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::tests::{insert_delete_test, split_append_test};
+
+    #[test]
+    fn treap_insert_delete_test() {
+        insert_delete_test::<Treap>(500, |tree, _| {
+            // The expected height is about 1.5 log n, this bound fails with negligible probability
+            assert!(tree.height() <= 40);
+        });
+    }
+
+    #[test]
+    fn treap_split_append_test() {
+        split_append_test::<Treap>(40, |other| {
+            // Inserted nodes get random priorities, so the joins meet trees of any shape
+            let first = other.get_leftmost_node().unwrap();
+            let contents = *other.get_contents(first);
+            other.insert_before(first, contents);
+            other.delete_node(first);
+        });
+    }
+}
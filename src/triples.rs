@@ -0,0 +1,65 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, RedBlack, Tree};
+use slotmap::SlotMap;
+use std::fmt;
+
+/// An iterator over every run of three consecutive nodes in a tree, yielding their NodeKeys as
+/// `(prev, node, next)`. Like `slice::windows(3)`, nothing is yielded for trees with fewer than
+/// three nodes.
+pub struct Triples<
+    'a,
+    T: Clone + fmt::Debug,
+    A: Augment<T> = (),
+    S: NodeStore<Node> = SlotMap<NodeKey, Node>,
+    B: Balance = RedBlack,
+> {
+    tree: &'a Tree<T, A, S, B>,
+    // The first node of the next triple to yield
+    first: Option<NodeKey>,
+}
+
+impl<'a, T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Iterator
+    for Triples<'a, T, A, S, B>
+{
+    type Item = (NodeKey, NodeKey, NodeKey);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let prev = self.first?;
+        let node = self.tree.get_next(prev)?;
+        let next = self.tree.get_next(node)?;
+        self.first = Some(node);
+        Some((prev, node, next))
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Returns an iterator over every run of three consecutive nodes, in order
+    pub fn triples(&self) -> Triples<'_, T, A, S, B> {
+        Triples {
+            tree: self,
+            first: self.leftmost,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triples_test() {
+        let mut tree: Tree<usize> = (0..2).collect();
+        assert_eq!(tree.triples().count(), 0);
+        tree.extend(2..6);
+        let triples: Vec<(usize, usize, usize)> = tree
+            .triples()
+            .map(|(prev, node, next)| {
+                (
+                    *tree.get_contents(prev),
+                    *tree.get_contents(node),
+                    *tree.get_contents(next),
+                )
+            })
+            .collect();
+        assert_eq!(triples, vec![(0, 1, 2), (1, 2, 3), (2, 3, 4), (3, 4, 5)]);
+    }
+}
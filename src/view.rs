@@ -0,0 +1,82 @@
+use crate::{Augment, Balance, Color, Node, NodeKey, NodeStore, Tree};
+use std::fmt;
+
+/// The links and color of a node read in one go, returned by `Tree::view`
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct NodeView {
+    /// The parent of the node, None for the root
+    pub parent: Option<NodeKey>,
+    /// The left child of the node
+    pub left: Option<NodeKey>,
+    /// The right child of the node
+    pub right: Option<NodeKey>,
+    /// The node before this one in the sequence
+    pub prev: Option<NodeKey>,
+    /// The node after this one in the sequence
+    pub next: Option<NodeKey>,
+    /// The color of the node, always black unless the tree is a red-black tree
+    pub color: Color,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Returns the links and color of the specified node with a single lookup of the node, rather
+    /// than one for each getter. Without the threading feature the neighbours are still found by
+    /// walking the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to inspect
+    ///
+    pub fn view(&self, node: NodeKey) -> NodeView {
+        let links = self.nodes.get(node).unwrap();
+        NodeView {
+            parent: links.parent,
+            left: links.left,
+            right: links.right,
+            #[cfg(feature = "threading")]
+            prev: links.prev,
+            #[cfg(not(feature = "threading"))]
+            prev: self.get_prev(node),
+            #[cfg(feature = "threading")]
+            next: links.next,
+            #[cfg(not(feature = "threading"))]
+            next: self.get_next(node),
+            color: if B::is_red(links) {
+                Color::Red
+            } else {
+                Color::Black
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AvlTree;
+
+    #[test]
+    fn view_test() {
+        let tree: Tree<usize> = (0..50).collect();
+        let mut node = tree.get_leftmost_node();
+        while let Some(current) = node {
+            let view = tree.view(current);
+            assert_eq!(view.parent, tree.get_parent(current));
+            assert_eq!(view.left, tree.get_left(current));
+            assert_eq!(view.right, tree.get_right(current));
+            assert_eq!(view.prev, tree.get_prev(current));
+            assert_eq!(view.next, tree.get_next(current));
+            assert_eq!(view.color, tree.get_color(Some(current)));
+            node = view.next;
+        }
+        assert_eq!(tree.view(tree.root.unwrap()).color, Color::Black);
+
+        // Only red-black trees have red nodes
+        let tree: AvlTree<usize> = (0..50).collect();
+        let mut node = tree.get_leftmost_node();
+        while let Some(current) = node {
+            assert_eq!(tree.view(current).color, Color::Black);
+            node = tree.get_next(current);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{Augment, Balance, Node, NodeKey, NodeStore, Tree};
+use std::fmt;
+
+/// Callbacks for a walk over the structure of a tree with `Tree::accept`. Every node is visited
+/// three times: before its left subtree, between its subtrees and after its right subtree, so
+/// the walk gives pre-order, in-order and post-order traversals at once. Each callback is passed
+/// the depth of the node, 0 for the root, and does nothing unless it is overridden.
+pub trait TreeVisitor<T> {
+    /// Called when the walk reaches a node, before its left subtree is visited
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node being visited
+    /// * `contents` - The contents of the node
+    /// * `depth` - The number of edges between the node and the root
+    ///
+    fn pre_visit(&mut self, _node: NodeKey, _contents: &T, _depth: usize) {}
+
+    /// Called after the left subtree of a node has been visited and before its right subtree, so
+    /// the nodes are reached in the order of the sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node being visited
+    /// * `contents` - The contents of the node
+    /// * `depth` - The number of edges between the node and the root
+    ///
+    fn in_visit(&mut self, _node: NodeKey, _contents: &T, _depth: usize) {}
+
+    /// Called once both subtrees of a node have been visited
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node being visited
+    /// * `contents` - The contents of the node
+    /// * `depth` - The number of edges between the node and the root
+    ///
+    fn post_visit(&mut self, _node: NodeKey, _contents: &T, _depth: usize) {}
+}
+
+// How far the walk has got through a node on the stack
+#[derive(Copy, Clone)]
+enum Stage {
+    Pre,
+    In,
+    Post,
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    /// Walks the structure of the tree from the root, calling the visitor as each node is reached
+    /// before, between and after its subtrees. The walk keeps its own stack rather than recursing,
+    /// so deep trees can't overflow the call stack.
+    ///
+    /// # Arguments
+    ///
+    /// * `visitor` - The callbacks to make for each node
+    ///
+    pub fn accept<V: TreeVisitor<T> + ?Sized>(&self, visitor: &mut V) {
+        let mut stack: Vec<(NodeKey, usize, Stage)> = self
+            .root
+            .map(|root| (root, 0, Stage::Pre))
+            .into_iter()
+            .collect();
+        while let Some((node, depth, stage)) = stack.pop() {
+            let contents = self.get_contents(node);
+            match stage {
+                Stage::Pre => {
+                    visitor.pre_visit(node, contents, depth);
+                    stack.push((node, depth, Stage::In));
+                    if let Some(left) = self.get_left(node) {
+                        stack.push((left, depth + 1, Stage::Pre));
+                    }
+                }
+                Stage::In => {
+                    visitor.in_visit(node, contents, depth);
+                    stack.push((node, depth, Stage::Post));
+                    if let Some(right) = self.get_right(node) {
+                        stack.push((right, depth + 1, Stage::Pre));
+                    }
+                }
+                Stage::Post => visitor.post_visit(node, contents, depth),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Records the order of every callback
+    #[derive(Default)]
+    struct Recorder {
+        pre: Vec<usize>,
+        inorder: Vec<usize>,
+        post: Vec<usize>,
+        max_depth: usize,
+    }
+
+    impl TreeVisitor<usize> for Recorder {
+        fn pre_visit(&mut self, _node: NodeKey, contents: &usize, depth: usize) {
+            self.pre.push(*contents);
+            self.max_depth = self.max_depth.max(depth);
+        }
+
+        fn in_visit(&mut self, _node: NodeKey, contents: &usize, _depth: usize) {
+            self.inorder.push(*contents);
+        }
+
+        fn post_visit(&mut self, _node: NodeKey, contents: &usize, _depth: usize) {
+            self.post.push(*contents);
+        }
+    }
+
+    #[test]
+    fn accept_test() {
+        let tree: Tree<usize> = (0..7).collect();
+        let mut recorder = Recorder::default();
+        tree.accept(&mut recorder);
+        // Seven nodes are built into a perfect tree
+        assert_eq!(recorder.pre, vec![3, 1, 0, 2, 5, 4, 6]);
+        assert_eq!(recorder.inorder, (0..7).collect::<Vec<_>>());
+        assert_eq!(recorder.post, vec![0, 2, 1, 4, 6, 5, 3]);
+        assert_eq!(recorder.max_depth, tree.height() - 1);
+
+        let mut recorder = Recorder::default();
+        Tree::<usize>::new().accept(&mut recorder);
+        assert!(recorder.pre.is_empty());
+    }
+}
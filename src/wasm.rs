@@ -0,0 +1,164 @@
+//! A JavaScript class wrapping a tree of strings, for building visualizations of the tree in the
+//! browser. Building the crate as a cdylib for a wasm target with the wasm feature and running
+//! wasm-bindgen over it gives a module exporting the `Tree` and `Node` classes. The library is
+//! built with `cargo rustc --release --lib --target wasm32-unknown-unknown --features wasm
+//! --crate-type cdylib`.
+//!
+//! Methods given a node that is no longer in the tree return undefined rather than throwing.
+
+use crate::{Color, NodeKey, Tree};
+use wasm_bindgen::prelude::*;
+
+/// A handle to a node of a `WasmTree`, passed back to the tree to refer to the node
+#[wasm_bindgen(js_name = Node)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct WasmNode {
+    key: NodeKey,
+}
+
+/// A sequence of strings kept in a red-black tree
+#[wasm_bindgen(js_name = Tree)]
+#[derive(Default)]
+pub struct WasmTree {
+    tree: Tree<String>,
+}
+
+impl WasmTree {
+    // Returns the key of a node if it is still in the tree
+    fn key(&self, node: &WasmNode) -> Option<NodeKey> {
+        Some(node.key).filter(|&key| self.tree.contains_node(key))
+    }
+}
+
+fn handle(key: NodeKey) -> WasmNode {
+    WasmNode { key }
+}
+
+#[wasm_bindgen(js_class = Tree)]
+impl WasmTree {
+    /// Creates an empty tree
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmTree {
+        WasmTree::default()
+    }
+
+    /// Creates a tree from the nested JSON written by `toJson`, throwing if the JSON doesn't
+    /// describe a valid red-black tree
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<WasmTree, JsError> {
+        let tree = Tree::from_json(json)?;
+        Ok(WasmTree { tree })
+    }
+
+    /// Returns the tree as nested JSON, where every node is an object holding its `value`, its
+    /// `color` and its `left` and `right` children
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        self.tree
+            .to_json()
+            .expect("strings can always be written as JSON")
+    }
+
+    /// Returns the number of nodes in the tree
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Adds the first node to an empty tree, returning undefined if the tree isn't empty
+    #[wasm_bindgen(js_name = createRoot)]
+    pub fn create_root(&mut self, value: String) -> Option<WasmNode> {
+        self.tree.try_create_root(value).ok().map(handle)
+    }
+
+    /// Inserts a value before a node in the sequence, returning the node holding it
+    #[wasm_bindgen(js_name = insertBefore)]
+    pub fn insert_before(&mut self, node: &WasmNode, value: String) -> Option<WasmNode> {
+        let key = self.key(node)?;
+        Some(handle(self.tree.insert_before(key, value)))
+    }
+
+    /// Inserts a value after a node in the sequence, returning the node holding it
+    #[wasm_bindgen(js_name = insertAfter)]
+    pub fn insert_after(&mut self, node: &WasmNode, value: String) -> Option<WasmNode> {
+        let key = self.key(node)?;
+        Some(handle(self.tree.insert_after(key, value)))
+    }
+
+    /// Deletes a node from the tree, returning the value it held
+    pub fn delete(&mut self, node: &WasmNode) -> Option<String> {
+        let key = self.key(node)?;
+        Some(self.tree.delete_node(key))
+    }
+
+    /// Returns the value held by a node
+    pub fn get(&self, node: &WasmNode) -> Option<String> {
+        let key = self.key(node)?;
+        Some(self.tree.get_contents(key).clone())
+    }
+
+    /// Returns true if the node is red, so a visualization can color it
+    #[wasm_bindgen(js_name = isRed)]
+    pub fn is_red(&self, node: &WasmNode) -> Option<bool> {
+        let key = self.key(node)?;
+        Some(self.tree.view(key).color == Color::Red)
+    }
+
+    /// Returns the first node in the sequence
+    pub fn first(&self) -> Option<WasmNode> {
+        self.tree.get_leftmost_node().map(handle)
+    }
+
+    /// Returns the node after a node in the sequence
+    pub fn next(&self, node: &WasmNode) -> Option<WasmNode> {
+        let key = self.key(node)?;
+        self.tree.get_next(key).map(handle)
+    }
+
+    /// Returns the root of the tree
+    pub fn root(&self) -> Option<WasmNode> {
+        self.tree.root.map(handle)
+    }
+
+    /// Returns the left child of a node
+    pub fn left(&self, node: &WasmNode) -> Option<WasmNode> {
+        let key = self.key(node)?;
+        self.tree.get_left(key).map(handle)
+    }
+
+    /// Returns the right child of a node
+    pub fn right(&self, node: &WasmNode) -> Option<WasmNode> {
+        let key = self.key(node)?;
+        self.tree.get_right(key).map(handle)
+    }
+
+    /// Returns the values of every node, in order
+    pub fn values(&self) -> Vec<String> {
+        self.tree.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wasm_tree_test() {
+        let mut tree = WasmTree::new();
+        let root = tree.create_root("b".to_string()).unwrap();
+        assert_eq!(tree.create_root("x".to_string()), None);
+        let first = tree.insert_before(&root, "a".to_string()).unwrap();
+        tree.insert_after(&root, "c".to_string()).unwrap();
+        assert_eq!(tree.length(), 3);
+        assert_eq!(tree.values(), vec!["a", "b", "c"]);
+        assert_eq!(tree.next(&first), Some(root));
+        assert_eq!(tree.is_red(&tree.root().unwrap()), Some(false));
+
+        let copy = WasmTree::from_json(&tree.to_json()).unwrap();
+        assert_eq!(copy.values(), tree.values());
+
+        assert_eq!(tree.delete(&first), Some("a".to_string()));
+        assert_eq!(tree.get(&first), None);
+        assert_eq!(tree.values(), vec!["b", "c"]);
+    }
+}
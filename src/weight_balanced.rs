@@ -0,0 +1,212 @@
+use crate::balance::{Rebalance, Subtree};
+use crate::{Augment, Balance, InvariantViolation, Node, NodeKey, NodeStore, Tree};
+use slotmap::SlotMap;
+use std::fmt;
+
+// The balance parameters from Hirai and Yamamoto's proof of the correctness of weight-balanced
+// trees. A subtree can be at most DELTA times the weight of its sibling, where the weight of a
+// subtree is its size plus one. GAMMA decides between a single and a double rotation.
+const DELTA: usize = 3;
+const GAMMA: usize = 2;
+
+/// Weight balancing, also known as BB[α] trees. Rather than keeping track of heights, the sizes
+/// of the two subtrees of every node are kept within a constant factor of each other. The tree
+/// already stores the size of every subtree, so no balance data is kept in the nodes, and
+/// splitting and joining only compare sizes rather than walking down to find heights.
+#[derive(PartialEq, Eq, Copy, Clone, Default, Debug)]
+pub struct WeightBalanced;
+
+/// A tree kept balanced by the sizes of its subtrees, see `WeightBalanced`
+pub type WeightBalancedTree<T, A = ()> = Tree<T, A, SlotMap<NodeKey, Node>, WeightBalanced>;
+
+// Returns true if a subtree of weight `light` is heavy enough to be the sibling of a subtree of
+// weight `heavy`
+fn balanced(light: usize, heavy: usize) -> bool {
+    DELTA * light >= heavy
+}
+
+impl Rebalance for WeightBalanced {
+    fn inserted<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        tree.weight_rebalance_from(tree.get_parent(node));
+    }
+
+    fn remove<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        node: NodeKey,
+    ) {
+        let parent = tree.unlink(node);
+        tree.weight_rebalance_from(parent);
+    }
+
+    fn rank<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &Tree<T, A, S, Self>,
+        node: Option<NodeKey>,
+    ) -> usize {
+        tree.get_size(node)
+    }
+
+    fn join<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        tree: &mut Tree<T, A, S, Self>,
+        left: Subtree,
+        pivot: NodeKey,
+        right: Subtree,
+    ) -> (NodeKey, usize) {
+        tree.weight_join(left, pivot, right)
+    }
+
+    fn check<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &Tree<T, A, S, Self>,
+        node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+        violations: &mut Vec<InvariantViolation>,
+    ) -> usize {
+        let (left, right) = (left.unwrap_or(0), right.unwrap_or(0));
+        if !balanced(left + 1, right + 1) || !balanced(right + 1, left + 1) {
+            violations.push(InvariantViolation::Unbalanced { node, left, right });
+        }
+        left + right + 1
+    }
+
+    fn restore<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>>(
+        _tree: &mut Tree<T, A, S, Self>,
+        _node: NodeKey,
+        left: Option<usize>,
+        right: Option<usize>,
+    ) -> Result<usize, &'static str> {
+        let (left, right) = (left.unwrap_or(0), right.unwrap_or(0));
+        if !balanced(left + 1, right + 1) || !balanced(right + 1, left + 1) {
+            return Err("the sizes of the subtrees are too far apart");
+        }
+        Ok(left + right + 1)
+    }
+}
+
+impl<T: Clone + fmt::Debug, A: Augment<T>, S: NodeStore<Node>, B: Balance> Tree<T, A, S, B> {
+    // The weight of a subtree, one more than its size so that empty subtrees weigh something
+    fn weight(&self, node: Option<NodeKey>) -> usize {
+        self.get_size(node) + 1
+    }
+
+    // Rotates a node whose subtrees have drifted too far apart in weight, returning the node that
+    // takes its place as the root of its subtree
+    fn weight_balance(&mut self, node: NodeKey) -> NodeKey {
+        let left = self.get_left(node);
+        let right = self.get_right(node);
+        if !balanced(self.weight(left), self.weight(right)) {
+            let right = right.unwrap();
+            let (inner, outer) = (self.get_left(right), self.get_right(right));
+            if self.weight(inner) >= GAMMA * self.weight(outer) {
+                self.right_rotate(right);
+            }
+            self.left_rotate(node);
+        } else if !balanced(self.weight(right), self.weight(left)) {
+            let left = left.unwrap();
+            let (inner, outer) = (self.get_right(left), self.get_left(left));
+            if self.weight(inner) >= GAMMA * self.weight(outer) {
+                self.left_rotate(left);
+            }
+            self.right_rotate(node);
+        } else {
+            return node;
+        }
+        self.get_parent(node).unwrap()
+    }
+
+    // Rebalances every node from the specified node up to the root. Every ancestor of a changed
+    // node has changed size, so the walk can't stop early.
+    fn weight_rebalance_from(&mut self, mut node: Option<NodeKey>) {
+        while let Some(current) = node {
+            self.count_fixup_iteration();
+            let root = self.weight_balance(current);
+            node = self.get_parent(root);
+        }
+    }
+
+    // Joins two detached subtrees and a detached pivot node that sits between them in the sequence
+    // into a single subtree. This takes time proportional to the logarithm of the ratio of the
+    // sizes of the subtrees.
+    fn weight_join(&mut self, left: Subtree, pivot: NodeKey, right: Subtree) -> (NodeKey, usize) {
+        let left_weight = left.map_or(0, |(_, size)| size) + 1;
+        let right_weight = right.map_or(0, |(_, size)| size) + 1;
+        if !balanced(right_weight, left_weight) {
+            let (root, _) = left.unwrap();
+            self.weight_join_into(root, pivot, right, false)
+        } else if !balanced(left_weight, right_weight) {
+            let (root, _) = right.unwrap();
+            self.weight_join_into(root, pivot, left, true)
+        } else {
+            // The subtrees are close enough in weight for the pivot to become the root of both
+            let left = left.map(|(root, _)| root);
+            let right = right.map(|(root, _)| root);
+            self.set_left(pivot, left);
+            self.set_right(pivot, right);
+            for child in left.into_iter().chain(right) {
+                self.set_parent(child, Some(pivot));
+            }
+            self.set_size(pivot, self.get_size(left) + self.get_size(right) + 1);
+            self.update_augmented_node(pivot);
+            (pivot, self.get_size(Some(pivot)))
+        }
+    }
+
+    // Joins the lighter subtree into the heavier one by walking down the inner spine of the heavier
+    // subtree to the first node that the lighter subtree balances, and replacing it with the pivot
+    // node that has that node and the lighter subtree as children.
+    // When `into_left` is false the lighter subtree comes after the heavier one in the sequence.
+    fn weight_join_into(
+        &mut self,
+        heavy_root: NodeKey,
+        pivot: NodeKey,
+        light: Subtree,
+        into_left: bool,
+    ) -> (NodeKey, usize) {
+        let light_weight = light.map_or(0, |(_, size)| size) + 1;
+        let mut parent = heavy_root;
+        let mut node = self.inner_child(heavy_root, into_left);
+        while let Some(current) = node {
+            if balanced(light_weight, self.weight(Some(current))) {
+                break;
+            }
+            parent = current;
+            node = self.inner_child(current, into_left);
+        }
+
+        self.link_pivot(parent, pivot, node, light, into_left);
+
+        // The ancestors of the pivot may be too heavy on its side, the rotations need the heavier
+        // subtree to be treated as the whole tree while they happen
+        let root = self.root.replace(heavy_root);
+        self.weight_rebalance_from(Some(parent));
+        let joined_root = std::mem::replace(&mut self.root, root).unwrap();
+        (joined_root, self.get_size(Some(joined_root)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::tests::{insert_delete_test, split_append_test};
+
+    #[test]
+    fn weight_balanced_insert_delete_test() {
+        insert_delete_test::<WeightBalanced>(500, |tree, _| {
+            // No subtree is more than three times the size of its sibling
+            assert!(tree.height() <= 2 * 9);
+        });
+    }
+
+    #[test]
+    fn weight_balanced_split_append_test() {
+        split_append_test::<WeightBalanced>(60, |other| {
+            // Appending a much smaller tree joins it deep into the larger one
+            let end = *other.get_contents(other.get_rightmost_node().unwrap()) + 1;
+            let mut small: WeightBalancedTree<usize> = (end..end + 3).collect();
+            other.append(&mut small);
+            other.assert_valid();
+        });
+    }
+}
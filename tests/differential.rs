@@ -0,0 +1,125 @@
+//! Applies long runs of random operations to the tree types and to the std collections they
+//! stand in for, checking after every step that both hold the same contents in the same order.
+//! The number of operations in each run can be raised with the DIFFERENTIAL_ITERATIONS
+//! environment variable.
+
+use binary_search_tree::{RBMap, Tree};
+use std::collections::{BTreeMap, LinkedList};
+
+// A small xorshift generator, so that the runs are reproducible from their seeds
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+fn iterations() -> usize {
+    // The validate feature checks the whole tree after every step, so fewer steps are taken
+    let default = if cfg!(feature = "validate") {
+        2_000
+    } else {
+        20_000
+    };
+    std::env::var("DIFFERENTIAL_ITERATIONS")
+        .ok()
+        .and_then(|iterations| iterations.parse().ok())
+        .unwrap_or(default)
+}
+
+#[test]
+fn rb_map_matches_btree_map() {
+    for seed in 1..=4 {
+        let mut rng = Rng(seed);
+        let mut map = RBMap::new();
+        let mut model = BTreeMap::new();
+        // The key range is kept small so that keys are often overwritten and removed
+        let keys = 64 << seed;
+        for step in 0..iterations() {
+            let key = rng.below(keys);
+            match rng.below(5) {
+                0 | 1 => assert_eq!(map.insert(key, step), model.insert(key, step)),
+                2 => assert_eq!(map.remove(&key), model.remove(&key)),
+                3 => assert_eq!(map.get(&key), model.get(&key)),
+                _ => {
+                    if let Some(value) = map.get_mut(&key) {
+                        *value += 1;
+                    }
+                    if let Some(value) = model.get_mut(&key) {
+                        *value += 1;
+                    }
+                }
+            }
+            assert_eq!(map.len(), model.len());
+            assert_eq!(map.first_key_value(), model.iter().next());
+            assert_eq!(map.last_key_value(), model.iter().next_back());
+            if step % 256 == 0 {
+                assert!(map.iter().eq(model.iter()), "seed {} step {}", seed, step);
+                assert_eq!(map.tree().check_invariants(), Ok(()));
+            }
+        }
+        assert!(map.iter().eq(model.iter()));
+    }
+}
+
+#[test]
+fn tree_matches_linked_list() {
+    for seed in 1..=4 {
+        let mut rng = Rng(seed);
+        let mut tree: Tree<usize> = Tree::new();
+        let mut model = LinkedList::new();
+        for step in 0..iterations() {
+            // Inserts are weighted so that the sequence grows into a deep tree
+            match rng.below(8) {
+                0 => {
+                    tree.insert_at(0, step);
+                    model.push_front(step);
+                }
+                1 | 2 => {
+                    tree.insert_at(tree.len(), step);
+                    model.push_back(step);
+                }
+                3 | 4 => {
+                    let index = rng.below(model.len() + 1);
+                    tree.insert_at(index, step);
+                    let mut back = model.split_off(index);
+                    model.push_back(step);
+                    model.append(&mut back);
+                }
+                5 => assert_eq!(tree.pop_first(), model.pop_front()),
+                6 => assert_eq!(tree.pop_last(), model.pop_back()),
+                _ => {
+                    if !model.is_empty() {
+                        let index = rng.below(model.len());
+                        let mut back = model.split_off(index);
+                        assert_eq!(tree.remove_at(index), back.pop_front());
+                        model.append(&mut back);
+                    }
+                }
+            }
+            assert_eq!(tree.len(), model.len());
+            assert_eq!(
+                tree.get_leftmost_node().map(|node| tree.get_contents(node)),
+                model.front()
+            );
+            if step % 256 == 0 {
+                assert!(
+                    tree.to_vec().iter().eq(model.iter()),
+                    "seed {} step {}",
+                    seed,
+                    step
+                );
+                assert_eq!(tree.check_invariants(), Ok(()));
+            }
+        }
+        assert!(tree.to_vec().iter().eq(model.iter()));
+    }
+}